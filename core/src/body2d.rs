@@ -1,29 +1,60 @@
+use super::scalar::Scalar;
 use super::vec2d::Vec2D;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
 use schemars::JsonSchema;
 
 /// A body is an object that has both position and velocity; e.g., a ball in Breakout.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-pub struct Body2D {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Body2D<S = f64> {
     /// Where this object is located in two dimensions.
-    pub position: Vec2D,
+    pub position: Vec2D<S>,
     /// How this object is moving in two dimensions.
-    pub velocity: Vec2D,
+    pub velocity: Vec2D<S>,
 }
 
-impl Body2D {
+// The schema of a Body2D of floats; see Vec2DSchema.
+/// A body is an object that has both position and velocity; e.g., a ball in Breakout.
+#[derive(JsonSchema)]
+#[schemars(rename = "Body2D")]
+#[allow(dead_code)]
+struct Body2DSchema {
+    /// Where this object is located in two dimensions.
+    position: Vec2D,
+    /// How this object is moving in two dimensions.
+    velocity: Vec2D,
+}
+
+impl JsonSchema for Body2D {
+    fn schema_name() -> String {
+        Body2DSchema::schema_name()
+    }
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        Body2DSchema::json_schema(gen)
+    }
+}
+
+impl<S: Scalar> Body2D<S> {
     /// Create a new body from a position; no velocity.
-    pub fn new_pos(x: f64, y: f64) -> Body2D {
-        Body2D::new_detailed(x, y, 0.0, 0.0)
+    pub fn new_pos(x: S, y: S) -> Body2D<S> {
+        Body2D::new_detailed(x, y, S::ZERO, S::ZERO)
     }
     /// Create a body with both a position and a velocity.
-    pub fn new_detailed(x: f64, y: f64, vx: f64, vy: f64) -> Body2D {
+    pub fn new_detailed(x: S, y: S, vx: S, vy: S) -> Body2D<S> {
         Body2D {
             position: Vec2D::new(x, y),
             velocity: Vec2D::new(vx, vy),
         }
     }
     /// Update the position of this body based on a time step and its velocity.
-    pub fn integrate_mut(&mut self, time_step: f64) {
+    pub fn integrate_mut(&mut self, time_step: S) {
         self.position += self.velocity.scale(time_step);
     }
+    /// This body in another kind of number; see ``Vec2D::cast``.
+    pub fn cast<T: Scalar>(&self) -> Body2D<T> {
+        Body2D {
+            position: self.position.cast(),
+            velocity: self.velocity.cast(),
+        }
+    }
 }
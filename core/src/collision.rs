@@ -1,5 +1,8 @@
-use super::graphics::Color;
+use super::graphics::{Color, FixedSpriteData};
+use super::scalar::Scalar;
+use super::vec2d::Vec2D;
 use std::cmp;
+use std::collections::HashMap;
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Rect {
@@ -54,6 +57,39 @@ impl Rect {
         false
     }
 
+    /// The rectangle two rectangles share, if they overlap by at least one pixel.
+    pub fn intersection(&self, rhs: &Rect) -> Option<Rect> {
+        let x1 = cmp::max(self.x1(), rhs.x1());
+        let x2 = cmp::min(self.x2(), rhs.x2());
+        let y1 = cmp::max(self.y1(), rhs.y1());
+        let y2 = cmp::min(self.y2(), rhs.y2());
+        if x1 < x2 && y1 < y2 {
+            Some(Rect::new(x1, y1, x2 - x1, y2 - y1))
+        } else {
+            None
+        }
+    }
+
+    /// How many pixels two rectangles share.
+    pub fn intersection_area(&self, rhs: &Rect) -> i32 {
+        self.intersection(rhs).map(|r| r.w * r.h).unwrap_or(0)
+    }
+
+    /// The shortest move (dx, dy), along one axis, that stops this rectangle sharing pixels with an obstacle; None if they share none.
+    pub fn resolve_overlap(&self, obstacle: &Rect) -> Option<(i32, i32)> {
+        self.intersection(obstacle)?;
+        let moves = [
+            (obstacle.x1() - self.x2(), 0),
+            (obstacle.x2() - self.x1(), 0),
+            (0, obstacle.y1() - self.y2()),
+            (0, obstacle.y2() - self.y1()),
+        ];
+        moves
+            .iter()
+            .cloned()
+            .min_by_key(|(dx, dy)| dx.abs() + dy.abs())
+    }
+
     /// Create a rectangle that surrounds a slice of rectangles.
     pub fn merge(rectangles: &[Rect]) -> Option<Rect> {
         if rectangles.len() == 0 {
@@ -83,6 +119,480 @@ fn rect_intersect(r1: &Rect, r2: &Rect) -> bool {
     r1.x <= r2.x + r2.w && r1.x + r1.w >= r2.x && r1.y <= r2.y + r2.h && r1.y + r1.h >= r2.y
 }
 
+/// A broad phase for collision checks: a uniform grid of square cells, each listing the items whose rectangles touch it.
+/// Games fill it each frame, then ask which items might touch a rectangle without looking at every item.
+#[derive(Clone, Debug)]
+pub struct SpatialGrid<T> {
+    cell_size: i32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    items: Vec<(Rect, T)>,
+}
+
+impl<T> SpatialGrid<T> {
+    /// An empty grid; ``cell_size`` should be about the size of the items going in.
+    pub fn new(cell_size: i32) -> SpatialGrid<T> {
+        assert!(cell_size > 0, "SpatialGrid cell_size must be positive.");
+        SpatialGrid {
+            cell_size,
+            cells: HashMap::new(),
+            items: Vec::new(),
+        }
+    }
+    /// Remove everything, keeping the cell size.
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.items.clear();
+    }
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+    /// Add an item covering a rectangle.
+    pub fn insert(&mut self, rect: Rect, item: T) {
+        let index = self.items.len();
+        for cell in self.cells_under(&rect) {
+            self.cells.entry(cell).or_default().push(index);
+        }
+        self.items.push((rect, item));
+    }
+    /// Every item whose rectangle intersects ``rect`` (in the sense of `Rect::intersects`), once each, in the order they were inserted.
+    pub fn query(&self, rect: &Rect) -> Vec<&T> {
+        let mut found: Vec<usize> = self
+            .cells_under(rect)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .cloned()
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+            .into_iter()
+            .map(|index| &self.items[index])
+            .filter(|(item_rect, _)| item_rect.intersects(rect))
+            .map(|(_, item)| item)
+            .collect()
+    }
+    /// Cells touched by a rectangle, including its right and bottom edges as `Rect::intersects` does.
+    fn cells_under(&self, rect: &Rect) -> impl Iterator<Item = (i32, i32)> {
+        let size = self.cell_size;
+        let (x1, x2) = (rect.x1().div_euclid(size), rect.x2().div_euclid(size));
+        let (y1, y2) = (rect.y1().div_euclid(size), rect.y2().div_euclid(size));
+        (x1..=x2).flat_map(move |x| (y1..=y2).map(move |y| (x, y)))
+    }
+}
+
+/// An axis-aligned box in world coordinates, for the swept collision tests below.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Aabb<S = f64> {
+    /// The upper-left corner of the box.
+    pub position: Vec2D<S>,
+    /// The width and height of the box.
+    pub size: Vec2D<S>,
+}
+
+impl<S: Scalar> Aabb<S> {
+    pub fn new(x: S, y: S, w: S, h: S) -> Aabb<S> {
+        Aabb {
+            position: Vec2D::new(x, y),
+            size: Vec2D::new(w, h),
+        }
+    }
+    pub fn min(&self) -> Vec2D<S> {
+        self.position.clone()
+    }
+    pub fn max(&self) -> Vec2D<S> {
+        self.position.translate(&self.size)
+    }
+    /// Grow this box by the same amount on every side.
+    pub fn expand(&self, by: S) -> Aabb<S> {
+        let two = S::from_f64(2.0);
+        Aabb::new(
+            self.position.x - by,
+            self.position.y - by,
+            self.size.x + two * by,
+            self.size.y + two * by,
+        )
+    }
+    /// Do these boxes overlap? Boxes that only touch along an edge do not.
+    pub fn intersects(&self, rhs: &Aabb<S>) -> bool {
+        let (a0, a1) = (self.min(), self.max());
+        let (b0, b1) = (rhs.min(), rhs.max());
+        a0.x < b1.x && a1.x > b0.x && a0.y < b1.y && a1.y > b0.y
+    }
+    /// The shortest move, along one axis, that stops this box overlapping an obstacle; None if they do not overlap.
+    pub fn resolve_overlap(&self, obstacle: &Aabb<S>) -> Option<Vec2D<S>> {
+        if !self.intersects(obstacle) {
+            return None;
+        }
+        let (a0, a1) = (self.min(), self.max());
+        let (b0, b1) = (obstacle.min(), obstacle.max());
+        let moves = [
+            Vec2D::new(b0.x - a1.x, S::ZERO),
+            Vec2D::new(b1.x - a0.x, S::ZERO),
+            Vec2D::new(S::ZERO, b0.y - a1.y),
+            Vec2D::new(S::ZERO, b1.y - a0.y),
+        ];
+        moves
+            .iter()
+            .min_by(|l, r| {
+                l.magnitude_squared()
+                    .partial_cmp(&r.magnitude_squared())
+                    .unwrap_or(cmp::Ordering::Equal)
+            })
+            .cloned()
+    }
+    /// This box in another kind of number; see `Vec2D::cast`.
+    pub fn cast<T: Scalar>(&self) -> Aabb<T> {
+        Aabb {
+            position: self.position.cast(),
+            size: self.size.cast(),
+        }
+    }
+    /// The point in or on this box nearest to another point.
+    pub fn closest_point(&self, point: &Vec2D<S>) -> Vec2D<S> {
+        let (min, max) = (self.min(), self.max());
+        Vec2D::new(point.x.clamp(min.x, max.x), point.y.clamp(min.y, max.y))
+    }
+}
+
+impl From<&Rect> for Aabb {
+    fn from(rect: &Rect) -> Aabb {
+        Aabb::new(rect.x.into(), rect.y.into(), rect.w.into(), rect.h.into())
+    }
+}
+
+/// A circle in world coordinates.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Circle {
+    pub center: Vec2D,
+    pub radius: f64,
+}
+
+impl Circle {
+    pub fn new(x: f64, y: f64, radius: f64) -> Circle {
+        Circle {
+            center: Vec2D::new(x, y),
+            radius,
+        }
+    }
+    /// Is this point inside or on the circle?
+    pub fn contains(&self, point: &Vec2D) -> bool {
+        (point.clone() - self.center.clone()).magnitude_squared() <= self.radius * self.radius
+    }
+    /// Do these circles overlap? Circles that only touch do not.
+    pub fn intersects(&self, rhs: &Circle) -> bool {
+        let reach = self.radius + rhs.radius;
+        (rhs.center.clone() - self.center.clone()).magnitude_squared() < reach * reach
+    }
+    /// Does this circle overlap a box? Touching does not count.
+    pub fn intersects_aabb(&self, rhs: &Aabb) -> bool {
+        let closest = rhs.closest_point(&self.center);
+        (self.center.clone() - closest).magnitude_squared() < self.radius * self.radius
+    }
+    /// The smallest box around this circle.
+    pub fn bounding_box(&self) -> Aabb {
+        Aabb::new(
+            self.center.x - self.radius,
+            self.center.y - self.radius,
+            2.0 * self.radius,
+            2.0 * self.radius,
+        )
+    }
+    /// The shortest move that stops this circle overlapping another; None if they do not overlap.
+    /// Circles with the same center are pushed up.
+    pub fn resolve_overlap(&self, obstacle: &Circle) -> Option<Vec2D> {
+        if !self.intersects(obstacle) {
+            return None;
+        }
+        let apart = self.center.clone() - obstacle.center.clone();
+        let distance = apart.magnitude();
+        let direction = if distance == 0.0 {
+            Vec2D::new(0.0, -1.0)
+        } else {
+            apart.scale(1.0 / distance)
+        };
+        Some(direction * (self.radius + obstacle.radius - distance))
+    }
+}
+
+/// Which pixels of a sprite are visible, worked out once so that collision checks do not look at colors.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PixelMask {
+    width: i32,
+    height: i32,
+    visible: Vec<bool>,
+}
+
+impl PixelMask {
+    pub fn from_sprite(sprite: &FixedSpriteData) -> PixelMask {
+        PixelMask::from_colors(&sprite.data)
+    }
+    pub fn from_colors(data: &[Vec<Color>]) -> PixelMask {
+        let height = data.len() as i32;
+        let width = data.iter().map(|row| row.len()).max().unwrap_or(0) as i32;
+        let mut visible = vec![false; (width * height) as usize];
+        for (y, row) in data.iter().enumerate() {
+            for (x, color) in row.iter().enumerate() {
+                visible[y * width as usize + x] = color.is_visible();
+            }
+        }
+        PixelMask {
+            width,
+            height,
+            visible,
+        }
+    }
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+    /// Is the pixel at (x, y), relative to the upper-left of the sprite, visible? Pixels outside the sprite are not.
+    pub fn is_visible(&self, x: i32, y: i32) -> bool {
+        x >= 0
+            && y >= 0
+            && x < self.width
+            && y < self.height
+            && self.visible[(y * self.width + x) as usize]
+    }
+    /// How many pixels are visible.
+    pub fn count_visible(&self) -> usize {
+        self.visible.iter().filter(|v| **v).count()
+    }
+    /// The rectangle covered by this sprite when drawn at (x, y).
+    pub fn bounds(&self, x: i32, y: i32) -> Rect {
+        Rect::new(x, y, self.width, self.height)
+    }
+    /// Does this sprite, drawn at (x, y), have a visible pixel inside the rectangle? Same answer as `Rect::collides_visible`.
+    pub fn collides_rect(&self, x: i32, y: i32, rect: &Rect) -> bool {
+        let x1 = cmp::max(x, rect.x1());
+        let x2 = cmp::min(x + self.width - 1, rect.x2());
+        let y1 = cmp::max(y, rect.y1());
+        let y2 = cmp::min(y + self.height - 1, rect.y2());
+        (y1..=y2).any(|py| (x1..=x2).any(|px| self.is_visible(px - x, py - y)))
+    }
+    /// Do two sprites, drawn at (x, y) and (ox, oy), have a visible pixel in the same place?
+    pub fn collides_mask(&self, x: i32, y: i32, other: &PixelMask, ox: i32, oy: i32) -> bool {
+        let overlap = match self.bounds(x, y).intersection(&other.bounds(ox, oy)) {
+            Some(overlap) => overlap,
+            None => return false,
+        };
+        (overlap.y1()..overlap.y2()).any(|py| {
+            (overlap.x1()..overlap.x2())
+                .any(|px| self.is_visible(px - x, py - y) && other.is_visible(px - ox, py - oy))
+        })
+    }
+}
+
+impl From<&FixedSpriteData> for PixelMask {
+    fn from(sprite: &FixedSpriteData) -> PixelMask {
+        PixelMask::from_sprite(sprite)
+    }
+}
+
+/// When and where a moving shape first touches another; see `sweep_aabb` and `sweep_circle_aabb`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Contact<S = f64> {
+    /// How much of the motion happens before the shapes touch, from 0.0 to 1.0.
+    pub time: S,
+    /// The unit normal of the surface that was hit, pointing back toward the moving shape.
+    pub normal: Vec2D<S>,
+}
+
+/// Sweep a box along `motion` against a box that stands still, returning the first contact if there is one.
+/// If both boxes move, pass the difference of their motions.
+/// Boxes that already overlap touch at time 0, unless the motion is already separating them.
+pub fn sweep_aabb<S: Scalar>(
+    moving: &Aabb<S>,
+    motion: &Vec2D<S>,
+    target: &Aabb<S>,
+) -> Option<Contact<S>> {
+    if moving.intersects(target) {
+        return overlap_contact(moving, motion, target);
+    }
+    let grown = Aabb::new(
+        target.position.x - moving.size.x,
+        target.position.y - moving.size.y,
+        target.size.x + moving.size.x,
+        target.size.y + moving.size.y,
+    );
+    sweep_point(&moving.position, motion, &grown)
+}
+
+/// Sweep a circle along `motion` against a box that stands still, returning the first contact if there is one.
+/// Circles that already overlap the box touch at time 0, unless the motion is already separating them.
+pub fn sweep_circle_aabb<S: Scalar>(
+    center: &Vec2D<S>,
+    radius: S,
+    motion: &Vec2D<S>,
+    target: &Aabb<S>,
+) -> Option<Contact<S>> {
+    let closest = target.closest_point(center);
+    let offset = center.clone() - closest;
+    let distance = offset.magnitude();
+    if distance < radius {
+        if distance == S::ZERO {
+            // The center is inside the box; push out of the nearest side.
+            let point = Aabb::new(center.x, center.y, S::ZERO, S::ZERO);
+            return overlap_contact(&point, motion, target);
+        }
+        let normal = offset.scale(S::ONE / distance);
+        return separating_filter(
+            Contact {
+                time: S::ZERO,
+                normal,
+            },
+            motion,
+        );
+    }
+
+    // The circle touches the box when its center enters the box grown by the radius with rounded corners.
+    let hit = sweep_point(center, motion, &target.expand(radius))?;
+    let at = center.translate(&motion.scale(hit.time));
+    let (min, max) = (target.min(), target.max());
+    let corner_x = if at.x < min.x {
+        Some(min.x)
+    } else if at.x > max.x {
+        Some(max.x)
+    } else {
+        None
+    };
+    let corner_y = if at.y < min.y {
+        Some(min.y)
+    } else if at.y > max.y {
+        Some(max.y)
+    } else {
+        None
+    };
+    match (corner_x, corner_y) {
+        (Some(x), Some(y)) => sweep_point_circle(center, motion, &Vec2D::new(x, y), radius),
+        _ => Some(hit),
+    }
+}
+
+/// Slab test for a point moving along `motion` into a box; None if it misses, only grazes, or starts inside.
+fn sweep_point<S: Scalar>(
+    origin: &Vec2D<S>,
+    motion: &Vec2D<S>,
+    target: &Aabb<S>,
+) -> Option<Contact<S>> {
+    let (min, max) = (target.min(), target.max());
+    let axes = [
+        (
+            origin.x,
+            motion.x,
+            min.x,
+            max.x,
+            Vec2D::new(S::ONE, S::ZERO),
+        ),
+        (
+            origin.y,
+            motion.y,
+            min.y,
+            max.y,
+            Vec2D::new(S::ZERO, S::ONE),
+        ),
+    ];
+    // None stands for minus and plus infinity: an axis the point does not move along never bounds the motion.
+    let mut enter: Option<S> = None;
+    let mut exit: Option<S> = None;
+    let mut normal = Vec2D::origin();
+    for (start, delta, low, high, axis) in axes.iter() {
+        if *delta == S::ZERO {
+            if *start <= *low || *start >= *high {
+                return None;
+            }
+            continue;
+        }
+        let t1 = (*low - *start) / *delta;
+        let t2 = (*high - *start) / *delta;
+        let (near, far) = if t1 < t2 { (t1, t2) } else { (t2, t1) };
+        let closer = match enter {
+            Some(enter) => near > enter,
+            None => true,
+        };
+        if closer {
+            enter = Some(near);
+            normal = axis.scale(-delta.signum());
+        }
+        exit = Some(exit.map_or(far, |exit| exit.min(far)));
+    }
+    let enter = enter?;
+    if !(S::ZERO..=S::ONE).contains(&enter) || matches!(exit, Some(exit) if enter >= exit) {
+        return None;
+    }
+    Some(Contact {
+        time: enter,
+        normal,
+    })
+}
+
+/// First time a point moving along `motion` comes within `radius` of `center`.
+fn sweep_point_circle<S: Scalar>(
+    origin: &Vec2D<S>,
+    motion: &Vec2D<S>,
+    center: &Vec2D<S>,
+    radius: S,
+) -> Option<Contact<S>> {
+    let (two, four) = (S::from_f64(2.0), S::from_f64(4.0));
+    let offset = origin.clone() - center.clone();
+    let a = motion.magnitude_squared();
+    let b = two * (motion.x * offset.x + motion.y * offset.y);
+    let c = offset.magnitude_squared() - radius * radius;
+    let discriminant = b * b - four * a * c;
+    if a == S::ZERO || discriminant <= S::ZERO {
+        return None;
+    }
+    let time = (-b - discriminant.sqrt()) / (two * a);
+    if !(S::ZERO..=S::ONE).contains(&time) {
+        return None;
+    }
+    let at = origin.translate(&motion.scale(time));
+    Some(Contact {
+        time,
+        normal: Vec2D::new((at.x - center.x) / radius, (at.y - center.y) / radius),
+    })
+}
+
+/// Overlapping boxes touch at time 0 on the side with the least penetration.
+fn overlap_contact<S: Scalar>(
+    moving: &Aabb<S>,
+    motion: &Vec2D<S>,
+    target: &Aabb<S>,
+) -> Option<Contact<S>> {
+    let (a0, a1) = (moving.min(), moving.max());
+    let (b0, b1) = (target.min(), target.max());
+    let sides = [
+        (a1.x - b0.x, Vec2D::new(-S::ONE, S::ZERO)),
+        (b1.x - a0.x, Vec2D::new(S::ONE, S::ZERO)),
+        (a1.y - b0.y, Vec2D::new(S::ZERO, -S::ONE)),
+        (b1.y - a0.y, Vec2D::new(S::ZERO, S::ONE)),
+    ];
+    let (_, normal) = sides
+        .iter()
+        .min_by(|l, r| l.0.partial_cmp(&r.0).unwrap_or(cmp::Ordering::Equal))
+        .cloned()
+        .unwrap();
+    separating_filter(
+        Contact {
+            time: S::ZERO,
+            normal,
+        },
+        motion,
+    )
+}
+
+fn separating_filter<S: Scalar>(contact: Contact<S>, motion: &Vec2D<S>) -> Option<Contact<S>> {
+    if motion.dot(&contact.normal) < S::ZERO {
+        Some(contact)
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -93,4 +603,255 @@ mod tests {
         let r2 = Rect::new(3, 3, 1, 1);
         assert_eq!(false, r1.intersects(&r2));
     }
+
+    fn close(expected: f64, actual: f64) -> bool {
+        (expected - actual).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_sweep_aabb_face() {
+        let wall = Aabb::new(10.0, 0.0, 2.0, 10.0);
+        let ball = Aabb::new(0.0, 4.0, 2.0, 2.0);
+        let hit = sweep_aabb(&ball, &Vec2D::new(16.0, 0.0), &wall).unwrap();
+        assert!(close(0.5, hit.time));
+        assert_eq!(Vec2D::new(-1.0, 0.0), hit.normal);
+
+        // Too short, wrong way, or passing above.
+        assert_eq!(None, sweep_aabb(&ball, &Vec2D::new(7.0, 0.0), &wall));
+        assert_eq!(None, sweep_aabb(&ball, &Vec2D::new(-16.0, 0.0), &wall));
+        assert_eq!(None, sweep_aabb(&ball, &Vec2D::new(16.0, -16.0), &wall));
+    }
+
+    #[test]
+    fn test_sweep_aabb_fast_no_tunneling() {
+        let paddle = Aabb::new(100.0, 0.0, 1.0, 16.0);
+        let ball = Aabb::new(0.0, 6.0, 2.0, 4.0);
+        let hit = sweep_aabb(&ball, &Vec2D::new(1000.0, 0.0), &paddle).unwrap();
+        assert!(close(0.098, hit.time));
+        assert!(!ball.intersects(&paddle));
+    }
+
+    #[test]
+    fn test_sweep_aabb_overlap() {
+        let wall = Aabb::new(0.0, 0.0, 10.0, 10.0);
+        let inside = Aabb::new(8.0, 4.0, 4.0, 2.0);
+        let hit = sweep_aabb(&inside, &Vec2D::new(-1.0, 0.0), &wall).unwrap();
+        assert_eq!(0.0, hit.time);
+        assert_eq!(Vec2D::new(1.0, 0.0), hit.normal);
+        // Already leaving.
+        assert_eq!(None, sweep_aabb(&inside, &Vec2D::new(1.0, 0.0), &wall));
+    }
+
+    #[test]
+    fn test_sweep_circle_face_and_corner() {
+        let brick = Aabb::new(0.0, 0.0, 10.0, 4.0);
+        let hit =
+            sweep_circle_aabb(&Vec2D::new(5.0, 10.0), 2.0, &Vec2D::new(0.0, -8.0), &brick).unwrap();
+        assert!(close(0.5, hit.time));
+        assert_eq!(Vec2D::new(0.0, 1.0), hit.normal);
+
+        // Diagonally into the lower-right corner.
+        let hit = sweep_circle_aabb(&Vec2D::new(13.0, 7.0), 2.0, &Vec2D::new(-3.0, -3.0), &brick)
+            .unwrap();
+        let root = 0.5f64.sqrt();
+        assert!(close(1.0 - 2.0 * root / 3.0, hit.time));
+        assert!(close(root, hit.normal.x) && close(root, hit.normal.y));
+
+        // Clips the grown box's corner but not the rounded circle.
+        let (center, motion) = (Vec2D::new(13.0, 4.6), Vec2D::new(-2.0, 2.0));
+        assert!(sweep_point(&center, &motion, &brick.expand(2.0)).is_some());
+        assert_eq!(None, sweep_circle_aabb(&center, 2.0, &motion, &brick));
+    }
+
+    #[test]
+    fn test_sweep_circle_overlap() {
+        let brick = Aabb::new(0.0, 0.0, 10.0, 4.0);
+        let hit =
+            sweep_circle_aabb(&Vec2D::new(5.0, 5.0), 2.0, &Vec2D::new(0.0, -1.0), &brick).unwrap();
+        assert_eq!(0.0, hit.time);
+        assert_eq!(Vec2D::new(0.0, 1.0), hit.normal);
+        assert_eq!(
+            None,
+            sweep_circle_aabb(&Vec2D::new(5.0, 5.0), 2.0, &Vec2D::new(0.0, 1.0), &brick)
+        );
+    }
+
+    #[test]
+    fn test_sweep_fixed() {
+        use crate::fixed::Fixed;
+        let brick: Aabb<Fixed> = Aabb::new(0.0, 0.0, 10.0, 4.0).cast();
+        let hit = sweep_circle_aabb(
+            &Vec2D::new(5.0, 10.0).cast(),
+            Fixed::from_int(2),
+            &Vec2D::new(0.0, -8.0).cast(),
+            &brick,
+        )
+        .unwrap();
+        assert_eq!(Fixed::from_f64(0.5), hit.time);
+        assert_eq!(Vec2D::new(0.0, 1.0).cast::<Fixed>(), hit.normal);
+
+        let wall = Aabb::new(10.0, 0.0, 2.0, 10.0).cast::<Fixed>();
+        let ball = Aabb::new(0.0, 4.0, 2.0, 2.0).cast::<Fixed>();
+        let hit = sweep_aabb(&ball, &Vec2D::new(16.0, 0.0).cast(), &wall).unwrap();
+        assert_eq!(Fixed::from_f64(0.5), hit.time);
+        assert_eq!(Vec2D::new(-1.0, 0.0).cast::<Fixed>(), hit.normal);
+        assert_eq!(None, sweep_aabb(&ball, &Vec2D::new(7.0, 0.0).cast(), &wall));
+    }
+
+    #[test]
+    fn test_rect_intersection() {
+        let r1 = Rect::new(0, 0, 10, 10);
+        let r2 = Rect::new(8, 5, 10, 10);
+        assert_eq!(Some(Rect::new(8, 5, 2, 5)), r1.intersection(&r2));
+        assert_eq!(10, r1.intersection_area(&r2));
+        assert_eq!(Some((2, 0)), r2.resolve_overlap(&r1));
+        // Touching edges share no pixels.
+        assert_eq!(0, r1.intersection_area(&Rect::new(10, 0, 5, 5)));
+        assert_eq!(None, r1.resolve_overlap(&Rect::new(10, 0, 5, 5)));
+    }
+
+    #[test]
+    fn test_circles() {
+        let c = Circle::new(0.0, 0.0, 2.0);
+        assert!(c.contains(&Vec2D::new(0.0, 2.0)));
+        assert!(c.intersects(&Circle::new(3.0, 0.0, 1.5)));
+        assert!(!c.intersects(&Circle::new(3.0, 0.0, 1.0)));
+        assert!(c.intersects_aabb(&Aabb::new(1.0, 1.0, 5.0, 5.0)));
+        assert!(!c.intersects_aabb(&Aabb::new(1.5, 1.5, 5.0, 5.0)));
+        assert_eq!(Aabb::new(-2.0, -2.0, 4.0, 4.0), c.bounding_box());
+        assert_eq!(
+            Some(Vec2D::new(-0.5, 0.0)),
+            c.resolve_overlap(&Circle::new(3.0, 0.0, 1.5))
+        );
+    }
+
+    #[test]
+    fn test_pixel_mask() {
+        let (x, o) = (Color::white(), Color::invisible());
+        let sprite = FixedSpriteData::new(vec![vec![x, o, o], vec![o, o, x]]);
+        let mask = PixelMask::from(&sprite);
+        assert_eq!((3, 2), (mask.width(), mask.height()));
+        assert_eq!(2, mask.count_visible());
+        assert!(mask.is_visible(2, 1));
+        assert!(!mask.is_visible(3, 1));
+        assert!(mask.collides_rect(10, 10, &Rect::new(12, 11, 0, 0)));
+        assert!(!mask.collides_rect(10, 10, &Rect::new(11, 10, 0, 1)));
+        assert!(mask.collides_mask(0, 0, &mask, 2, 1));
+        assert!(!mask.collides_mask(0, 0, &mask, 1, 0));
+    }
+
+    #[test]
+    fn test_spatial_grid() {
+        let mut grid = SpatialGrid::new(8);
+        grid.insert(Rect::new(0, 0, 4, 4), 'a');
+        grid.insert(Rect::new(6, 0, 20, 4), 'b');
+        grid.insert(Rect::new(-30, -30, 4, 4), 'c');
+        assert_eq!(3, grid.len());
+        assert_eq!(vec![&'a', &'b'], grid.query(&Rect::new(3, 3, 4, 4)));
+        assert_eq!(vec![&'b'], grid.query(&Rect::new(20, 2, 0, 0)));
+        assert_eq!(vec![&'c'], grid.query(&Rect::new(-27, -27, 1, 1)));
+        assert!(grid.query(&Rect::new(100, 100, 5, 5)).is_empty());
+        grid.clear();
+        assert!(grid.is_empty());
+        assert!(grid.query(&Rect::new(0, 0, 4, 4)).is_empty());
+    }
+
+    use proptest::prelude::*;
+
+    fn rect() -> impl Strategy<Value = Rect> {
+        (-20..20, -20..20, 0..15, 0..15).prop_map(|(x, y, w, h)| Rect::new(x, y, w, h))
+    }
+
+    fn sprite() -> impl Strategy<Value = Vec<Vec<Color>>> {
+        (1..6usize, 1..6usize).prop_flat_map(|(w, h)| {
+            let pixel = any::<bool>().prop_map(|on| {
+                if on {
+                    Color::white()
+                } else {
+                    Color::invisible()
+                }
+            });
+            proptest::collection::vec(proptest::collection::vec(pixel, w), h)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_intersection_area_counts_pixels(a in rect(), b in rect()) {
+            let inside = |r: &Rect, x: i32, y: i32| x >= r.x1() && x < r.x2() && y >= r.y1() && y < r.y2();
+            let shared = (-20..40)
+                .flat_map(|x| (-20..40).map(move |y| (x, y)))
+                .filter(|(x, y)| inside(&a, *x, *y) && inside(&b, *x, *y))
+                .count();
+            prop_assert_eq!(shared as i32, a.intersection_area(&b));
+            prop_assert_eq!(a.intersection(&b), b.intersection(&a));
+        }
+
+        #[test]
+        fn prop_spatial_grid_matches_brute_force(
+            rects in proptest::collection::vec(rect(), 0..40), query in rect(), cell_size in 1..12,
+        ) {
+            let mut grid = SpatialGrid::new(cell_size);
+            for (i, r) in rects.iter().enumerate() {
+                grid.insert(r.clone(), i);
+            }
+            let expected: Vec<usize> = (0..rects.len()).filter(|i| rects[*i].intersects(&query)).collect();
+            let found: Vec<usize> = grid.query(&query).into_iter().cloned().collect();
+            prop_assert_eq!(expected, found);
+        }
+
+        #[test]
+        fn prop_resolve_overlap_separates(a in rect(), b in rect()) {
+            match a.resolve_overlap(&b) {
+                None => prop_assert_eq!(0, a.intersection_area(&b)),
+                Some((dx, dy)) => {
+                    let moved = Rect::new(a.x + dx, a.y + dy, a.w, a.h);
+                    prop_assert_eq!(0, moved.intersection_area(&b));
+                    prop_assert!(dx == 0 || dy == 0);
+                    prop_assert!(dx.abs() <= a.w.max(b.w) && dy.abs() <= a.h.max(b.h));
+                }
+            }
+        }
+
+        #[test]
+        fn prop_aabb_resolve_overlap_separates(a in rect(), b in rect()) {
+            let (a, b) = (Aabb::from(&a), Aabb::from(&b));
+            if let Some(by) = a.resolve_overlap(&b) {
+                let moved = Aabb { position: a.position.clone() + by, size: a.size.clone() };
+                prop_assert!(!moved.intersects(&b));
+            } else {
+                prop_assert!(!a.intersects(&b));
+            }
+        }
+
+        #[test]
+        fn prop_circle_resolve_overlap_separates(
+            x in -10.0..10.0f64, y in -10.0..10.0f64, r in 0.1..5.0f64,
+            ox in -10.0..10.0f64, oy in -10.0..10.0f64, or in 0.1..5.0f64,
+        ) {
+            let (a, b) = (Circle::new(x, y, r), Circle::new(ox, oy, or));
+            prop_assert_eq!(a.intersects(&b), b.intersects(&a));
+            if let Some(by) = a.resolve_overlap(&b) {
+                let moved = a.center.clone() + by;
+                let gap = (moved - b.center.clone()).magnitude() - (r + or);
+                prop_assert!(gap.abs() < 1e-9);
+            }
+        }
+
+        #[test]
+        fn prop_pixel_mask_matches_collides_visible(
+            data in sprite(), x in -10..10, y in -10..10, r in rect(),
+        ) {
+            let mask = PixelMask::from_colors(&data);
+            prop_assert_eq!(r.collides_visible(x, y, &data), mask.collides_rect(x, y, &r));
+        }
+
+        #[test]
+        fn prop_pixel_masks_collide_symmetrically(
+            a in sprite(), b in sprite(), x in -6..6, y in -6..6,
+        ) {
+            let (a, b) = (PixelMask::from_colors(&a), PixelMask::from_colors(&b));
+            prop_assert_eq!(a.collides_mask(0, 0, &b, x, y), b.collides_mask(x, y, &a, 0, 0));
+        }
+    }
 }
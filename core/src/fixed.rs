@@ -0,0 +1,381 @@
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// A fixed-point number: an integer count of 1/256ths.
+/// All arithmetic, including square roots and trigonometry, is done on integers, so results are the same on every platform; see ``Scalar``.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed(i32);
+
+/// Trigonometry works on 1/65536ths internally, so rounding there does not show in the result.
+const TRIG_BITS: u32 = 16;
+/// Pi in 1/65536ths.
+const TRIG_PI: i64 = 205_887;
+/// atan(2^-i) in 1/65536ths, for each CORDIC step i.
+const CORDIC_ANGLES: [i64; TRIG_BITS as usize] = [
+    51472, 30386, 16055, 8150, 4091, 2047, 1024, 512, 256, 128, 64, 32, 16, 8, 4, 2,
+];
+/// The CORDIC gain to undo, 0.6072529 in 1/65536ths.
+const CORDIC_GAIN: i64 = 39797;
+
+impl Fixed {
+    /// How many bits of the integer are after the binary point.
+    pub const FRACTION_BITS: u32 = 8;
+    /// The number zero.
+    pub const ZERO: Fixed = Fixed(0);
+    /// The number one.
+    pub const ONE: Fixed = Fixed(1 << Fixed::FRACTION_BITS);
+    /// The nearest number to pi.
+    pub const PI: Fixed = Fixed(804);
+    const SCALE: f64 = (1 << Fixed::FRACTION_BITS) as f64;
+
+    /// Create a number from its raw representation, in units of 1/256.
+    pub fn from_raw(raw: i32) -> Fixed {
+        Fixed(raw)
+    }
+    /// The raw representation of this number, in units of 1/256.
+    pub fn raw(self) -> i32 {
+        self.0
+    }
+    /// Create a number from an integer.
+    pub fn from_int(value: i32) -> Fixed {
+        Fixed(value << Fixed::FRACTION_BITS)
+    }
+    /// The nearest fixed-point number to a float; out-of-range values saturate and NaN becomes zero.
+    pub fn from_f64(value: f64) -> Fixed {
+        Fixed((value * Fixed::SCALE).round() as i32)
+    }
+    /// Convert back to a float; this is always exact.
+    pub fn to_f64(self) -> f64 {
+        f64::from(self.0) / Fixed::SCALE
+    }
+
+    /// Narrow a raw result computed in 64 bits.
+    fn from_wide(raw: i64) -> Fixed {
+        debug_assert!(
+            raw >= i64::from(i32::MIN) && raw <= i64::from(i32::MAX),
+            "Fixed overflow: {}",
+            raw
+        );
+        Fixed(raw as i32)
+    }
+    /// The raw value scaled up by one more ``FRACTION_BITS``, so that dividing by another raw value gives a raw result.
+    fn widen(self) -> i64 {
+        i64::from(self.0) << Fixed::FRACTION_BITS
+    }
+    /// Divide, rounding half away from zero.
+    fn div_round(numerator: i64, denominator: i64) -> i64 {
+        let twice = numerator * 2 / denominator;
+        (twice + twice.signum()) / 2
+    }
+
+    pub fn abs(self) -> Fixed {
+        Fixed(self.0.abs())
+    }
+    /// One with the sign of this number; like ``f64::signum``, zero counts as positive.
+    pub fn signum(self) -> Fixed {
+        if self.0 < 0 {
+            -Fixed::ONE
+        } else {
+            Fixed::ONE
+        }
+    }
+    /// The largest whole number not above this one.
+    pub fn floor(self) -> Fixed {
+        Fixed((self.0 >> Fixed::FRACTION_BITS) << Fixed::FRACTION_BITS)
+    }
+    /// The smallest whole number not below this one.
+    pub fn ceil(self) -> Fixed {
+        -(-self).floor()
+    }
+    pub fn min(self, other: Fixed) -> Fixed {
+        Ord::min(self, other)
+    }
+    pub fn max(self, other: Fixed) -> Fixed {
+        Ord::max(self, other)
+    }
+    pub fn clamp(self, low: Fixed, high: Fixed) -> Fixed {
+        self.max(low).min(high)
+    }
+    /// The nearest number to the square root; negative numbers have a root of zero.
+    pub fn sqrt(self) -> Fixed {
+        if self.0 <= 0 {
+            return Fixed::ZERO;
+        }
+        // sqrt(raw / 256) * 256 = sqrt(raw * 256)
+        let n = (self.0 as u64) << Fixed::FRACTION_BITS;
+        let mut root = 0u64;
+        let mut bit = 1u64 << 62;
+        while bit > n {
+            bit >>= 2;
+        }
+        let mut rest = n;
+        while bit != 0 {
+            if rest >= root + bit {
+                rest -= root + bit;
+                root = (root >> 1) + bit;
+            } else {
+                root >>= 1;
+            }
+            bit >>= 2;
+        }
+        // root is rounded down; round to nearest instead.
+        if rest > root {
+            root += 1;
+        }
+        Fixed(root as i32)
+    }
+
+    /// The (sine, cosine) of an angle in radians.
+    pub fn sin_cos(self) -> (Fixed, Fixed) {
+        let mut angle = i64::from(self.0) << (TRIG_BITS - Fixed::FRACTION_BITS);
+        // Bring the angle into [-pi, pi), then into [-pi/2, pi/2] where CORDIC converges.
+        angle = (angle + TRIG_PI).rem_euclid(2 * TRIG_PI) - TRIG_PI;
+        let mut flip = false;
+        if angle > TRIG_PI / 2 {
+            angle -= TRIG_PI;
+            flip = true;
+        } else if angle < -TRIG_PI / 2 {
+            angle += TRIG_PI;
+            flip = true;
+        }
+        let (mut x, mut y) = (CORDIC_GAIN, 0);
+        for (i, step) in CORDIC_ANGLES.iter().enumerate() {
+            let (dx, dy) = (y >> i, x >> i);
+            if angle >= 0 {
+                x -= dx;
+                y += dy;
+                angle -= step;
+            } else {
+                x += dx;
+                y -= dy;
+                angle += step;
+            }
+        }
+        if flip {
+            x = -x;
+            y = -y;
+        }
+        (Fixed::from_trig(y), Fixed::from_trig(x))
+    }
+    pub fn sin(self) -> Fixed {
+        self.sin_cos().0
+    }
+    pub fn cos(self) -> Fixed {
+        self.sin_cos().1
+    }
+    /// The angle in radians, in [-pi, pi], from the positive x axis to the point (x, self); like ``f64::atan2``.
+    pub fn atan2(self, x: Fixed) -> Fixed {
+        let shift = TRIG_BITS - Fixed::FRACTION_BITS;
+        let (mut x, mut y) = (i64::from(x.0) << shift, i64::from(self.0) << shift);
+        if x == 0 && y == 0 {
+            return Fixed::ZERO;
+        }
+        // CORDIC only converges in the right half-plane; turn left-hand points half way round first.
+        let mut angle = 0;
+        if x < 0 {
+            angle = if y >= 0 { TRIG_PI } else { -TRIG_PI };
+            x = -x;
+            y = -y;
+        }
+        for (i, step) in CORDIC_ANGLES.iter().enumerate() {
+            let (dx, dy) = (y >> i, x >> i);
+            if y > 0 {
+                x += dx;
+                y -= dy;
+                angle += step;
+            } else {
+                x -= dx;
+                y += dy;
+                angle -= step;
+            }
+        }
+        Fixed::from_trig(angle)
+    }
+    pub fn to_radians(self) -> Fixed {
+        Fixed::from_wide(Fixed::div_round(
+            i64::from(self.0) * TRIG_PI,
+            180 << TRIG_BITS,
+        ))
+    }
+    pub fn to_degrees(self) -> Fixed {
+        Fixed::from_wide(Fixed::div_round(
+            i64::from(self.0) * (180 << TRIG_BITS),
+            TRIG_PI,
+        ))
+    }
+    /// Round a trigonometry result back to 1/256ths.
+    fn from_trig(raw: i64) -> Fixed {
+        let shift = TRIG_BITS - Fixed::FRACTION_BITS;
+        Fixed::from_wide((raw + (1 << (shift - 1))) >> shift)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+    fn add(self, other: Fixed) -> Fixed {
+        Fixed(self.0 + other.0)
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+    fn sub(self, other: Fixed) -> Fixed {
+        Fixed(self.0 - other.0)
+    }
+}
+
+/// Products are rounded to the nearest 1/256, halves up.
+impl Mul for Fixed {
+    type Output = Fixed;
+    fn mul(self, other: Fixed) -> Fixed {
+        let wide = i64::from(self.0) * i64::from(other.0);
+        Fixed::from_wide((wide + (1 << (Fixed::FRACTION_BITS - 1))) >> Fixed::FRACTION_BITS)
+    }
+}
+
+/// Quotients are rounded to the nearest 1/256, halves away from zero; like integers, dividing by zero panics.
+impl Div for Fixed {
+    type Output = Fixed;
+    fn div(self, other: Fixed) -> Fixed {
+        Fixed::from_wide(Fixed::div_round(self.widen(), i64::from(other.0)))
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+    fn neg(self) -> Fixed {
+        Fixed(-self.0)
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, other: Fixed) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Fixed {
+    fn sub_assign(&mut self, other: Fixed) {
+        *self = *self - other;
+    }
+}
+
+impl MulAssign for Fixed {
+    fn mul_assign(&mut self, other: Fixed) {
+        *self = *self * other;
+    }
+}
+
+impl DivAssign for Fixed {
+    fn div_assign(&mut self, other: Fixed) {
+        *self = *self / other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed(value: f64) -> Fixed {
+        Fixed::from_f64(value)
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(Fixed::ONE, Fixed::from_int(1));
+        assert_eq!(Fixed::from_raw(384), Fixed::from_f64(1.5));
+        assert_eq!(-2.25, Fixed::from_f64(-2.25).to_f64());
+        // Rounds to the nearest 1/256.
+        assert_eq!(Fixed::from_raw(26), Fixed::from_f64(0.1));
+        assert_eq!(Fixed::default(), Fixed::from_f64(f64::NAN));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(fixed(3.75), fixed(1.5) + fixed(2.25));
+        assert_eq!(fixed(-0.75), fixed(1.5) - fixed(2.25));
+        assert_eq!(fixed(-3.375), fixed(1.5) * fixed(-2.25));
+        // -2/3 is -170.67/256.
+        assert_eq!(Fixed::from_raw(-171), fixed(1.5) / fixed(-2.25));
+        // 1/3 is 85.33/256.
+        assert_eq!(Fixed::from_raw(85), Fixed::ONE / fixed(3.0));
+        assert_eq!(Fixed::from_raw(-85), -Fixed::ONE / fixed(3.0));
+        // Rounding errors grow when multiplied back up.
+        assert_eq!(Fixed::from_raw(510), (Fixed::ONE / fixed(3.0)) * fixed(6.0));
+
+        assert_eq!(fixed(-3.0), fixed(-2.5).floor());
+        assert_eq!(fixed(-2.0), fixed(-2.5).ceil());
+        assert_eq!(fixed(2.0), fixed(2.0).ceil());
+        assert_eq!(Fixed::ONE, Fixed::ZERO.signum());
+        assert_eq!(-Fixed::ONE, fixed(-0.5).signum());
+        assert_eq!(fixed(1.0), fixed(7.0).clamp(fixed(-1.0), fixed(1.0)));
+    }
+
+    #[test]
+    fn test_sqrt() {
+        assert_eq!(fixed(3.0), fixed(9.0).sqrt());
+        assert_eq!(fixed(0.5), fixed(0.25).sqrt());
+        // sqrt(2) is 362.04/256.
+        assert_eq!(Fixed::from_raw(362), fixed(2.0).sqrt());
+        assert_eq!(Fixed::ZERO, fixed(-4.0).sqrt());
+    }
+
+    #[test]
+    fn test_trig() {
+        assert_eq!((Fixed::ZERO, Fixed::ONE), Fixed::ZERO.sin_cos());
+        assert_eq!(Fixed::PI, fixed(180.0).to_radians());
+        // 804/256 is a little under pi.
+        assert_eq!(Fixed::from_raw(46066), Fixed::PI.to_degrees());
+        assert_eq!(Fixed::ZERO, Fixed::ZERO.atan2(Fixed::ZERO));
+        assert_eq!(Fixed::PI, Fixed::ZERO.atan2(-Fixed::ONE));
+        for degrees in (-720..=720).step_by(5) {
+            let radians = f64::from(degrees).to_radians();
+            let (sin, cos) = fixed(radians).sin_cos();
+            assert!((sin.to_f64() - radians.sin()).abs() < 0.01, "{}", degrees);
+            assert!((cos.to_f64() - radians.cos()).abs() < 0.01, "{}", degrees);
+        }
+        for &(y, x) in &[
+            (1.0, 2.0),
+            (-3.0, 0.5),
+            (0.25, -4.0),
+            (-2.0, -2.0),
+            (5.0, 0.0),
+        ] {
+            let angle = fixed(y).atan2(fixed(x)).to_f64();
+            assert!((angle - f64::atan2(y, x)).abs() < 0.01, "{} {}", y, x);
+        }
+    }
+
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn prop_round_trip(raw in -(1 << 20)..(1 << 20)) {
+            let x = Fixed::from_raw(raw);
+            prop_assert_eq!(x, Fixed::from_f64(x.to_f64()));
+        }
+
+        #[test]
+        fn prop_mul_div_round(a in -(1 << 16)..(1 << 16), b in -(1 << 16)..(1 << 16)) {
+            let (x, y) = (Fixed::from_raw(a), Fixed::from_raw(b));
+            // Both round to the nearest 1/256, so they are within half a step of the exact answer.
+            prop_assert!(((x * y).to_f64() - x.to_f64() * y.to_f64()).abs() <= 0.5 / 256.0);
+            prop_assume!(b != 0);
+            prop_assert!(((x / y).to_f64() - x.to_f64() / y.to_f64()).abs() <= 0.5 / 256.0);
+        }
+
+        #[test]
+        fn prop_sqrt(raw in 0..(1 << 24)) {
+            let x = Fixed::from_raw(raw);
+            prop_assert!((x.sqrt().to_f64() - x.to_f64().sqrt()).abs() <= 0.5 / 256.0);
+        }
+
+        #[test]
+        fn prop_polar_round_trip(degrees in -179..=179i32, length in 1..100i32) {
+            let angle = Fixed::from_int(degrees).to_radians();
+            let r = Fixed::from_int(length);
+            let (sin, cos) = angle.sin_cos();
+            let back = (r * sin).atan2(r * cos);
+            prop_assert!((back - angle).abs() <= Fixed::from_raw(2));
+        }
+    }
+}
@@ -1,8 +1,8 @@
 use png;
 use std::sync::Arc;
 
-/// For now we only support RGB colors so we don't have to do alpha-blending in our software renderer.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+/// An RGBA color; anything drawn with an alpha below 255 is blended over what was drawn before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub struct Color {
     pub r: u8,
     pub b: u8,
@@ -15,9 +15,18 @@ impl Color {
     pub fn rgb(r: u8, g: u8, b: u8) -> Color {
         Color { r, g, b, a: 255 }
     }
-    fn rgba(r: u8, g: u8, b: u8, a: u8) -> Color {
+    /// Create a color from (r, g, b, a) components; a=0 is invisible and a=255 is opaque.
+    pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> Color {
         Color { r, g, b, a }
     }
+    /// The same color with a different opacity.
+    pub fn with_alpha(self, a: u8) -> Color {
+        Color { a, ..self }
+    }
+    /// The opacity of this color; 0 is invisible and 255 is opaque.
+    pub fn alpha(self) -> u8 {
+        self.a
+    }
     pub fn invisible() -> Color {
         Color {
             r: 0,
@@ -29,6 +38,30 @@ impl Color {
     pub fn is_visible(self) -> bool {
         self.a > 0
     }
+    pub fn is_opaque(self) -> bool {
+        self.a == 255
+    }
+    /// Composite this color over `dest` ("source-over"); the result is opaque if either input is.
+    pub fn blend_over(self, dest: Color) -> Color {
+        // Weights are scaled by 255 * 255: the source counts by its alpha, the destination by
+        // whatever of its alpha the source lets through.
+        let src_weight = u32::from(self.a) * 255;
+        let dest_weight = u32::from(dest.a) * (255 - u32::from(self.a));
+        let out_weight = src_weight + dest_weight;
+        if out_weight == 0 {
+            return Color::invisible();
+        }
+        let channel = |src: u8, dest: u8| {
+            ((u32::from(src) * src_weight + u32::from(dest) * dest_weight + out_weight / 2)
+                / out_weight) as u8
+        };
+        Color {
+            r: channel(self.r, dest.r),
+            g: channel(self.g, dest.g),
+            b: channel(self.b, dest.b),
+            a: ((out_weight + 127) / 255) as u8,
+        }
+    }
     pub fn black() -> Color {
         Color::rgb(0, 0, 0)
     }
@@ -60,6 +93,13 @@ impl Color {
     }
 }
 
+/// Mix one 8-bit channel over an opaque one: `src * alpha + dest * (1 - alpha)`, rounded to nearest.
+#[inline(always)]
+fn blend_channel(src: u8, dest: u8, alpha: u8) -> u8 {
+    let alpha = u32::from(alpha);
+    ((u32::from(src) * alpha + u32::from(dest) * (255 - alpha) + 127) / 255) as u8
+}
+
 impl<'a> From<&'a (u8, u8, u8)> for Color {
     fn from(tuple: &'a (u8, u8, u8)) -> Color {
         Color::rgb(tuple.0, tuple.1, tuple.2)
@@ -147,15 +187,23 @@ impl FixedSpriteData {
 
     /// Given an include_bytes! png, convert it to a FixedSpriteData.
     pub fn load_png(data: &[u8]) -> FixedSpriteData {
+        FixedSpriteData::try_load_png(data).unwrap()
+    }
+
+    /// Like ``load_png``, but for data that may not be an 8-bit RGBA png, such as a user's file.
+    pub fn try_load_png(data: &[u8]) -> Result<FixedSpriteData, String> {
         let decoder = png::Decoder::new(data);
-        let (info, mut reader) = decoder.read_info().unwrap();
+        let (info, mut reader) = decoder.read_info().map_err(|e| e.to_string())?;
         let width = info.width as usize;
-        let _height = info.height as usize;
-        assert_eq!(info.color_type, png::ColorType::RGBA);
-        assert_eq!(info.bit_depth, png::BitDepth::Eight);
+        if info.color_type != png::ColorType::RGBA || info.bit_depth != png::BitDepth::Eight {
+            return Err(format!(
+                "Expected an 8-bit RGBA png, found {:?} at {:?}.",
+                info.color_type, info.bit_depth
+            ));
+        }
 
         let mut buf = vec![0; info.buffer_size()];
-        reader.next_frame(&mut buf).unwrap();
+        reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
 
         let mut output = Vec::new();
         for pix_row in buf.chunks(width * 4) {
@@ -166,7 +214,7 @@ impl FixedSpriteData {
             output.push(row);
         }
 
-        FixedSpriteData::new(output)
+        Ok(FixedSpriteData::new(output))
     }
 
     pub fn find_visible_color(&self) -> Option<Color> {
@@ -248,6 +296,32 @@ pub enum Drawable {
         y: i32,
         data: FixedSpriteData,
     },
+    /// A line segment with round ends, in pixel coordinates (pixel (0,0) covers [0,1)x[0,1)).
+    Line {
+        color: Color,
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        thickness: f64,
+        anti_alias: bool,
+    },
+    /// An axis-aligned ellipse; circles have rx == ry. Outlines are one pixel wide.
+    Ellipse {
+        color: Color,
+        cx: f64,
+        cy: f64,
+        rx: f64,
+        ry: f64,
+        filled: bool,
+        anti_alias: bool,
+    },
+    /// A filled polygon; self-intersections are resolved with the even-odd rule.
+    Polygon {
+        color: Color,
+        points: Vec<(f64, f64)>,
+        anti_alias: bool,
+    },
 }
 
 impl Drawable {
@@ -257,8 +331,306 @@ impl Drawable {
     pub fn sprite(x: i32, y: i32, sprite: FixedSpriteData) -> Drawable {
         Drawable::StaticSprite { x, y, data: sprite }
     }
+    /// A one-pixel-thick line; see [`Drawable::anti_aliased`] and [`Drawable::Line`].
+    pub fn line(color: Color, x1: f64, y1: f64, x2: f64, y2: f64) -> Drawable {
+        Drawable::Line {
+            color,
+            x1,
+            y1,
+            x2,
+            y2,
+            thickness: 1.0,
+            anti_alias: false,
+        }
+    }
+    /// A filled circle.
+    pub fn circle(color: Color, cx: f64, cy: f64, r: f64) -> Drawable {
+        Drawable::ellipse(color, cx, cy, r, r)
+    }
+    /// A filled ellipse; use [`Drawable::outlined`] for just the edge.
+    pub fn ellipse(color: Color, cx: f64, cy: f64, rx: f64, ry: f64) -> Drawable {
+        Drawable::Ellipse {
+            color,
+            cx,
+            cy,
+            rx,
+            ry,
+            filled: true,
+            anti_alias: false,
+        }
+    }
+    /// A filled polygon.
+    pub fn polygon(color: Color, points: Vec<(f64, f64)>) -> Drawable {
+        Drawable::Polygon {
+            color,
+            points,
+            anti_alias: false,
+        }
+    }
+    /// Turn on anti-aliasing for lines, ellipses and polygons; other drawables are returned unchanged.
+    pub fn anti_aliased(mut self) -> Drawable {
+        match &mut self {
+            Drawable::Line { anti_alias, .. }
+            | Drawable::Ellipse { anti_alias, .. }
+            | Drawable::Polygon { anti_alias, .. } => *anti_alias = true,
+            _ => {}
+        }
+        self
+    }
+    /// Draw only the edge of an ellipse; other drawables are returned unchanged.
+    pub fn outlined(mut self) -> Drawable {
+        if let Drawable::Ellipse { filled, .. } = &mut self {
+            *filled = false;
+        }
+        self
+    }
+}
+
+/// Anti-aliased shapes take SUPERSAMPLE x SUPERSAMPLE coverage samples per pixel.
+const SUPERSAMPLE: i32 = 4;
+
+/// Distance from (px, py) to the segment from (x1, y1) to (x2, y2).
+fn segment_distance(px: f64, py: f64, x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq > 0.0 {
+        (((px - x1) * dx + (py - y1) * dy) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let (nx, ny) = (x1 + t * dx - px, y1 + t * dy - py);
+    (nx * nx + ny * ny).sqrt()
+}
+
+/// Even-odd point-in-polygon test.
+fn polygon_contains(points: &[(f64, f64)], px: f64, py: f64) -> bool {
+    let mut inside = false;
+    let mut j = points.len() - 1;
+    for i in 0..points.len() {
+        let (xi, yi) = points[i];
+        let (xj, yj) = points[j];
+        if (yi > py) != (yj > py) && px < (xj - xi) * (py - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
 }
 
+/// Rasterize a Line, Ellipse or Polygon into a width x height image.
+///
+/// Calls `plot(x, y, color)` once per touched pixel, with the color's alpha scaled by how much of the pixel the shape covers: one sample at the pixel center without anti-aliasing, or the fraction of a supersampled grid with it. Both buffers blend these with [`Color::blend_over`], so they agree on every primitive.
+fn rasterize_shape<F: FnMut(i32, i32, Color)>(
+    shape: &Drawable,
+    width: i32,
+    height: i32,
+    mut plot: F,
+) {
+    // Bounding box (x1, y1, x2, y2), anti-aliasing flag, color and an inside test.
+    let (bounds, anti_alias, color, inside): (_, _, _, Box<dyn Fn(f64, f64) -> bool + '_>) =
+        match shape {
+            &Drawable::Line {
+                color,
+                x1,
+                y1,
+                x2,
+                y2,
+                thickness,
+                anti_alias,
+            } => {
+                let r = thickness / 2.0;
+                (
+                    (
+                        x1.min(x2) - r,
+                        y1.min(y2) - r,
+                        x1.max(x2) + r,
+                        y1.max(y2) + r,
+                    ),
+                    anti_alias,
+                    color,
+                    Box::new(move |px, py| segment_distance(px, py, x1, y1, x2, y2) <= r),
+                )
+            }
+            &Drawable::Ellipse {
+                color,
+                cx,
+                cy,
+                rx,
+                ry,
+                filled,
+                anti_alias,
+            } => {
+                if rx <= 0.0 || ry <= 0.0 {
+                    return;
+                }
+                let within = move |px: f64, py: f64, rx: f64, ry: f64| {
+                    let (dx, dy) = ((px - cx) / rx, (py - cy) / ry);
+                    dx * dx + dy * dy <= 1.0
+                };
+                (
+                    (cx - rx, cy - ry, cx + rx, cy + ry),
+                    anti_alias,
+                    color,
+                    Box::new(move |px, py| {
+                        within(px, py, rx, ry)
+                            && (filled
+                                || rx <= 1.0
+                                || ry <= 1.0
+                                || !within(px, py, rx - 1.0, ry - 1.0))
+                    }),
+                )
+            }
+            Drawable::Polygon {
+                color,
+                points,
+                anti_alias,
+            } => {
+                if points.len() < 3 {
+                    return;
+                }
+                let mut bounds = (points[0].0, points[0].1, points[0].0, points[0].1);
+                for &(x, y) in points.iter() {
+                    bounds = (
+                        bounds.0.min(x),
+                        bounds.1.min(y),
+                        bounds.2.max(x),
+                        bounds.3.max(y),
+                    );
+                }
+                (
+                    bounds,
+                    *anti_alias,
+                    *color,
+                    Box::new(move |px, py| polygon_contains(points, px, py)),
+                )
+            }
+            _ => return,
+        };
+    if !color.is_visible() {
+        return;
+    }
+
+    let (bx1, by1, bx2, by2) = bounds;
+    let x_start = (bx1.floor() as i32).max(0);
+    let y_start = (by1.floor() as i32).max(0);
+    let x_end = (bx2.ceil() as i32).min(width);
+    let y_end = (by2.ceil() as i32).min(height);
+    let samples = if anti_alias { SUPERSAMPLE } else { 1 };
+    let step = 1.0 / f64::from(samples);
+    let total = samples * samples;
+
+    for y in y_start..y_end {
+        for x in x_start..x_end {
+            let mut hits = 0;
+            for sy in 0..samples {
+                for sx in 0..samples {
+                    let px = f64::from(x) + (f64::from(sx) + 0.5) * step;
+                    let py = f64::from(y) + (f64::from(sy) + 0.5) * step;
+                    if inside(px, py) {
+                        hits += 1;
+                    }
+                }
+            }
+            if hits > 0 {
+                let alpha = (u32::from(color.a) * hits as u32 + (total as u32) / 2) / total as u32;
+                plot(x, y, color.with_alpha(alpha as u8));
+            }
+        }
+    }
+}
+
+/// A pixel buffer that [`Drawable`] commands can be rendered into.
+///
+/// Backends only describe how to store a color; [`render_to_buffer`] does all of the drawing and clipping, so every pixel format sees exactly the same set of pixels.
+pub trait RenderTarget {
+    /// Width in pixels.
+    fn width(&self) -> i32;
+    /// Height in pixels.
+    fn height(&self) -> i32;
+    /// Overwrite every pixel with this color; this is [`Drawable::Clear`], which does not blend.
+    fn clear(&mut self, color: Color);
+    /// Composite a visible color over a single pixel with [`Color::blend_over`] semantics.
+    /// Only called with 0 <= x < width and 0 <= y < height.
+    fn blend_pixel(&mut self, x: i32, y: i32, color: Color);
+}
+
+/// Blend a w x h block of colors with its top-left at (x, y), skipping pixels outside the target and invisible colors.
+fn draw_pixels<T, F>(target: &mut T, x: i32, y: i32, w: i32, h: i32, color_at: F)
+where
+    T: RenderTarget + ?Sized,
+    F: Fn(i32, i32) -> Color,
+{
+    let (width, height) = (target.width(), target.height());
+    for yi in (-y).max(0)..h.min(height - y) {
+        for xi in (-x).max(0)..w.min(width - x) {
+            let color = color_at(xi, yi);
+            if color.is_visible() {
+                target.blend_pixel(x + xi, y + yi, color);
+            }
+        }
+    }
+}
+
+/// The rasterizer shared by every [`RenderTarget`]: draw each command in order.
+pub fn render_to_buffer<T: RenderTarget + ?Sized>(target: &mut T, commands: &[Drawable]) {
+    for cmd in commands {
+        match cmd {
+            &Drawable::Clear(color) => target.clear(color),
+            &Drawable::Rectangle { color, x, y, w, h } => {
+                draw_pixels(target, x, y, w, h, |_, _| color)
+            }
+            &Drawable::ColoredBitmap {
+                x,
+                y,
+                color,
+                data: ref sprite,
+            } => draw_pixels(target, x, y, sprite.width(), sprite.height(), |xi, yi| {
+                if sprite.data[yi as usize][xi as usize] {
+                    color
+                } else {
+                    Color::invisible()
+                }
+            }),
+            &Drawable::StaticSprite {
+                x,
+                y,
+                data: ref sprite,
+            } => draw_pixels(target, x, y, sprite.width(), sprite.height(), |xi, yi| {
+                sprite.data[yi as usize][xi as usize]
+            }),
+            Drawable::DestructibleSprite(sprite) => {
+                let (x, y) = sprite.position();
+                draw_pixels(target, x, y, sprite.width(), sprite.height(), |xi, yi| {
+                    sprite.data[yi as usize][xi as usize]
+                })
+            }
+            shape @ Drawable::Line { .. }
+            | shape @ Drawable::Ellipse { .. }
+            | shape @ Drawable::Polygon { .. } => {
+                let (w, h) = (target.width(), target.height());
+                rasterize_shape(shape, w, h, |x, y, color| target.blend_pixel(x, y, color))
+            }
+        }
+    }
+}
+
+/// Find the palette entry closest (in RGB) to a color.
+fn nearest_palette_index(palette: &[Color], color: Color) -> u8 {
+    let distance = |p: &Color| {
+        let dr = i32::from(p.r) - i32::from(color.r);
+        let dg = i32::from(p.g) - i32::from(color.g);
+        let db = i32::from(p.b) - i32::from(color.b);
+        dr * dr + dg * dg + db * db
+    };
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, p)| distance(p))
+        .map(|(i, _)| i as u8)
+        .unwrap_or(0)
+}
+
+/// One byte of luminance per pixel.
 pub struct GrayscaleBuffer {
     pub width: i32,
     pub height: i32,
@@ -272,90 +644,32 @@ impl GrayscaleBuffer {
             data: vec![0; (width * height) as usize],
         }
     }
-    #[inline(always)]
-    fn set_pixel(&mut self, x: i32, y: i32, color: u8) {
-        if x < 0 || y < 0 || x > self.width || y > self.height {
-            return;
-        }
-        let start = ((y * self.width) + x) as usize;
-        let start = start as usize;
-        if start >= self.data.len() {
-            return;
-        }
-        self.data[start] = color;
+    pub fn render(&mut self, commands: &[Drawable]) {
+        render_to_buffer(self, commands)
     }
-    #[inline(always)]
-    fn set_pixel_alpha(&mut self, x: i32, y: i32, color: Color) {
-        if color.is_visible() {
-            self.set_pixel(x, y, color.grayscale_byte())
-        }
+}
+impl RenderTarget for GrayscaleBuffer {
+    fn width(&self) -> i32 {
+        self.width
     }
-
-    pub fn render(&mut self, commands: &[Drawable]) {
-        for cmd in commands {
-            match cmd {
-                &Drawable::Clear(color) => {
-                    let fill = color.grayscale_byte();
-                    for x in self.data.iter_mut() {
-                        *x = fill;
-                    }
-                }
-                &Drawable::Rectangle { color, x, y, w, h } => {
-                    let fill = color.grayscale_byte();
-                    for yi in y..(y + h) {
-                        for xi in x..(x + w) {
-                            self.set_pixel(xi, yi, fill)
-                        }
-                    }
-                }
-                &Drawable::DestructibleSprite(ref sprite) => {
-                    let w = sprite.width();
-                    let h = sprite.height();
-                    let (x, y) = sprite.position();
-                    for yi in 0..h {
-                        for xi in 0..w {
-                            let color = sprite.data[yi as usize][xi as usize];
-                            self.set_pixel_alpha(xi + x, yi + y, color)
-                        }
-                    }
-                }
-                &Drawable::ColoredBitmap {
-                    x,
-                    y,
-                    color,
-                    data: ref sprite,
-                } => {
-                    let w = sprite.width();
-                    let h = sprite.height();
-                    let color = color.grayscale_byte();
-                    for yi in 0..h {
-                        for xi in 0..w {
-                            if sprite.data[yi as usize][xi as usize] {
-                                self.set_pixel(x + xi, y + yi, color);
-                            }
-                        }
-                    }
-                }
-                &Drawable::StaticSprite {
-                    x,
-                    y,
-                    data: ref sprite,
-                } => {
-                    let w = sprite.width();
-                    let h = sprite.height();
-                    for yi in 0..h {
-                        for xi in 0..w {
-                            let color = sprite.data[yi as usize][xi as usize];
-                            self.set_pixel_alpha(xi + x, yi + y, color)
-                        }
-                    }
-                }
-            }
+    fn height(&self) -> i32 {
+        self.height
+    }
+    fn clear(&mut self, color: Color) {
+        let fill = color.grayscale_byte();
+        for x in self.data.iter_mut() {
+            *x = fill;
         }
-        // Done.
+    }
+    /// Gray is mixed with the same alpha as RGB would be.
+    #[inline(always)]
+    fn blend_pixel(&mut self, x: i32, y: i32, color: Color) {
+        let pixel = &mut self.data[(y * self.width + x) as usize];
+        *pixel = blend_channel(color.grayscale_byte(), *pixel, color.a);
     }
 }
 
+/// Pixels encoded as RGBA, four bytes each.
 pub struct ImageBuffer {
     pub width: i32,
     pub height: i32,
@@ -371,115 +685,207 @@ impl ImageBuffer {
         }
     }
 
-    #[inline(always)]
-    fn set_pixel(&mut self, x: i32, y: i32, color: Color) {
-        if x < 0 || y < 0 || x > self.width || y > self.height {
-            return;
-        }
-        debug_assert!(color.is_visible());
-        let start = ((y * self.width * 4) + (x * 4)) as usize;
-        if start >= self.data.len() {
-            return;
-        }
-        self.data[start] = color.r;
-        self.data[start + 1] = color.g;
-        self.data[start + 2] = color.b;
-        self.data[start + 3] = color.a;
+    /// Used in breakout_wp
+    pub fn render_sprite(&mut self, data: &[Vec<Color>]) {
+        let h = data.len() as i32;
+        let w = data[0].len() as i32;
+        draw_pixels(self, 0, 0, w, h, |xi, yi| data[yi as usize][xi as usize]);
     }
 
-    #[inline(always)]
-    fn set_pixel_alpha(&mut self, x: i32, y: i32, color: Color) {
-        if color.is_visible() {
-            self.set_pixel(x, y, color)
-        }
+    pub fn render(&mut self, commands: &[Drawable]) {
+        render_to_buffer(self, commands)
     }
 
-    /// Used in breakout_wp
-    pub fn render_sprite(&mut self, data: &Vec<Vec<Color>>) {
-        let h = data.len() as i32;
-        let w = data[0].len() as i32;
-        for yi in 0..h {
-            for xi in 0..w {
-                let color = data[yi as usize][xi as usize];
-                self.set_pixel_alpha(xi, yi, color)
+    /// Average the RGB of a scale-by-scale block of pixels; pixels outside the image are skipped.
+    fn average_block(&self, x: i32, y: i32, scale: i32) -> (u8, u8, u8) {
+        let mut sum = [0u32; 3];
+        let mut count = 0;
+        for yi in y..(y + scale).min(self.height) {
+            for xi in x..(x + scale).min(self.width) {
+                let start = ((yi * self.width + xi) * 4) as usize;
+                for (total, channel) in sum.iter_mut().zip(&self.data[start..start + 3]) {
+                    *total += u32::from(*channel);
+                }
+                count += 1;
             }
         }
+        if count == 0 {
+            return (0, 0, 0);
+        }
+        (
+            (sum[0] / count) as u8,
+            (sum[1] / count) as u8,
+            (sum[2] / count) as u8,
+        )
     }
 
-    fn render_rectangle(&mut self, color: Color, x: i32, y: i32, w: i32, h: i32) {
-        for yi in y..(y + h) {
-            for xi in x..(x + w) {
-                self.set_pixel(xi, yi, color)
+    /// Render this image as ANSI truecolor text for viewing in a terminal.
+    ///
+    /// Each character is an upper half-block whose foreground is the top pixel and whose background is the bottom one, so a line of text covers two rows. With `scale > 1`, each half-character averages a scale-by-scale block of pixels.
+    pub fn to_ansi(&self, scale: i32) -> String {
+        let scale = scale.max(1);
+        let mut output = String::new();
+        let mut y = 0;
+        while y < self.height {
+            let mut x = 0;
+            while x < self.width {
+                let (tr, tg, tb) = self.average_block(x, y, scale);
+                let (br, bg, bb) = self.average_block(x, y + scale, scale);
+                output.push_str(&format!(
+                    "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m\u{2580}",
+                    tr, tg, tb, br, bg, bb
+                ));
+                x += scale;
             }
+            output.push_str("\x1b[0m\n");
+            y += 2 * scale;
         }
+        output
+    }
+}
+impl RenderTarget for ImageBuffer {
+    fn width(&self) -> i32 {
+        self.width
+    }
+    fn height(&self) -> i32 {
+        self.height
+    }
+    fn clear(&mut self, color: Color) {
+        for pixel in self.data.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+        }
+    }
+    #[inline(always)]
+    fn blend_pixel(&mut self, x: i32, y: i32, color: Color) {
+        let start = ((y * self.width + x) * 4) as usize;
+        let px = &mut self.data[start..start + 4];
+        let out = color.blend_over(Color::rgba(px[0], px[1], px[2], px[3]));
+        px.copy_from_slice(&[out.r, out.g, out.b, out.a]);
     }
+}
 
-    fn render_static_sprite(&mut self, x: i32, y: i32, sprite: &FixedSpriteData) {
-        let w = sprite.width();
-        let h = sprite.height();
-        for yi in 0..h {
-            for xi in 0..w {
-                let color = sprite.data[yi as usize][xi as usize];
-                self.set_pixel_alpha(xi + x, yi + y, color)
-            }
+/// Pixels encoded as RGB, three bytes each; every pixel is treated as opaque.
+pub struct RgbBuffer {
+    pub width: i32,
+    pub height: i32,
+    pub data: Vec<u8>,
+}
+impl RgbBuffer {
+    pub fn alloc(width: i32, height: i32) -> RgbBuffer {
+        RgbBuffer {
+            width,
+            height,
+            data: vec![0; (width * height * 3) as usize],
         }
     }
+    pub fn render(&mut self, commands: &[Drawable]) {
+        render_to_buffer(self, commands)
+    }
+}
+impl RenderTarget for RgbBuffer {
+    fn width(&self) -> i32 {
+        self.width
+    }
+    fn height(&self) -> i32 {
+        self.height
+    }
+    fn clear(&mut self, color: Color) {
+        for pixel in self.data.chunks_exact_mut(3) {
+            pixel.copy_from_slice(&[color.r, color.g, color.b]);
+        }
+    }
+    #[inline(always)]
+    fn blend_pixel(&mut self, x: i32, y: i32, color: Color) {
+        let start = ((y * self.width + x) * 3) as usize;
+        let px = &mut self.data[start..start + 3];
+        let out = color.blend_over(Color::rgb(px[0], px[1], px[2]));
+        px.copy_from_slice(&[out.r, out.g, out.b]);
+    }
+}
 
+/// One f32 of luminance per pixel, from 0.0 (black) to 1.0 (white).
+pub struct FloatGrayscaleBuffer {
+    pub width: i32,
+    pub height: i32,
+    pub data: Vec<f32>,
+}
+impl FloatGrayscaleBuffer {
+    pub fn alloc(width: i32, height: i32) -> FloatGrayscaleBuffer {
+        FloatGrayscaleBuffer {
+            width,
+            height,
+            data: vec![0.0; (width * height) as usize],
+        }
+    }
     pub fn render(&mut self, commands: &[Drawable]) {
-        for cmd in commands {
-            match cmd {
-                &Drawable::Clear(color) => {
-                    for pixel in self.data.chunks_exact_mut(4) {
-                        pixel[0] = color.r;
-                        pixel[1] = color.g;
-                        pixel[2] = color.b;
-                        pixel[3] = color.a;
-                    }
-                }
-                &Drawable::Rectangle { color, x, y, w, h } => {
-                    self.render_rectangle(color, x, y, w, h)
-                }
-                &Drawable::ColoredBitmap {
-                    x,
-                    y,
-                    color,
-                    data: ref sprite,
-                } => {
-                    let w = sprite.width();
-                    let h = sprite.height();
-                    for yi in 0..h {
-                        for xi in 0..w {
-                            if sprite.data[yi as usize][xi as usize] {
-                                self.set_pixel(x + xi, y + yi, color);
-                            }
-                        }
-                    }
-                }
-                &Drawable::StaticSprite {
-                    x,
-                    y,
-                    data: ref sprite,
-                } => self.render_static_sprite(x, y, sprite),
-                &Drawable::DestructibleSprite(ref sprite) => {
-                    let w = sprite.width();
-                    let h = sprite.height();
-                    let (x, y) = sprite.position();
-                    for yi in 0..h {
-                        for xi in 0..w {
-                            let color = sprite.data[yi as usize][xi as usize];
-                            self.set_pixel_alpha(xi + x, yi + y, color)
-                        }
-                    }
-                }
-            }
+        render_to_buffer(self, commands)
+    }
+}
+impl RenderTarget for FloatGrayscaleBuffer {
+    fn width(&self) -> i32 {
+        self.width
+    }
+    fn height(&self) -> i32 {
+        self.height
+    }
+    fn clear(&mut self, color: Color) {
+        let fill = color.luminance() as f32;
+        for x in self.data.iter_mut() {
+            *x = fill;
         }
-        // Done.
+    }
+    #[inline(always)]
+    fn blend_pixel(&mut self, x: i32, y: i32, color: Color) {
+        let alpha = f32::from(color.a) / 255.0;
+        let pixel = &mut self.data[(y * self.width + x) as usize];
+        *pixel = color.luminance() as f32 * alpha + *pixel * (1.0 - alpha);
     }
 }
 
-/// Maybe deprecated? I made it OOP.
-pub fn render_to_buffer(target: &mut ImageBuffer, commands: &[Drawable]) {
-    target.render(commands);
+/// One byte per pixel, indexing into a fixed palette; blended colors snap to the nearest palette entry.
+pub struct PaletteBuffer {
+    pub width: i32,
+    pub height: i32,
+    pub palette: Vec<Color>,
+    pub data: Vec<u8>,
+}
+impl PaletteBuffer {
+    /// Allocate a buffer filled with palette entry 0; the palette must have between 1 and 256 colors.
+    pub fn alloc(width: i32, height: i32, palette: Vec<Color>) -> PaletteBuffer {
+        assert!(!palette.is_empty() && palette.len() <= 256);
+        PaletteBuffer {
+            width,
+            height,
+            palette,
+            data: vec![0; (width * height) as usize],
+        }
+    }
+    pub fn render(&mut self, commands: &[Drawable]) {
+        render_to_buffer(self, commands)
+    }
+    /// The palette color stored at a pixel.
+    pub fn color_at(&self, x: i32, y: i32) -> Color {
+        self.palette[self.data[(y * self.width + x) as usize] as usize]
+    }
+}
+impl RenderTarget for PaletteBuffer {
+    fn width(&self) -> i32 {
+        self.width
+    }
+    fn height(&self) -> i32 {
+        self.height
+    }
+    fn clear(&mut self, color: Color) {
+        let fill = nearest_palette_index(&self.palette, color);
+        for x in self.data.iter_mut() {
+            *x = fill;
+        }
+    }
+    #[inline(always)]
+    fn blend_pixel(&mut self, x: i32, y: i32, color: Color) {
+        let out = color.blend_over(self.color_at(x, y));
+        self.data[(y * self.width + x) as usize] = nearest_palette_index(&self.palette, out);
+    }
 }
 
 /// Parse a number from number_sprites.txt into a SpriteData.
@@ -549,3 +955,331 @@ pub fn load_bitmap_sprites(data: &str, set: char) -> Vec<BitmapSpriteData> {
 
     sprites.into_iter().rev().collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn render_both(
+        width: i32,
+        height: i32,
+        commands: &[Drawable],
+    ) -> (ImageBuffer, GrayscaleBuffer) {
+        let mut rgba = ImageBuffer::alloc(width, height);
+        rgba.render(commands);
+        let mut gray = GrayscaleBuffer::alloc(width, height);
+        gray.render(commands);
+        (rgba, gray)
+    }
+
+    fn rgba_at(img: &ImageBuffer, x: i32, y: i32) -> Color {
+        let start = ((y * img.width + x) * 4) as usize;
+        let px = &img.data[start..start + 4];
+        Color::rgba(px[0], px[1], px[2], px[3])
+    }
+
+    /// Every pixel of the grayscale render should match the grayscale of the color render, up to rounding.
+    fn assert_renders_agree(rgba: &ImageBuffer, gray: &GrayscaleBuffer) {
+        for y in 0..rgba.height {
+            for x in 0..rgba.width {
+                let expected = i32::from(rgba_at(rgba, x, y).grayscale_byte());
+                let actual = i32::from(gray.data[(y * gray.width + x) as usize]);
+                assert!(
+                    (expected - actual).abs() <= 1,
+                    "({}, {}): rgba gray {} vs gray {}",
+                    x,
+                    y,
+                    expected,
+                    actual
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_blend_over() {
+        let red = Color::rgb(255, 0, 0);
+        assert_eq!(red, red.blend_over(Color::white()));
+        assert_eq!(
+            Color::white(),
+            Color::invisible().blend_over(Color::white())
+        );
+        let half = Color::rgba(255, 0, 0, 128).blend_over(Color::black());
+        assert_eq!(Color::rgb(128, 0, 0), half);
+        // Blending onto a transparent pixel keeps the source alpha.
+        assert_eq!(
+            Color::rgba(0, 0, 0, 128),
+            Color::rgba(0, 0, 0, 128).blend_over(Color::invisible())
+        );
+        // ... and its color, rather than darkening toward the transparent pixel's black.
+        assert_eq!(
+            Color::rgba(255, 200, 10, 128),
+            Color::rgba(255, 200, 10, 128).blend_over(Color::invisible())
+        );
+        // Two translucent layers: 50% red over 50% blue is 75% opaque and 2/3 red.
+        assert_eq!(
+            Color::rgba(170, 0, 85, 192),
+            Color::rgba(255, 0, 0, 128).blend_over(Color::rgba(0, 0, 255, 128))
+        );
+    }
+
+    #[test]
+    fn test_alpha_rectangle_both_renderers() {
+        let commands = [
+            Drawable::Clear(Color::rgb(0, 0, 200)),
+            Drawable::rect(Color::rgba(255, 255, 0, 100), 1, 1, 2, 2),
+        ];
+        let (rgba, gray) = render_both(4, 4, &commands);
+        assert_eq!(Color::rgb(0, 0, 200), rgba_at(&rgba, 0, 0));
+        assert_eq!(
+            Color::rgba(255, 255, 0, 100).blend_over(Color::rgb(0, 0, 200)),
+            rgba_at(&rgba, 1, 1)
+        );
+        assert_renders_agree(&rgba, &gray);
+    }
+
+    #[test]
+    fn test_aliased_line_is_exact() {
+        let commands = [
+            Drawable::Clear(Color::black()),
+            Drawable::line(Color::white(), 0.5, 1.5, 4.5, 1.5),
+        ];
+        let (rgba, gray) = render_both(6, 3, &commands);
+        for x in 0..6 {
+            let expected = if x <= 4 { 255 } else { 0 };
+            assert_eq!(expected, gray.data[(6 + x) as usize], "x={}", x);
+            assert_eq!(0, gray.data[x as usize]);
+        }
+        assert_renders_agree(&rgba, &gray);
+    }
+
+    #[test]
+    fn test_anti_aliased_line_has_partial_coverage() {
+        let commands = [
+            Drawable::Clear(Color::black()),
+            Drawable::line(Color::white(), 0.0, 0.0, 8.0, 3.0).anti_aliased(),
+        ];
+        let (rgba, gray) = render_both(8, 4, &commands);
+        assert!(gray.data.iter().any(|&g| g > 0 && g < 255));
+        assert_renders_agree(&rgba, &gray);
+    }
+
+    #[test]
+    fn test_circle_area_and_symmetry() {
+        let r = 10.0;
+        let commands = [
+            Drawable::Clear(Color::black()),
+            Drawable::circle(Color::white(), 16.0, 16.0, r).anti_aliased(),
+        ];
+        let (rgba, gray) = render_both(32, 32, &commands);
+        let area: f64 = gray.data.iter().map(|&g| f64::from(g) / 255.0).sum();
+        let expected = std::f64::consts::PI * r * r;
+        assert!((area - expected).abs() / expected < 0.02, "area {}", area);
+        for y in 0..32 {
+            for x in 0..32 {
+                let mirrored = gray.data[(y * 32 + (31 - x)) as usize];
+                assert_eq!(gray.data[(y * 32 + x) as usize], mirrored);
+            }
+        }
+        assert_renders_agree(&rgba, &gray);
+    }
+
+    #[test]
+    fn test_outlined_ellipse_is_hollow() {
+        let commands = [
+            Drawable::Clear(Color::black()),
+            Drawable::ellipse(Color::white(), 10.0, 5.0, 8.0, 4.0).outlined(),
+        ];
+        let (rgba, gray) = render_both(20, 10, &commands);
+        assert_eq!(0, gray.data[5 * 20 + 10]);
+        assert_eq!(255, gray.data[5 * 20 + 2]);
+        assert_renders_agree(&rgba, &gray);
+    }
+
+    #[test]
+    fn test_polygon_fill_and_clipping() {
+        // A triangle hanging off the left and top of the image.
+        let triangle = vec![(-4.0, -4.0), (8.0, 0.0), (0.0, 8.0)];
+        let commands = [
+            Drawable::Clear(Color::black()),
+            Drawable::polygon(Color::rgba(0, 255, 0, 255), triangle),
+        ];
+        let (rgba, gray) = render_both(8, 8, &commands);
+        assert_eq!(Color::rgb(0, 255, 0), rgba_at(&rgba, 0, 0));
+        assert_eq!(Color::black(), rgba_at(&rgba, 7, 7));
+        assert_renders_agree(&rgba, &gray);
+
+        // A square covering exactly 4x4 pixels fills exactly 16, with or without anti-aliasing.
+        let square = vec![(2.0, 2.0), (6.0, 2.0), (6.0, 6.0), (2.0, 6.0)];
+        for shape in &[
+            Drawable::polygon(Color::white(), square.clone()),
+            Drawable::polygon(Color::white(), square.clone()).anti_aliased(),
+        ] {
+            let (_, gray) = render_both(8, 8, &[Drawable::Clear(Color::black()), shape.clone()]);
+            assert_eq!(16, gray.data.iter().filter(|&&g| g == 255).count());
+            assert_eq!(48, gray.data.iter().filter(|&&g| g == 0).count());
+        }
+    }
+
+    /// A scene touching every Drawable, with everything hanging off some edge of a 12x10 frame.
+    fn edge_scene(a: Color, b: Color, anti_alias: bool) -> Vec<Drawable> {
+        let bits = BitmapSpriteData::load(&["X.X", ".X.", "X.X"], 'X');
+        let sprite = FixedSpriteData::new(vec![vec![a, Color::invisible()], vec![b, a]]);
+        let mut destructible = SpriteData::new(vec![vec![b; 3]; 2]);
+        destructible.x = 10;
+        destructible.y = 9;
+        let shapes = vec![
+            Drawable::line(a, -3.0, 2.5, 14.0, 6.5),
+            Drawable::circle(b, 11.0, 1.0, 3.0),
+            Drawable::ellipse(a, 2.0, 8.0, 4.0, 2.5).outlined(),
+            Drawable::polygon(b, vec![(5.0, -2.0), (9.0, 4.0), (3.0, 5.0)]),
+        ];
+        let mut scene = vec![
+            Drawable::Clear(Color::black()),
+            Drawable::rect(a, 10, 3, 5, 2),
+            Drawable::rect(b, -2, -2, 3, 3),
+            Drawable::ColoredBitmap {
+                x: -1,
+                y: 8,
+                color: b,
+                data: bits,
+            },
+            Drawable::sprite(11, 9, sprite),
+            Drawable::DestructibleSprite(destructible),
+        ];
+        scene.extend(shapes.into_iter().map(|shape| {
+            if anti_alias {
+                shape.anti_aliased()
+            } else {
+                shape
+            }
+        }));
+        scene
+    }
+
+    #[test]
+    fn test_clipping_does_not_wrap_rows() {
+        // Before the shared rasterizer, x == width wrote into the first pixel of the next row.
+        let commands = [
+            Drawable::Clear(Color::black()),
+            Drawable::rect(Color::white(), 3, 0, 3, 1),
+            Drawable::rect(Color::white(), 0, 2, 4, 3),
+        ];
+        let (rgba, gray) = render_both(4, 3, &commands);
+        assert_eq!(
+            vec![0, 0, 0, 255, 0, 0, 0, 0, 255, 255, 255, 255],
+            gray.data
+        );
+        assert_renders_agree(&rgba, &gray);
+
+        let mut palette = PaletteBuffer::alloc(4, 3, vec![Color::black(), Color::white()]);
+        palette.render(&commands);
+        assert_eq!(vec![0, 0, 0, 1, 0, 0, 0, 0, 1, 1, 1, 1], palette.data);
+    }
+
+    #[test]
+    fn test_formats_pixel_exact_opaque() {
+        let (a, b) = (Color::rgb(200, 40, 40), Color::rgb(30, 220, 90));
+        let scene = edge_scene(a, b, false);
+        let (w, h) = (12, 10);
+        let (rgba, gray) = render_both(w, h, &scene);
+        let mut rgb = RgbBuffer::alloc(w, h);
+        rgb.render(&scene);
+        let mut float = FloatGrayscaleBuffer::alloc(w, h);
+        float.render(&scene);
+        let mut palette = PaletteBuffer::alloc(w, h, vec![Color::black(), a, b]);
+        palette.render(&scene);
+
+        let mut seen = HashSet::new();
+        for y in 0..h {
+            for x in 0..w {
+                let i = (y * w + x) as usize;
+                let expected = rgba_at(&rgba, x, y);
+                seen.insert(expected);
+                assert_eq!(
+                    &[expected.r, expected.g, expected.b],
+                    &rgb.data[i * 3..i * 3 + 3]
+                );
+                assert_eq!(expected.grayscale_byte(), gray.data[i]);
+                assert_eq!(expected.luminance() as f32, float.data[i]);
+                assert_eq!(expected, palette.color_at(x, y));
+            }
+        }
+        // Make sure the scene actually drew both colors.
+        assert_eq!(3, seen.len());
+    }
+
+    #[test]
+    fn test_formats_agree_translucent() {
+        let (a, b) = (Color::rgba(250, 200, 10, 160), Color::rgba(20, 90, 240, 90));
+        let scene = edge_scene(a, b, true);
+        let (w, h) = (12, 10);
+        let (rgba, gray) = render_both(w, h, &scene);
+        let mut rgb = RgbBuffer::alloc(w, h);
+        rgb.render(&scene);
+        let mut float = FloatGrayscaleBuffer::alloc(w, h);
+        float.render(&scene);
+
+        for y in 0..h {
+            for x in 0..w {
+                let i = (y * w + x) as usize;
+                let expected = rgba_at(&rgba, x, y);
+                assert!(expected.is_opaque());
+                assert_eq!(
+                    &[expected.r, expected.g, expected.b],
+                    &rgb.data[i * 3..i * 3 + 3]
+                );
+                let gray_error = i32::from(expected.grayscale_byte()) - i32::from(gray.data[i]);
+                assert!(
+                    gray_error.abs() <= 2,
+                    "({}, {}) off by {}",
+                    x,
+                    y,
+                    gray_error
+                );
+                let float_error = expected.luminance() - f64::from(float.data[i]);
+                assert!(float_error.abs() <= 2.0 / 255.0, "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_palette_snaps_blends() {
+        let palette = vec![Color::black(), Color::rgb(128, 128, 128), Color::white()];
+        let mut buffer = PaletteBuffer::alloc(2, 1, palette);
+        buffer.render(&[
+            Drawable::Clear(Color::rgb(10, 10, 10)),
+            Drawable::rect(Color::rgba(255, 255, 255, 120), 1, 0, 1, 1),
+        ]);
+        assert_eq!(vec![0, 1], buffer.data);
+    }
+
+    #[test]
+    fn test_to_ansi_half_blocks() {
+        let mut img = ImageBuffer::alloc(2, 2);
+        img.render(&[
+            Drawable::Clear(Color::black()),
+            Drawable::rect(Color::rgb(255, 0, 0), 0, 0, 1, 1),
+            Drawable::rect(Color::rgb(0, 0, 255), 1, 1, 1, 1),
+        ]);
+        let expected = concat!(
+            "\x1b[38;2;255;0;0m\x1b[48;2;0;0;0m\u{2580}",
+            "\x1b[38;2;0;0;0m\x1b[48;2;0;0;255m\u{2580}",
+            "\x1b[0m\n"
+        );
+        assert_eq!(expected, img.to_ansi(1));
+    }
+
+    #[test]
+    fn test_to_ansi_scaled_averages() {
+        let mut img = ImageBuffer::alloc(2, 3);
+        img.render(&[
+            Drawable::Clear(Color::black()),
+            Drawable::rect(Color::rgb(200, 100, 40), 0, 0, 1, 2),
+        ]);
+        // One column, one line; the bottom half is the partial third row.
+        let expected = "\x1b[38;2;100;50;20m\x1b[48;2;0;0;0m\u{2580}\x1b[0m\n";
+        assert_eq!(expected, img.to_ansi(2));
+    }
+}
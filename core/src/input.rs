@@ -1,3 +1,7 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
 /// Think NES-style controls: directions, and two buttons.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(C)]
@@ -32,6 +36,58 @@ impl Input {
     pub fn is_empty(self) -> bool {
         !self.left && !self.right && !self.up && !self.down && !self.button1 && !self.button2
     }
+    /// The inverse of [`AleAction::to_input`]; see [`AleAction::from_input`].
+    pub fn to_ale(self) -> Option<AleAction> {
+        AleAction::from_input(self)
+    }
+}
+
+/// A continuous control given alongside the buttons, like a joystick: each axis runs from -1.0 (full left or up) to 1.0 (full right or down).
+/// Games that honor it describe what each axis does with [`Simulation::continuous_action_space`](crate::Simulation::continuous_action_space).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AnalogInput {
+    pub horizontal: f64,
+    pub vertical: f64,
+}
+
+impl AnalogInput {
+    /// Create an analog input; values outside -1.0..1.0 are clamped and NaN is treated as centered.
+    pub fn new(horizontal: f64, vertical: f64) -> AnalogInput {
+        let axis = |x: f64| if x.is_nan() { 0.0 } else { x.clamp(-1.0, 1.0) };
+        AnalogInput {
+            horizontal: axis(horizontal),
+            vertical: axis(vertical),
+        }
+    }
+    /// Is the stick centered?
+    pub fn is_empty(self) -> bool {
+        self.horizontal == 0.0 && self.vertical == 0.0
+    }
+}
+
+/// One axis of a game's continuous action space; see [`Simulation::continuous_action_space`](crate::Simulation::continuous_action_space).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContinuousAxis {
+    /// Which field of [`AnalogInput`] this is: "horizontal" or "vertical".
+    pub name: String,
+    /// The smallest meaningful value.
+    pub low: f64,
+    /// The largest meaningful value.
+    pub high: f64,
+    /// What this axis controls in the game.
+    pub description: String,
+}
+
+impl ContinuousAxis {
+    /// An axis covering the whole -1.0..1.0 range.
+    pub fn full(name: &str, description: &str) -> ContinuousAxis {
+        ContinuousAxis {
+            name: name.to_string(),
+            low: -1.0,
+            high: 1.0,
+            description: description.to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, PartialOrd, Ord)]
@@ -56,7 +112,97 @@ pub enum AleAction {
     DOWNLEFTFIRE,
 }
 
+/// Every action, in ALE integer order; index i holds the action whose to_int() is i.
+const ALL_ACTIONS: [AleAction; 18] = [
+    AleAction::NOOP,
+    AleAction::FIRE,
+    AleAction::UP,
+    AleAction::RIGHT,
+    AleAction::LEFT,
+    AleAction::DOWN,
+    AleAction::UPRIGHT,
+    AleAction::UPLEFT,
+    AleAction::DOWNRIGHT,
+    AleAction::DOWNLEFT,
+    AleAction::UPFIRE,
+    AleAction::RIGHTFIRE,
+    AleAction::LEFTFIRE,
+    AleAction::DOWNFIRE,
+    AleAction::UPRIGHTFIRE,
+    AleAction::UPLEFTFIRE,
+    AleAction::DOWNRIGHTFIRE,
+    AleAction::DOWNLEFTFIRE,
+];
+
 impl AleAction {
+    /// Iterate over all 18 ALE actions in integer order, from NOOP to DOWNLEFTFIRE.
+    pub fn all() -> impl Iterator<Item = AleAction> {
+        ALL_ACTIONS.iter().cloned()
+    }
+    /// The ALE name of this action, e.g., "UPLEFTFIRE".
+    pub fn name(self) -> &'static str {
+        match self {
+            AleAction::NOOP => "NOOP",
+            AleAction::FIRE => "FIRE",
+            AleAction::UP => "UP",
+            AleAction::RIGHT => "RIGHT",
+            AleAction::LEFT => "LEFT",
+            AleAction::DOWN => "DOWN",
+            AleAction::UPRIGHT => "UPRIGHT",
+            AleAction::UPLEFT => "UPLEFT",
+            AleAction::DOWNRIGHT => "DOWNRIGHT",
+            AleAction::DOWNLEFT => "DOWNLEFT",
+            AleAction::UPFIRE => "UPFIRE",
+            AleAction::RIGHTFIRE => "RIGHTFIRE",
+            AleAction::LEFTFIRE => "LEFTFIRE",
+            AleAction::DOWNFIRE => "DOWNFIRE",
+            AleAction::UPRIGHTFIRE => "UPRIGHTFIRE",
+            AleAction::UPLEFTFIRE => "UPLEFTFIRE",
+            AleAction::DOWNRIGHTFIRE => "DOWNRIGHTFIRE",
+            AleAction::DOWNLEFTFIRE => "DOWNLEFTFIRE",
+        }
+    }
+    /// Convert a set of buttons back into an ALE action, if one exists.
+    ///
+    /// ALE has no second button, so any input with button2 held is None, as are the
+    /// physically impossible combinations left+right and up+down.
+    pub fn from_input(input: Input) -> Option<AleAction> {
+        if input.button2 || (input.left && input.right) || (input.up && input.down) {
+            return None;
+        }
+        let action = match (input.up, input.down, input.left, input.right) {
+            (false, false, false, false) => AleAction::NOOP,
+            (true, false, false, false) => AleAction::UP,
+            (false, true, false, false) => AleAction::DOWN,
+            (false, false, true, false) => AleAction::LEFT,
+            (false, false, false, true) => AleAction::RIGHT,
+            (true, false, false, true) => AleAction::UPRIGHT,
+            (true, false, true, false) => AleAction::UPLEFT,
+            (false, true, false, true) => AleAction::DOWNRIGHT,
+            (false, true, true, false) => AleAction::DOWNLEFT,
+            _ => unreachable!("opposing directions handled above"),
+        };
+        if input.button1 {
+            action.with_fire()
+        } else {
+            Some(action)
+        }
+    }
+    /// The FIRE variant of a directional action; None if already firing.
+    fn with_fire(self) -> Option<AleAction> {
+        match self {
+            AleAction::NOOP => Some(AleAction::FIRE),
+            AleAction::UP => Some(AleAction::UPFIRE),
+            AleAction::RIGHT => Some(AleAction::RIGHTFIRE),
+            AleAction::LEFT => Some(AleAction::LEFTFIRE),
+            AleAction::DOWN => Some(AleAction::DOWNFIRE),
+            AleAction::UPRIGHT => Some(AleAction::UPRIGHTFIRE),
+            AleAction::UPLEFT => Some(AleAction::UPLEFTFIRE),
+            AleAction::DOWNRIGHT => Some(AleAction::DOWNRIGHTFIRE),
+            AleAction::DOWNLEFT => Some(AleAction::DOWNLEFTFIRE),
+            _ => None,
+        }
+    }
     pub fn to_int(self) -> i32 {
         match self {
             AleAction::NOOP => 0,
@@ -178,10 +324,48 @@ impl AleAction {
     }
 }
 
+impl fmt::Display for AleAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Returned when a string is not the name of any AleAction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseAleActionError(String);
+
+impl fmt::Display for ParseAleActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown ALE action: `{}`.", self.0)
+    }
+}
+
+impl Error for ParseAleActionError {}
+
+impl FromStr for AleAction {
+    type Err = ParseAleActionError;
+
+    /// Parse an ALE action name, e.g., "UPLEFTFIRE"; case-insensitive.
+    fn from_str(s: &str) -> Result<AleAction, ParseAleActionError> {
+        let upper = s.trim().to_uppercase();
+        AleAction::all()
+            .find(|a| a.name() == upper)
+            .ok_or_else(|| ParseAleActionError(s.to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_analog_input_clamps() {
+        assert_eq!(AnalogInput::new(1.0, -0.4), AnalogInput::new(3.0, -0.4));
+        assert_eq!(-1.0, AnalogInput::new(-10.0, 0.0).horizontal);
+        assert!(AnalogInput::new(f64::NAN, 0.0).is_empty());
+        assert!(AnalogInput::default().is_empty());
+    }
+
     #[test]
     pub fn test_ale_to_from_int() {
         for i in 0..=17 {
@@ -190,4 +374,56 @@ mod tests {
             assert_eq!(Some(i), action_int);
         }
     }
+
+    #[test]
+    pub fn test_ale_all_in_order() {
+        let actions: Vec<AleAction> = AleAction::all().collect();
+        assert_eq!(18, actions.len());
+        for (i, action) in actions.into_iter().enumerate() {
+            assert_eq!(i as i32, action.to_int());
+        }
+    }
+
+    #[test]
+    pub fn test_ale_to_from_input() {
+        for action in AleAction::all() {
+            assert_eq!(Some(action), action.to_input().to_ale());
+        }
+    }
+
+    #[test]
+    pub fn test_input_to_from_ale() {
+        // Enumerate all 64 button combinations.
+        for bits in 0..64u32 {
+            let input = Input {
+                left: bits & 1 != 0,
+                right: bits & 2 != 0,
+                up: bits & 4 != 0,
+                down: bits & 8 != 0,
+                button1: bits & 16 != 0,
+                button2: bits & 32 != 0,
+            };
+            let impossible =
+                input.button2 || (input.left && input.right) || (input.up && input.down);
+            match input.to_ale() {
+                Some(action) => {
+                    assert!(!impossible, "{:?} -> {}", input, action);
+                    assert_eq!(input, action.to_input());
+                }
+                None => assert!(impossible, "{:?} has no action", input),
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_ale_display_from_str() {
+        for action in AleAction::all() {
+            let name = action.to_string();
+            assert_eq!(Ok(action), name.parse());
+            assert_eq!(Ok(action), name.to_lowercase().parse());
+        }
+        assert_eq!("UPLEFTFIRE", AleAction::UPLEFTFIRE.to_string());
+        assert!("UPDOWN".parse::<AleAction>().is_err());
+        assert!("".parse::<AleAction>().is_err());
+    }
 }
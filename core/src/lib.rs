@@ -8,13 +8,18 @@ extern crate png;
 
 pub mod body2d;
 pub mod collision;
+pub mod fixed;
 pub mod graphics;
 pub mod random;
+pub mod scalar;
 pub mod vec2d;
 
 mod input;
 pub use crate::input::AleAction;
+pub use crate::input::AnalogInput;
+pub use crate::input::ContinuousAxis;
 pub use crate::input::Input;
+pub use crate::input::ParseAleActionError;
 
 mod direction;
 pub use crate::direction::Direction;
@@ -56,6 +61,15 @@ pub trait State {
     fn level(&self) -> i32;
     /// To update internally to the next state, we pass buttons to internal logic.
     fn update_mut(&mut self, buttons: Input);
+    /// Update with an analog stick as well as the buttons; games without continuous controls ignore the stick.
+    fn update_mut_analog(&mut self, buttons: Input, _analog: AnalogInput) {
+        self.update_mut(buttons)
+    }
+    /// Update with one ``Input`` per player, in player order; players without an input press nothing.
+    /// Single-player games use the first input and ignore the rest.
+    fn update_mut_players(&mut self, inputs: &[Input]) {
+        self.update_mut(inputs.first().cloned().unwrap_or_default())
+    }
     /// Any state can create a vector of drawable objects to present itself.
     fn draw(&self) -> Vec<graphics::Drawable>;
     /// Any state can serialize to JSON String.
@@ -87,6 +101,14 @@ pub trait Simulation {
 
     /// Legal action set:
     fn legal_action_set(&self) -> Vec<AleAction>;
+    /// The axes of ``AnalogInput`` this game honors in ``State::update_mut_analog``; empty if it only uses buttons.
+    fn continuous_action_space(&self) -> Vec<ContinuousAxis> {
+        Vec::new()
+    }
+    /// How many players ``State::update_mut_players`` expects inputs for.
+    fn player_count(&self) -> usize {
+        1
+    }
 
     /// Getter for JSON Schema for this game's state.
     fn schema_for_state(&self) -> String;
@@ -0,0 +1,147 @@
+use super::fixed::Fixed;
+use std::fmt::Debug;
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// The numbers ``Vec2D`` and ``Body2D`` are made of: ``f64``, or ``Fixed`` for physics that comes out the same on every platform.
+/// Games keep their state in ``f64`` and may run a frame of physics in either; converting a ``Fixed`` result back is exact.
+pub trait Scalar:
+    Copy
+    + Debug
+    + PartialEq
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    /// The nearest number to a float.
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+
+    fn abs(self) -> Self;
+    /// One with the sign of this number; zero counts as positive.
+    fn signum(self) -> Self;
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn clamp(self, low: Self, high: Self) -> Self;
+    fn sqrt(self) -> Self;
+    /// Sine of an angle in radians.
+    fn sin(self) -> Self;
+    /// Cosine of an angle in radians.
+    fn cos(self) -> Self;
+    /// The angle in radians from the positive x axis to the point (x, self).
+    fn atan2(self, x: Self) -> Self;
+    fn to_radians(self) -> Self;
+    fn to_degrees(self) -> Self;
+}
+
+impl Scalar for f64 {
+    const ZERO: f64 = 0.0;
+    const ONE: f64 = 1.0;
+
+    fn from_f64(value: f64) -> f64 {
+        value
+    }
+    fn to_f64(self) -> f64 {
+        self
+    }
+    fn abs(self) -> f64 {
+        f64::abs(self)
+    }
+    fn signum(self) -> f64 {
+        f64::signum(self)
+    }
+    fn floor(self) -> f64 {
+        f64::floor(self)
+    }
+    fn ceil(self) -> f64 {
+        f64::ceil(self)
+    }
+    fn min(self, other: f64) -> f64 {
+        f64::min(self, other)
+    }
+    fn max(self, other: f64) -> f64 {
+        f64::max(self, other)
+    }
+    fn clamp(self, low: f64, high: f64) -> f64 {
+        f64::clamp(self, low, high)
+    }
+    fn sqrt(self) -> f64 {
+        f64::sqrt(self)
+    }
+    fn sin(self) -> f64 {
+        f64::sin(self)
+    }
+    fn cos(self) -> f64 {
+        f64::cos(self)
+    }
+    fn atan2(self, x: f64) -> f64 {
+        f64::atan2(self, x)
+    }
+    fn to_radians(self) -> f64 {
+        f64::to_radians(self)
+    }
+    fn to_degrees(self) -> f64 {
+        f64::to_degrees(self)
+    }
+}
+
+impl Scalar for Fixed {
+    const ZERO: Fixed = Fixed::ZERO;
+    const ONE: Fixed = Fixed::ONE;
+
+    fn from_f64(value: f64) -> Fixed {
+        Fixed::from_f64(value)
+    }
+    fn to_f64(self) -> f64 {
+        Fixed::to_f64(self)
+    }
+    fn abs(self) -> Fixed {
+        Fixed::abs(self)
+    }
+    fn signum(self) -> Fixed {
+        Fixed::signum(self)
+    }
+    fn floor(self) -> Fixed {
+        Fixed::floor(self)
+    }
+    fn ceil(self) -> Fixed {
+        Fixed::ceil(self)
+    }
+    fn min(self, other: Fixed) -> Fixed {
+        Fixed::min(self, other)
+    }
+    fn max(self, other: Fixed) -> Fixed {
+        Fixed::max(self, other)
+    }
+    fn clamp(self, low: Fixed, high: Fixed) -> Fixed {
+        Fixed::clamp(self, low, high)
+    }
+    fn sqrt(self) -> Fixed {
+        Fixed::sqrt(self)
+    }
+    fn sin(self) -> Fixed {
+        Fixed::sin(self)
+    }
+    fn cos(self) -> Fixed {
+        Fixed::cos(self)
+    }
+    fn atan2(self, x: Fixed) -> Fixed {
+        Fixed::atan2(self, x)
+    }
+    fn to_radians(self) -> Fixed {
+        Fixed::to_radians(self)
+    }
+    fn to_degrees(self) -> Fixed {
+        Fixed::to_degrees(self)
+    }
+}
@@ -1,93 +1,182 @@
+use super::scalar::Scalar;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
 use schemars::JsonSchema;
 
 /// This represents a point or a size or a velocity in 2 dimensions.
 /// We use f64 for internal representations but we can get integer coordinates upon request for drawing.
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
-pub struct Vec2D {
+/// Physics may run on ``Fixed`` numbers instead (see ``Scalar``); ``cast`` converts between the two.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Vec2D<S = f64> {
     /// The x-coordinate of this vector.
-    pub x: f64,
+    pub x: S,
     /// The y-coordinate of this vector.
-    pub y: f64,
+    pub y: S,
 }
 
-impl Vec2D {
+// The schema of a Vec2D of floats, derived from a copy so that it is named "Vec2D" rather than "Vec2D_for_double".
+/// This represents a point or a size or a velocity in 2 dimensions.
+/// We use f64 for internal representations but we can get integer coordinates upon request for drawing.
+#[derive(JsonSchema)]
+#[schemars(rename = "Vec2D")]
+#[allow(dead_code)]
+struct Vec2DSchema {
+    /// The x-coordinate of this vector.
+    x: f64,
+    /// The y-coordinate of this vector.
+    y: f64,
+}
+
+impl JsonSchema for Vec2D {
+    fn schema_name() -> String {
+        Vec2DSchema::schema_name()
+    }
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        Vec2DSchema::json_schema(gen)
+    }
+}
+
+impl<S: Scalar> Vec2D<S> {
     /// Create a new vector at the origin.
-    pub fn origin() -> Vec2D {
-        Vec2D { x: 0.0, y: 0.0 }
+    pub fn origin() -> Vec2D<S> {
+        Vec2D::new(S::ZERO, S::ZERO)
     }
     /// Create a new vector from components.
-    pub fn new(x: f64, y: f64) -> Vec2D {
+    pub fn new(x: S, y: S) -> Vec2D<S> {
         Vec2D { x, y }
     }
     /// Create a new vector from angle and speed.
-    pub fn from_polar(r: f64, theta: f64) -> Vec2D {
+    pub fn from_polar(r: S, theta: S) -> Vec2D<S> {
         Vec2D::new(r * theta.cos(), r * theta.sin())
     }
 
     /// The magnitude of the vector.
-    pub fn magnitude(&self) -> f64 {
+    pub fn magnitude(&self) -> S {
         (self.x * self.x + self.y * self.y).sqrt()
     }
 
     /// The squared magnitude of this vector; cheaper than magnitude if you just want to know which vector is biggest.
-    pub fn magnitude_squared(&self) -> f64 {
+    pub fn magnitude_squared(&self) -> S {
         self.x * self.x + self.y * self.y
     }
 
     /// Get the angle of this vector in radians.
-    pub fn angle(&self) -> f64 {
+    pub fn angle(&self) -> S {
         self.y.atan2(self.x)
     }
 
     /// Move a vector by another and produce a new vector.
-    pub fn translate(&self, by: &Vec2D) -> Vec2D {
+    pub fn translate(&self, by: &Vec2D<S>) -> Vec2D<S> {
         Vec2D::new(self.x + by.x, self.y + by.y)
     }
 
     /// Move a vector by another; modifying it.
-    pub fn translate_mut(&mut self, by: &Vec2D) {
+    pub fn translate_mut(&mut self, by: &Vec2D<S>) {
         self.x += by.x;
         self.y += by.y;
     }
 
     /// Scale a vector by a constant, producing a new vector.
-    pub fn scale(&self, by: f64) -> Vec2D {
+    pub fn scale(&self, by: S) -> Vec2D<S> {
         Vec2D::new(self.x * by, self.y * by)
     }
 
     /// Scale a vector by a constant, modifying it.
-    pub fn scale_mut(&mut self, by: f64) {
+    pub fn scale_mut(&mut self, by: S) {
         self.x *= by;
         self.y *= by;
     }
 
+    /// The dot product of two vectors.
+    pub fn dot(&self, other: &Vec2D<S>) -> S {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The z-component of the 3d cross product; positive if ``other`` is clockwise from this vector on screen (y points down).
+    pub fn cross(&self, other: &Vec2D<S>) -> S {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// A vector in the same direction with magnitude 1, or the zero vector if this one has no direction.
+    pub fn normalize(&self) -> Vec2D<S> {
+        let magnitude = self.magnitude();
+        if magnitude == S::ZERO {
+            Vec2D::origin()
+        } else {
+            self.scale(S::ONE / magnitude)
+        }
+    }
+
+    /// Bounce this vector off a surface with the given unit normal.
+    pub fn reflect(&self, normal: &Vec2D<S>) -> Vec2D<S> {
+        let along = S::from_f64(2.0) * self.dot(normal);
+        Vec2D::new(self.x - along * normal.x, self.y - along * normal.y)
+    }
+
+    /// This vector in another kind of number; to ``Fixed`` rounds to the nearest 1/256, and back is exact.
+    pub fn cast<T: Scalar>(&self) -> Vec2D<T> {
+        Vec2D::new(T::from_f64(self.x.to_f64()), T::from_f64(self.y.to_f64()))
+    }
+
     /// In order to render, we want pixel coordinates.
     pub fn pixels(&self) -> (i32, i32) {
-        (self.x.floor() as i32, self.y.floor() as i32)
+        (
+            self.x.floor().to_f64() as i32,
+            self.y.floor().to_f64() as i32,
+        )
     }
 }
 
 // For operator overloading.
-use std::ops::{Add, AddAssign};
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
 
 /// Support using the plus operator on vectors.
-impl Add for Vec2D {
-    type Output = Vec2D;
+impl<S: Scalar> Add for Vec2D<S> {
+    type Output = Vec2D<S>;
 
     /// This defers to translate; producing a new vector from the sum of two others.
-    fn add(self, other: Vec2D) -> Vec2D {
+    fn add(self, other: Vec2D<S>) -> Vec2D<S> {
         self.translate(&other)
     }
 }
 
 /// Support using the += operator on vectors.
-impl AddAssign for Vec2D {
+impl<S: Scalar> AddAssign for Vec2D<S> {
     /// This defers to translate_mut; translating a vector by another.
-    fn add_assign(&mut self, other: Vec2D) {
+    fn add_assign(&mut self, other: Vec2D<S>) {
         self.translate_mut(&other)
     }
 }
 
+/// Support using the minus operator on vectors.
+impl<S: Scalar> Sub for Vec2D<S> {
+    type Output = Vec2D<S>;
+
+    /// The vector from ``other`` to this one.
+    fn sub(self, other: Vec2D<S>) -> Vec2D<S> {
+        Vec2D::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+/// Support multiplying a vector by a constant.
+impl<S: Scalar> Mul<S> for Vec2D<S> {
+    type Output = Vec2D<S>;
+
+    /// This defers to scale.
+    fn mul(self, by: S) -> Vec2D<S> {
+        self.scale(by)
+    }
+}
+
+/// Support negating a vector.
+impl<S: Scalar> Neg for Vec2D<S> {
+    type Output = Vec2D<S>;
+
+    fn neg(self) -> Vec2D<S> {
+        Vec2D::new(-self.x, -self.y)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,4 +189,98 @@ mod tests {
         assert_eq!(pos, Vec2D::new(11.0, 7.0));
         assert_eq!(velocity.magnitude(), (32.0 as f64).sqrt())
     }
+
+    #[test]
+    fn test_operators() {
+        let a = Vec2D::new(3.0, -4.0);
+        let b = Vec2D::new(1.0, 2.0);
+        assert_eq!(Vec2D::new(2.0, -6.0), a.clone() - b.clone());
+        assert_eq!(Vec2D::new(6.0, -8.0), a.clone() * 2.0);
+        assert_eq!(Vec2D::new(-3.0, 4.0), -a.clone());
+        assert_eq!(-5.0, a.dot(&b));
+        assert_eq!(10.0, a.cross(&b));
+        assert_eq!(Vec2D::new(0.0, -1.0), Vec2D::new(0.0, -5.0).normalize());
+        let zero: Vec2D = Vec2D::origin();
+        assert_eq!(zero, zero.normalize());
+        // Bounce off a floor:
+        assert_eq!(Vec2D::new(3.0, 4.0), a.reflect(&Vec2D::new(0.0, -1.0)));
+    }
+
+    #[test]
+    fn test_cast() {
+        use crate::fixed::Fixed;
+        let v = Vec2D::new(1.0 / 3.0, -2.5);
+        let fixed: Vec2D<Fixed> = v.cast();
+        assert_eq!(
+            Vec2D::new(Fixed::from_raw(85), Fixed::from_raw(-640)),
+            fixed
+        );
+        assert_eq!(Vec2D::new(85.0 / 256.0, -2.5), fixed.cast::<f64>());
+        assert_eq!(fixed, fixed.cast::<f64>().cast::<Fixed>());
+        assert_eq!((0, -3), fixed.pixels());
+    }
+
+    #[test]
+    fn test_fixed_operators() {
+        use crate::fixed::Fixed;
+        let a: Vec2D<Fixed> = Vec2D::new(3.0, -4.0).cast();
+        assert_eq!(Fixed::from_int(5), a.magnitude());
+        // Scaled by 51/256, the nearest to 1/5.
+        assert_eq!(
+            Vec2D::new(Fixed::from_raw(153), Fixed::from_raw(-204)),
+            a.normalize()
+        );
+        let floor = Vec2D::new(0.0, -1.0).cast();
+        assert_eq!(Vec2D::new(3.0, 4.0).cast::<Fixed>(), a.reflect(&floor));
+        let up = Vec2D::from_polar(Fixed::from_int(2), Fixed::from_int(-90).to_radians());
+        assert_eq!(Vec2D::new(0.0, -2.0).cast::<Fixed>(), up);
+    }
+
+    use proptest::prelude::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-6 * (1.0 + a.abs().max(b.abs()))
+    }
+
+    fn vec2d() -> impl Strategy<Value = Vec2D> {
+        (-1000.0..1000.0f64, -1000.0..1000.0f64).prop_map(|(x, y)| Vec2D::new(x, y))
+    }
+
+    proptest! {
+        #[test]
+        fn prop_sub_undoes_add(a in vec2d(), b in vec2d()) {
+            let back = (a.clone() + b.clone()) - b;
+            prop_assert!(close(a.x, back.x) && close(a.y, back.y));
+        }
+
+        #[test]
+        fn prop_dot_and_cross(a in vec2d(), b in vec2d()) {
+            prop_assert_eq!(a.dot(&b), b.dot(&a));
+            prop_assert_eq!(a.cross(&b), -b.cross(&a));
+            prop_assert_eq!(a.dot(&a), a.magnitude_squared());
+            // Lagrange's identity in 2d.
+            let lhs = a.dot(&b).powi(2) + a.cross(&b).powi(2);
+            prop_assert!(close(lhs, a.magnitude_squared() * b.magnitude_squared()));
+        }
+
+        #[test]
+        fn prop_normalize(a in vec2d()) {
+            prop_assume!(a.magnitude() > 1e-6);
+            let unit = a.normalize();
+            prop_assert!(close(1.0, unit.magnitude()));
+            prop_assert!(close(0.0, unit.cross(&a) / a.magnitude()));
+            prop_assert!(unit.dot(&a) > 0.0);
+        }
+
+        #[test]
+        fn prop_reflect(a in vec2d(), n in vec2d()) {
+            prop_assume!(n.magnitude() > 1e-6);
+            let n = n.normalize();
+            let bounced = a.reflect(&n);
+            prop_assert!(close(a.magnitude(), bounced.magnitude()));
+            prop_assert!(close(-a.dot(&n), bounced.dot(&n)));
+            let twice = bounced.reflect(&n);
+            prop_assert!(close(a.x, twice.x) && close(a.y, twice.y));
+        }
+    }
 }
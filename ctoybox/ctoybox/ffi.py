@@ -93,6 +93,14 @@ class Simulator(object):
         """Mutably update this simulator/config with the replacement json."""
         self.__sim = self.__sim.from_json(json_str(config_js))
 
+    def get_continuous_action_space(self) -> List[Dict[str, Any]]:
+        """Get the analog axes this game honors in addition to its buttons: a list of ``{name, low, high, description}``; empty if it has none."""
+        return json.loads(self.__sim.continuous_action_space())
+
+    def get_player_count(self) -> int:
+        """Get how many players this game expects inputs for in ``apply_player_actions``."""
+        return self.__sim.player_count()
+
     def schema_for_state(self) -> Dict[str, Any]:
         """Get the JSON Schema for any state for this game."""
         return json.loads(self.__sim.frame_schema())
@@ -235,6 +243,22 @@ class State(object):
         self.get_state().render_into_buffer(frame, False)
         return np.asarray(frame, dtype=np.uint8).reshape(h, w, depth)
 
+    def render_ansi(self, scale: int = 1) -> str:
+        """Render the current frame as ANSI truecolor text, for terminals without a display.
+
+        Parameters:
+            scale: how many pixels in each direction to average into one half-character.
+        """
+        return self.get_state().render_ansi(scale)
+
+    def text_grid(self) -> List[str]:
+        """Get the logical tile grid as a list of strings; only GridWorld and Amidar support this.
+
+        Raises:
+            ValueError: if this game has no text mode.
+        """
+        return self.get_state().text_grid()
+
     def to_json(self) -> Dict[str, Any]:
         """Get a JSON representation of the state."""
         return json.loads(self.get_state().to_json())
@@ -356,6 +380,42 @@ class Toybox(object):
         for _ in range(self.frames_per_action):
             self.rstate.get_state().apply_action(action_input_obj)
 
+    def get_continuous_action_space(self) -> List[Dict[str, Any]]:
+        """Get the analog axes this game honors: a list of ``{name, low, high, description}`` where ``name`` is ``"horizontal"`` or ``"vertical"``; empty for button-only games."""
+        return self.rsimulator.get_continuous_action_space()
+
+    def apply_analog_action(
+        self, action_input_obj: Input, horizontal: float = 0.0, vertical: float = 0.0
+    ):
+        """Like [apply_action][], but with an analog stick as well; each axis runs from -1.0 to 1.0 and is ignored by games that don't list it in ``get_continuous_action_space``.
+
+        This applies the action *k* times, where *k* based on the frameskip passed to the Toybox constructor.
+
+        Parameters:
+            action_input_obj: An instance of the [ctoybox.Input][] class.
+            horizontal: Stick position from -1.0 (left) to 1.0 (right).
+            vertical: Stick position from -1.0 (up) to 1.0 (down).
+        """
+        for _ in range(self.frames_per_action):
+            self.rstate.get_state().apply_analog_action(
+                action_input_obj, horizontal, vertical
+            )
+
+    def get_player_count(self) -> int:
+        """Get how many players this game expects inputs for; 1 unless the config sets up a two-player game."""
+        return self.rsimulator.get_player_count()
+
+    def apply_player_actions(self, action_input_objs: List[Input]):
+        """Like [apply_action][], but with one input per player, in player order; players without an input press nothing.
+
+        This applies the actions *k* times, where *k* based on the frameskip passed to the Toybox constructor.
+
+        Parameters:
+            action_input_objs: A list of instances of the [ctoybox.Input][] class.
+        """
+        for _ in range(self.frames_per_action):
+            self.rstate.get_state().apply_player_actions(action_input_objs)
+
     def get_state(self) -> np.array:
         """This state here actually refers to the graphical, RGBA or grayscale representation of the current state."""
         return self.rstate.render_frame(self.rsimulator, self.grayscale)
@@ -387,6 +447,10 @@ class Toybox(object):
             )
         img.save(path, format="png")
 
+    def get_ansi_frame(self, scale: int = 1) -> str:
+        """Get the current frame as ANSI truecolor text; ``print`` it to see it in a terminal."""
+        return self.rstate.render_ansi(scale)
+
     def get_rgb_frame(self) -> np.array:
         """Get the RGB frame as a numpy array."""
         return self.rstate.render_frame_rgb(self.rsimulator)
@@ -70,6 +70,15 @@ impl Game {
         Ok(self.inner.game_size())
     }
 
+    fn continuous_action_space(&self) -> PyResult<String> {
+        serde_json::to_string(&self.inner.continuous_action_space())
+            .map_err(|e| PyValueError::new_err(format!("{:?}", e)))
+    }
+
+    fn player_count(&self) -> PyResult<usize> {
+        Ok(self.inner.player_count())
+    }
+
     fn legal_actions(&self) -> PyResult<Vec<i32>> {
         Ok(self
             .inner
@@ -114,6 +123,19 @@ struct Input {
     pub button2: bool,
 }
 
+impl Input {
+    fn to_toybox(&self) -> toybox_core::Input {
+        toybox_core::Input {
+            left: self.left,
+            right: self.right,
+            up: self.up,
+            down: self.down,
+            button1: self.button1,
+            button2: self.button2,
+        }
+    }
+}
+
 #[pymethods]
 impl Input {
     #[new]
@@ -178,17 +200,43 @@ impl State {
         }
         Ok(())
     }
+    fn render_ansi(&self, scale: i32) -> PyResult<String> {
+        let (w, h) = self.shape;
+        let mut img = ImageBuffer::alloc(w, h);
+        img.render(&self.inner.draw());
+        Ok(img.to_ansi(scale))
+    }
+    fn text_grid(&self) -> PyResult<Vec<String>> {
+        let grid = self
+            .inner
+            .query_json("text_grid", &serde_json::Value::Null)
+            .map_err(|e| PyValueError::new_err(format!("{:?}", e)))?;
+        Ok(serde_json::from_str(&grid).map_err(|e| PyValueError::new_err(format!("{:?}", e)))?)
+    }
     fn apply_action(&mut self, input: &PyCell<Input>) -> PyResult<()> {
         let input: PyRef<Input> = input.borrow();
-        let tb_input = toybox_core::Input {
-            left: input.left,
-            right: input.right,
-            up: input.up,
-            down: input.down,
-            button1: input.button1,
-            button2: input.button2,
-        };
-        self.inner.as_mut().update_mut(tb_input);
+        self.inner.as_mut().update_mut(input.to_toybox());
+        Ok(())
+    }
+    fn apply_analog_action(
+        &mut self,
+        input: &PyCell<Input>,
+        horizontal: f64,
+        vertical: f64,
+    ) -> PyResult<()> {
+        let input: PyRef<Input> = input.borrow();
+        let analog = toybox_core::AnalogInput::new(horizontal, vertical);
+        self.inner
+            .as_mut()
+            .update_mut_analog(input.to_toybox(), analog);
+        Ok(())
+    }
+    fn apply_player_actions(&mut self, inputs: Vec<&PyCell<Input>>) -> PyResult<()> {
+        let inputs: Vec<toybox_core::Input> = inputs
+            .into_iter()
+            .map(|input| input.borrow().to_toybox())
+            .collect();
+        self.inner.as_mut().update_mut_players(&inputs);
         Ok(())
     }
 
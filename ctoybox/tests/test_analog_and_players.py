@@ -0,0 +1,42 @@
+import unittest
+from ctoybox import Toybox, Input
+
+
+class TestAnalogAction(unittest.TestCase):
+
+    def test_pong_stick_moves_paddle(self):
+        with Toybox('pong') as tb:
+            start = tb.state_to_json()['p1_paddle']['position']['y']
+            for _ in range(10):
+                tb.apply_analog_action(Input(), vertical=1.0)
+            paddle = tb.state_to_json()['p1_paddle']
+            # Positive is down.
+            self.assertGreater(paddle['position']['y'], start)
+            self.assertGreater(paddle['velocity']['y'], 0.0)
+
+
+class TestPlayerActions(unittest.TestCase):
+
+    def test_space_invaders_cooperative_ships(self):
+        with Toybox('spaceinvaders') as tb:
+            config = tb.config_to_json()
+            config['two_player'] = 'Cooperative'
+            tb.write_config_json(config)
+            self.assertEqual(2, tb.get_player_count())
+            while not tb.state_to_json()['ship']['alive']:
+                tb.apply_player_actions([Input(), Input()])
+
+            start = tb.query_state_json('ships_xy')
+            left = Input()
+            left.left = True
+            right = Input()
+            right.right = True
+            # Each input moves its own ship.
+            tb.apply_player_actions([right, left])
+            ships = tb.query_state_json('ships_xy')
+            self.assertGreater(ships[0][0], start[0][0])
+            self.assertLess(ships[1][0], start[1][0])
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,126 @@
+//! Print a game frame to the terminal, for eyeballing states on machines without a display.
+extern crate serde_json;
+extern crate toybox;
+extern crate toybox_core;
+
+use std::fs;
+use toybox::graphics::ImageBuffer;
+use toybox_core::AleAction;
+
+const USAGE: &str = "usage: ansi_frame <game> [--scale N] [--steps N] [--action NOOP] [--seed N] [--config config.json] [--state state.json] [--text]";
+
+struct Options {
+    game: String,
+    scale: i32,
+    steps: usize,
+    action: AleAction,
+    seed: Option<u32>,
+    config: Option<String>,
+    state: Option<String>,
+    text: bool,
+}
+
+fn parse(args: &[String]) -> Result<Options, String> {
+    let mut game = None;
+    let mut out = Options {
+        game: String::new(),
+        scale: 1,
+        steps: 0,
+        action: AleAction::NOOP,
+        seed: None,
+        config: None,
+        state: None,
+        text: false,
+    };
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        if arg == "--text" {
+            out.text = true;
+            continue;
+        }
+        if !arg.starts_with("--") {
+            if game.is_some() {
+                return Err(format!("Unexpected argument: `{}`.", arg));
+            }
+            game = Some(arg.clone());
+            continue;
+        }
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("Missing value for {}.", arg))?;
+        let bad_value = || format!("Bad value for {}: `{}`.", arg, value);
+        match arg.as_str() {
+            "--scale" => out.scale = value.parse().map_err(|_| bad_value())?,
+            "--steps" => out.steps = value.parse().map_err(|_| bad_value())?,
+            "--action" => out.action = value.parse().map_err(|e| format!("{}", e))?,
+            "--seed" => out.seed = Some(value.parse().map_err(|_| bad_value())?),
+            "--config" => out.config = Some(value.clone()),
+            "--state" => out.state = Some(value.clone()),
+            _ => return Err(format!("Unknown option: `{}`.", arg)),
+        }
+    }
+    out.game = game.ok_or_else(|| "Missing game name.".to_string())?;
+    Ok(out)
+}
+
+fn read(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))
+}
+
+fn run(opts: Options) -> Result<(), String> {
+    let mut sim = toybox::get_simulation_by_name(&opts.game)?;
+    if let Some(path) = &opts.config {
+        sim = sim
+            .from_json(&read(path)?)
+            .map_err(|e| format!("Cannot load config from {}: {}", path, e))?;
+    }
+    if let Some(seed) = opts.seed {
+        sim.reset_seed(seed);
+    }
+    let mut state = match &opts.state {
+        Some(path) => sim
+            .new_state_from_json(&read(path)?)
+            .map_err(|e| format!("Cannot load state from {}: {}", path, e))?,
+        None => sim.new_game(),
+    };
+    for _ in 0..opts.steps {
+        state.update_mut(opts.action.to_input());
+    }
+
+    if opts.text {
+        let grid = state
+            .query_json("text_grid", &serde_json::Value::Null)
+            .map_err(|e| format!("{} has no text mode: {}", opts.game, e))?;
+        let lines: Vec<String> = serde_json::from_str(&grid).map_err(|e| format!("{}", e))?;
+        for line in lines {
+            println!("{}", line);
+        }
+    } else {
+        let (w, h) = sim.game_size();
+        let mut img = ImageBuffer::alloc(w, h);
+        img.render(&state.draw());
+        print!("{}", img.to_ansi(opts.scale));
+    }
+    println!(
+        "score: {} lives: {} level: {}",
+        state.score(),
+        state.lives(),
+        state.level()
+    );
+    Ok(())
+}
+
+fn main() {
+    let argv: Vec<String> = std::env::args().collect();
+    let opts = match parse(&argv) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{}\n{}\ngames: {:?}", e, USAGE, toybox::GAME_LIST);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = run(opts) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,178 @@
+//! Window-independent pieces of the native human_play viewer: argument parsing, frame scaling and input recording.
+//! The window itself lives in main.rs behind the `window` feature.
+extern crate serde_json;
+extern crate toybox;
+extern crate toybox_core;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use toybox::graphics::ImageBuffer;
+use toybox_core::Input;
+
+pub const USAGE: &str = "usage: human_play <game> [--scale N] [--fps N] [--seed N] [--config config.json] [--state state.json] [--save state.json] [--record inputs.jsonl]";
+
+/// Command-line options for the viewer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Args {
+    /// Which game to play; see `toybox::GAME_LIST`.
+    pub game: String,
+    /// Integer zoom factor applied to each game pixel.
+    pub scale: usize,
+    /// Frames per second while playing; Tab plays at four times this rate.
+    pub fps: usize,
+    /// Seed the simulation before the first new_game.
+    pub seed: Option<u32>,
+    /// Replace the default config with this JSON file.
+    pub config: Option<String>,
+    /// Start from this state JSON file rather than a new game.
+    pub state: Option<String>,
+    /// Where F5 saves (and F9 loads) the current state.
+    pub save: String,
+    /// Append one JSON line per simulated frame to this file.
+    pub record: Option<String>,
+}
+
+impl Args {
+    /// Parse arguments in the style of ctoybox's human_play.py; the first element should be the program name.
+    pub fn parse(args: &[String]) -> Result<Args, String> {
+        let mut game = None;
+        let mut out = Args {
+            game: String::new(),
+            scale: 2,
+            fps: 32,
+            seed: None,
+            config: None,
+            state: None,
+            save: "human_play_state.json".to_string(),
+            record: None,
+        };
+        let mut iter = args.iter().skip(1);
+        while let Some(arg) = iter.next() {
+            if !arg.starts_with("--") {
+                if game.is_some() {
+                    return Err(format!("Unexpected argument: `{}`.", arg));
+                }
+                game = Some(arg.clone());
+                continue;
+            }
+            let value = iter
+                .next()
+                .ok_or_else(|| format!("Missing value for {}.", arg))?;
+            match arg.as_str() {
+                "--scale" => out.scale = parse_positive(arg, value)?,
+                "--fps" => out.fps = parse_positive(arg, value)?,
+                "--seed" => {
+                    out.seed = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Bad value for --seed: `{}`.", value))?,
+                    )
+                }
+                "--config" => out.config = Some(value.clone()),
+                "--state" => out.state = Some(value.clone()),
+                "--save" => out.save = value.clone(),
+                "--record" => out.record = Some(value.clone()),
+                _ => return Err(format!("Unknown option: `{}`.", arg)),
+            }
+        }
+        out.game = game.ok_or_else(|| "Missing game name.".to_string())?;
+        Ok(out)
+    }
+}
+
+fn parse_positive(flag: &str, value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(x) if x > 0 => Ok(x),
+        _ => Err(format!("Bad value for {}: `{}`.", flag, value)),
+    }
+}
+
+/// Copy an RGBA image into a 0RGB framebuffer that is `scale` times larger in each dimension.
+pub fn blit_scaled(img: &ImageBuffer, scale: usize, dest: &mut Vec<u32>) {
+    let w = img.width as usize;
+    let h = img.height as usize;
+    dest.clear();
+    dest.reserve(w * h * scale * scale);
+    for row in img.data.chunks_exact(w * 4).take(h) {
+        let start = dest.len();
+        for px in row.chunks_exact(4) {
+            let rgb = (u32::from(px[0]) << 16) | (u32::from(px[1]) << 8) | u32::from(px[2]);
+            dest.extend((0..scale).map(|_| rgb));
+        }
+        let end = dest.len();
+        for _ in 1..scale {
+            dest.extend_from_within(start..end);
+        }
+    }
+}
+
+/// Writes every simulated frame's buttons as a line of JSON, with the matching ALE action name when there is one.
+pub struct Recorder {
+    out: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn create(path: &str) -> io::Result<Recorder> {
+        Ok(Recorder {
+            out: BufWriter::new(File::create(path)?),
+        })
+    }
+    pub fn record(&mut self, frame: u64, input: Input) -> io::Result<()> {
+        let line = serde_json::json!({
+            "frame": frame,
+            "input": input,
+            "ale": input.to_ale().map(|a| a.to_string()),
+        });
+        writeln!(self.out, "{}", line)
+    }
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toybox::graphics::{Color, Drawable};
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = Args::parse(&args(&[
+            "human_play",
+            "pong",
+            "--scale",
+            "3",
+            "--seed",
+            "7",
+        ]))
+        .unwrap();
+        assert_eq!("pong", parsed.game);
+        assert_eq!(3, parsed.scale);
+        assert_eq!(32, parsed.fps);
+        assert_eq!(Some(7), parsed.seed);
+
+        assert!(Args::parse(&args(&["human_play"])).is_err());
+        assert!(Args::parse(&args(&["human_play", "pong", "--fps", "0"])).is_err());
+        assert!(Args::parse(&args(&["human_play", "pong", "--scale"])).is_err());
+        assert!(Args::parse(&args(&["human_play", "pong", "--bogus", "1"])).is_err());
+    }
+
+    #[test]
+    fn test_blit_scaled() {
+        let mut img = ImageBuffer::alloc(2, 1);
+        img.render(&[
+            Drawable::Clear(Color::black()),
+            Drawable::rect(Color::rgb(1, 2, 3), 1, 0, 1, 1),
+        ]);
+        let mut dest = Vec::new();
+        blit_scaled(&img, 2, &mut dest);
+        assert_eq!(
+            vec![0, 0, 0x010203, 0x010203, 0, 0, 0x010203, 0x010203],
+            dest
+        );
+    }
+}
@@ -0,0 +1,145 @@
+extern crate human_play;
+extern crate minifb;
+extern crate toybox;
+extern crate toybox_core;
+
+use human_play::{blit_scaled, Args, Recorder, USAGE};
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
+use std::fs;
+use toybox::graphics::ImageBuffer;
+use toybox::{Simulation, State};
+use toybox_core::Input;
+
+/// Mirrors the key bindings of ctoybox's human_play.py.
+fn read_input(window: &Window) -> Input {
+    let down = |keys: &[Key]| keys.iter().any(|k| window.is_key_down(*k));
+    Input {
+        left: down(&[Key::Left, Key::A]),
+        right: down(&[Key::Right, Key::D]),
+        up: down(&[Key::Up, Key::W]),
+        down: down(&[Key::Down, Key::S]),
+        button1: down(&[Key::Z, Key::Space]),
+        button2: down(&[Key::X, Key::LeftShift, Key::RightShift]),
+    }
+}
+
+fn load_state(sim: &dyn Simulation, path: &str) -> Result<Box<dyn State + Send>, String> {
+    let json = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    sim.new_state_from_json(&json)
+        .map_err(|e| format!("Cannot load state from {}: {}", path, e))
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let mut sim = toybox::get_simulation_by_name(&args.game)?;
+    if let Some(path) = &args.config {
+        let json = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+        sim = sim
+            .from_json(&json)
+            .map_err(|e| format!("Cannot load config from {}: {}", path, e))?;
+    }
+    if let Some(seed) = args.seed {
+        sim.reset_seed(seed);
+    }
+    let mut state = match &args.state {
+        Some(path) => load_state(sim.as_ref(), path)?,
+        None => sim.new_game(),
+    };
+    let mut recorder = match &args.record {
+        Some(path) => {
+            Some(Recorder::create(path).map_err(|e| format!("Cannot create {}: {}", path, e))?)
+        }
+        None => None,
+    };
+
+    let (w, h) = sim.game_size();
+    let mut window = Window::new(
+        &format!(
+            "toybox: {} (P pause, N step, F5 save, F9 load, R reset)",
+            args.game
+        ),
+        w as usize * args.scale,
+        h as usize * args.scale,
+        WindowOptions::default(),
+    )
+    .map_err(|e| format!("Cannot open window: {}", e))?;
+
+    let mut img = ImageBuffer::alloc(w, h);
+    let mut framebuffer = Vec::new();
+    let mut paused = false;
+    let mut frame: u64 = 0;
+
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            paused = !paused;
+        }
+        if window.is_key_pressed(Key::F5, KeyRepeat::No) {
+            match fs::write(&args.save, state.to_json()) {
+                Ok(()) => println!("Saved state to {}", args.save),
+                Err(e) => eprintln!("Cannot save state to {}: {}", args.save, e),
+            }
+        }
+        if window.is_key_pressed(Key::F9, KeyRepeat::No) {
+            match load_state(sim.as_ref(), &args.save) {
+                Ok(loaded) => state = loaded,
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+        if window.is_key_pressed(Key::R, KeyRepeat::No) {
+            state = sim.new_game();
+        }
+        let step = window.is_key_pressed(Key::N, KeyRepeat::Yes);
+
+        if state.lives() >= 0 && (!paused || step) {
+            let input = read_input(&window);
+            state.update_mut(input);
+            if let Some(recorder) = recorder.as_mut() {
+                recorder
+                    .record(frame, input)
+                    .map_err(|e| format!("Cannot record input: {}", e))?;
+            }
+            frame += 1;
+            if state.lives() < 0 {
+                println!("Game over; score: {}. Press R to restart.", state.score());
+            }
+        }
+
+        let fps = if window.is_key_down(Key::Tab) {
+            args.fps * 4
+        } else {
+            args.fps
+        };
+        window.set_target_fps(fps);
+
+        img.render(&state.draw());
+        blit_scaled(&img, args.scale, &mut framebuffer);
+        window
+            .update_with_buffer(
+                &framebuffer,
+                w as usize * args.scale,
+                h as usize * args.scale,
+            )
+            .map_err(|e| format!("Cannot draw frame: {}", e))?;
+    }
+
+    if let Some(recorder) = recorder.as_mut() {
+        recorder
+            .flush()
+            .map_err(|e| format!("Cannot record input: {}", e))?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let argv: Vec<String> = std::env::args().collect();
+    let args = match Args::parse(&argv) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n{}\ngames: {:?}", e, USAGE, toybox::GAME_LIST);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = run(args) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
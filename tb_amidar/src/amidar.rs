@@ -24,6 +24,9 @@ pub mod screen {
 
     pub const SCORE_Y_POS: i32 = 198;
     pub const SCORE_X_POS: i32 = LIVES_X_POS - LIVES_X_STEP * 3 - 8;
+
+    /// Room left below the default board for the score and lives.
+    pub const HUD_HEIGHT: i32 = 58;
 }
 pub mod raw_images {
     pub const PLAYER_L1: &[u8] = include_bytes!("resources/amidar/player_l1.png");
@@ -88,6 +91,13 @@ mod world {
 pub const AMIDAR_BOARD: &str = include_str!("resources/amidar_default_board");
 pub const AMIDAR_ENEMY_POSITIONS_DATA: &str = include_str!("resources/amidar_enemy_positions");
 
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
 mod inits {
     pub const ENEMY_STARTING_SPEED: i32 = 10;
     pub const PLAYER_SPEED: i32 = 8;
@@ -104,6 +114,183 @@ impl Amidar {
             &self.unpainted_color,
         ]
     }
+    /// Which board does a given (1-based) level use? The last entry of `boards` repeats.
+    pub fn board_for_level(&self, level: i32) -> &[String] {
+        match &self.boards {
+            Some(boards) if !boards.is_empty() => &boards[level_index(level, boards.len())],
+            _ => &self.board,
+        }
+    }
+    /// How fast do enemies move on a given (1-based) level?
+    pub fn enemy_speed_for_level(&self, level: i32) -> i32 {
+        match &self.enemy_speeds {
+            Some(speeds) if !speeds.is_empty() => speeds[level_index(level, speeds.len())],
+            _ => {
+                if level < 3 {
+                    self.enemy_starting_speed
+                } else if level < 5 {
+                    self.enemy_starting_speed + 2
+                } else {
+                    self.enemy_starting_speed + 4
+                }
+            }
+        }
+    }
+    /// Every board this config can reach, in level order.
+    fn level_boards(&self) -> Vec<&[String]> {
+        match &self.boards {
+            Some(boards) if !boards.is_empty() => boards.iter().map(|b| b.as_slice()).collect(),
+            _ => vec![self.board.as_slice()],
+        }
+    }
+    /// Check that every level's board is free of errors and that the speed table is usable; board warnings are allowed.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(boards) = &self.boards {
+            if boards.is_empty() {
+                return Err("boards must list at least one board".to_owned());
+            }
+        }
+        for (i, lines) in self.level_boards().into_iter().enumerate() {
+            let errors: Vec<String> = self
+                .diagnose_board(lines)
+                .iter()
+                .filter(|p| p.severity == Severity::Error)
+                .map(|p| p.to_string())
+                .collect();
+            if !errors.is_empty() {
+                return Err(format!("board for level {}: {}", i + 1, errors.join("; ")));
+            }
+        }
+        if let Some(speeds) = &self.enemy_speeds {
+            if speeds.is_empty() {
+                return Err("enemy_speeds must list at least one speed".to_owned());
+            }
+            if let Some(bad) = speeds.iter().find(|s| **s <= 0) {
+                return Err(format!("enemy_speeds must be positive, found {}", bad));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Amidar {
+    /// How many extra points is a bonus box worth?
+    pub fn bonus_box_score(&self) -> i32 {
+        self.bonus_box_score.unwrap_or(self.box_bonus)
+    }
+    /// Check a board in the format documented on ``Amidar::board`` against this config: errors for every problem
+    /// that would keep it from loading, and warnings for track or markers that can't be used. Empty means it is fine.
+    pub fn diagnose_board(&self, lines: &[String]) -> Vec<BoardDiagnostic> {
+        let board = match Board::parse(lines) {
+            Ok(board) => board,
+            Err(problems) => return problems,
+        };
+        let mut problems = Vec::new();
+        let start = board
+            .player_start
+            .clone()
+            .unwrap_or_else(|| self.player_start.clone());
+        if !board.get_tile(&start).walkable() {
+            problems.push(BoardDiagnostic::at(&start, "player starts off the track"));
+        }
+        let markers = board.enemy_starts.clone().unwrap_or_default();
+        for (i, ai) in self.enemies.iter().enumerate() {
+            if let (None, Some(marker)) = (ai.start(), markers.get(i)) {
+                let kind = match ai {
+                    MovementAI::EnemyLookupAI { .. } => "EnemyLookupAI",
+                    MovementAI::EnemyPerimeterAI { .. } => "EnemyPerimeterAI",
+                    _ => "Player",
+                };
+                problems.push(BoardDiagnostic::warning(
+                    marker,
+                    &format!("enemy {} is {} and ignores this 'E'", i, kind),
+                ));
+            }
+            let enemy_start = match ai {
+                MovementAI::EnemyLookupAI {
+                    default_route_index,
+                    ..
+                } => match DEFAULT_ENEMY_ROUTES.get(*default_route_index as usize) {
+                    Some(route) => board.lookup_position(route[0]),
+                    None => {
+                        problems.push(BoardDiagnostic::new(
+                            1,
+                            1,
+                            &format!("enemy {} uses unknown route {}", i, default_route_index),
+                        ));
+                        continue;
+                    }
+                },
+                MovementAI::EnemyPerimeterAI { .. } => TilePoint::new(0, 0),
+                MovementAI::Player => start.clone(),
+                _ => markers
+                    .get(i)
+                    .or_else(|| ai.start())
+                    .cloned()
+                    .unwrap_or_else(|| start.clone()),
+            };
+            if !board.get_tile(&enemy_start).walkable() {
+                problems.push(BoardDiagnostic::at(
+                    &enemy_start,
+                    &format!("enemy {} starts off the track", i),
+                ));
+            }
+        }
+        for extra in markers.iter().skip(self.enemies.len()) {
+            problems.push(BoardDiagnostic::warning(
+                extra,
+                "no enemy left to start at this 'E'",
+            ));
+        }
+        let bugs_junction = TilePoint::new(31, 18);
+        if self.default_board_bugs && board.get_junction_id(&bugs_junction).is_none() {
+            problems.push(BoardDiagnostic::at(
+                &bugs_junction,
+                "default_board_bugs needs a junction here",
+            ));
+        }
+        problems.extend(board.diagnostics(&start));
+        problems
+    }
+}
+
+impl BoardDiagnostic {
+    fn new(line: usize, column: usize, message: &str) -> BoardDiagnostic {
+        BoardDiagnostic {
+            line,
+            column,
+            message: message.to_owned(),
+            severity: Severity::Error,
+        }
+    }
+    fn at(tile: &TilePoint, message: &str) -> BoardDiagnostic {
+        BoardDiagnostic::new(tile.ty as usize + 1, tile.tx as usize + 1, message)
+    }
+    fn warning(tile: &TilePoint, message: &str) -> BoardDiagnostic {
+        BoardDiagnostic {
+            severity: Severity::Warning,
+            ..BoardDiagnostic::at(tile, message)
+        }
+    }
+}
+
+impl std::fmt::Display for BoardDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let prefix = match self.severity {
+            Severity::Error => "",
+            Severity::Warning => "warning: ",
+        };
+        write!(
+            f,
+            "line {}, column {}: {}{}",
+            self.line, self.column, prefix, self.message
+        )
+    }
+}
+
+/// Index into a per-level table where level 1 is the first entry and the last entry repeats.
+fn level_index(level: i32, len: usize) -> usize {
+    ((level - 1).max(0) as usize).min(len - 1)
 }
 
 impl Default for Amidar {
@@ -136,6 +323,9 @@ impl Default for Amidar {
                 .collect(),
             enemy_starting_speed: inits::ENEMY_STARTING_SPEED,
             player_speed: inits::PLAYER_SPEED,
+            boards: None,
+            enemy_speeds: None,
+            bonus_box_score: None,
         }
     }
 }
@@ -243,25 +433,47 @@ impl Tile {
             '=' => Ok(Tile::Unpainted),
             'p' => Ok(Tile::Painted),
             'c' => Ok(Tile::ChaseMarker),
+            'b' => Ok(Tile::BonusMarker),
             ' ' => Ok(Tile::Empty),
             _ => Err(format!("Cannot construct AmidarTile from '{}'", c)),
         }
     }
+    /// The board-file character for this tile; the inverse of new_from_char.
+    pub fn to_char(self) -> char {
+        match self {
+            Tile::Unpainted => '=',
+            Tile::Painted => 'p',
+            Tile::ChaseMarker => 'c',
+            Tile::BonusMarker => 'b',
+            Tile::Empty => ' ',
+        }
+    }
     pub fn walkable(self) -> bool {
         match self {
             Tile::Empty => false,
-            Tile::ChaseMarker | Tile::Painted | Tile::Unpainted => true,
+            Tile::ChaseMarker | Tile::BonusMarker | Tile::Painted | Tile::Unpainted => true,
         }
     }
     pub fn needs_paint(self) -> bool {
         match self {
             Tile::Painted | Tile::Empty => false,
-            Tile::ChaseMarker | Tile::Unpainted => true,
+            Tile::ChaseMarker | Tile::BonusMarker | Tile::Unpainted => true,
         }
     }
 }
 
 impl MovementAI {
+    /// Where does this AI ask to start, if it gets a choice?
+    fn start(&self) -> Option<&TilePoint> {
+        match self {
+            MovementAI::EnemyAmidarMvmt { start, .. }
+            | MovementAI::EnemyRandomMvmt { start, .. }
+            | MovementAI::EnemyTargetPlayer { start, .. } => Some(start),
+            MovementAI::Player
+            | MovementAI::EnemyLookupAI { .. }
+            | MovementAI::EnemyPerimeterAI { .. } => None,
+        }
+    }
     /// Resetting the mob AI state after player death.
     fn reset(&mut self) {
         match self {
@@ -610,6 +822,7 @@ impl BoardUpdate {
             vertical: 0,
             horizontal: 0,
             num_boxes: 0,
+            bonus_boxes: 0,
             triggers_chase: false,
         }
     }
@@ -618,6 +831,7 @@ impl BoardUpdate {
             || self.vertical != 0
             || self.horizontal != 0
             || self.num_boxes != 0
+            || self.bonus_boxes != 0
             || self.triggers_chase
     }
     fn into_option(self) -> Option<Self> {
@@ -634,23 +848,85 @@ impl Board {
         DEFAULT_BOARD.clone()
     }
     fn try_new(lines: &[String]) -> Result<Board, String> {
+        Board::parse(lines).map_err(|problems| {
+            problems
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>()
+                .join("; ")
+        })
+    }
+    /// Read a board in the format documented on ``Amidar::board``, reporting every character or row that doesn't fit.
+    pub fn parse(lines: &[String]) -> Result<Board, Vec<BoardDiagnostic>> {
+        if lines.is_empty() {
+            return Err(vec![BoardDiagnostic::new(
+                1,
+                1,
+                "board must have at least one row",
+            )]);
+        }
+        let width = lines[0].chars().count();
+        let mut problems = Vec::new();
         let mut tiles = Vec::new();
-        for line in lines {
-            // Rust will aggregate errors in collect for us if we give it a type-hint.
-            let row: Result<Vec<_>, _> = line.chars().map(Tile::new_from_char).collect();
-            // Exit function if row is errorful.
-            tiles.push(row?);
+        let mut player_start = None;
+        let mut enemy_starts = Vec::new();
+        for (y, line) in lines.iter().enumerate() {
+            let mut row = Vec::new();
+            for (x, c) in line.chars().enumerate() {
+                let here = TilePoint::new(x as i32, y as i32);
+                let tile = match c {
+                    'P' => {
+                        if player_start.is_some() {
+                            problems.push(BoardDiagnostic::at(&here, "second player start 'P'"));
+                        }
+                        player_start = Some(here);
+                        Tile::Unpainted
+                    }
+                    'E' => {
+                        enemy_starts.push(here);
+                        Tile::Unpainted
+                    }
+                    _ => match Tile::new_from_char(c) {
+                        Ok(tile) => tile,
+                        Err(msg) => {
+                            problems.push(BoardDiagnostic::at(&here, &msg));
+                            Tile::Empty
+                        }
+                    },
+                };
+                row.push(tile);
+            }
+            if row.len() != width {
+                problems.push(BoardDiagnostic::new(
+                    y + 1,
+                    row.len().min(width) + 1,
+                    &format!(
+                        "row is {} tiles wide but the first row is {}",
+                        row.len(),
+                        width
+                    ),
+                ));
+            }
+            tiles.push(row);
+        }
+        if !problems.is_empty() {
+            return Err(problems);
         }
-        let width = tiles[0].len() as u32;
-        let height = tiles.len() as u32;
 
         let mut board = Board {
             tiles,
-            width,
-            height,
+            width: width as u32,
+            height: lines.len() as u32,
             junctions: HashSet::new(),
             chase_junctions: HashSet::new(),
             boxes: Vec::new(),
+            bonus_junctions: Some(HashSet::new()),
+            player_start,
+            enemy_starts: if enemy_starts.is_empty() {
+                None
+            } else {
+                Some(enemy_starts)
+            },
         };
         board.init_junctions();
         debug_assert!(board.boxes.is_empty());
@@ -662,6 +938,82 @@ impl Board {
         Ok(board)
     }
 
+    /// Find structural problems with a parsed board: an error if it has no track, and warnings for track that can't be
+    /// reached from ``start`` (or from the first track tile, if ``start`` is off the track), dead ends, box markers that
+    /// don't begin a box, and boxes the player can never paint.
+    pub fn diagnostics(&self, start: &TilePoint) -> Vec<BoardDiagnostic> {
+        let mut problems = Vec::new();
+        let track: Vec<TilePoint> = (0..self.height as i32)
+            .flat_map(|ty| (0..self.width as i32).map(move |tx| TilePoint::new(tx, ty)))
+            .filter(|pt| self.get_tile(pt).walkable())
+            .collect();
+        let first = if self.get_tile(start).walkable() {
+            start.clone()
+        } else if let Some(first) = track.first() {
+            first.clone()
+        } else {
+            return vec![BoardDiagnostic::new(1, 1, "board has no track")];
+        };
+
+        // Report each piece of track apart from the one holding ``first`` once, at its first tile.
+        let main = self.flood_fill(&first);
+        let mut reached = main.clone();
+        for pt in &track {
+            let id = self.tile_id(pt).unwrap();
+            if !reached.contains(&id) {
+                problems.push(BoardDiagnostic::warning(
+                    pt,
+                    "track is not connected to the rest of the board",
+                ));
+                reached.extend(self.flood_fill(pt));
+            }
+        }
+
+        for pt in &track {
+            let exits = DIRECTIONS
+                .iter()
+                .filter(|d| self.can_move(pt, **d).is_some())
+                .count();
+            if exits < 2 {
+                problems.push(BoardDiagnostic::warning(pt, "track dead-ends here"));
+            }
+            let tile = self.get_tile(pt);
+            if (tile == Tile::ChaseMarker || tile == Tile::BonusMarker)
+                && !self.boxes.iter().any(|b| &b.top_left == pt)
+            {
+                problems.push(BoardDiagnostic::warning(
+                    pt,
+                    &format!("'{}' is not the top-left corner of a box", tile.to_char()),
+                ));
+            }
+        }
+
+        for b in &self.boxes {
+            let id = self.tile_id(&b.top_left).unwrap();
+            if !main.contains(&id) {
+                problems.push(BoardDiagnostic::warning(
+                    &b.top_left,
+                    "box can't be reached from the player start",
+                ));
+            }
+        }
+        problems
+    }
+
+    /// Every track tile id reachable from ``start`` by walking.
+    fn flood_fill(&self, start: &TilePoint) -> HashSet<u32> {
+        let mut seen = HashSet::new();
+        let mut frontier = vec![start.clone()];
+        while let Some(pt) = frontier.pop() {
+            if let Some(id) = self.tile_id(&pt) {
+                if self.get_tile(&pt).walkable() && seen.insert(id) {
+                    frontier.extend(DIRECTIONS.iter().map(|d| pt.step(*d)));
+                }
+            }
+        }
+        seen
+    }
+
     fn is_corner(&self, tx: i32, ty: i32) -> bool {
         let last_y = (self.height as i32) - 1;
         let last_x = (self.width as i32) - 1;
@@ -776,6 +1128,11 @@ impl Board {
                         if cell == &Tile::ChaseMarker {
                             self.chase_junctions.insert(y * self.width + x);
                         }
+                        if cell == &Tile::BonusMarker {
+                            self.bonus_junctions
+                                .get_or_insert_with(HashSet::new)
+                                .insert(y * self.width + x);
+                        }
                     }
                 }
             }
@@ -856,8 +1213,8 @@ impl Board {
     }
 
     /// Check whether the painting of segment t1 .. t2 filled any boxes, and return a tuple of
-    /// (triggers_chase, count) if so.
-    fn check_box_painting(&mut self, t1: &TilePoint, t2: &TilePoint) -> (bool, i32) {
+    /// (triggers_chase, count, bonus_count) if so.
+    fn check_box_painting(&mut self, t1: &TilePoint, t2: &TilePoint) -> (bool, i32, i32) {
         let indices: Vec<usize> = self
             .boxes
             .iter()
@@ -868,12 +1225,16 @@ impl Board {
             .collect();
 
         let updated = indices.len() as i32;
+        let mut bonus = 0;
         let mut chase_change = false;
         for i in indices {
             self.boxes[i].painted = true;
             if self.boxes[i].triggers_chase {
                 chase_change = true;
             }
+            if self.is_bonus_box(&self.boxes[i]) {
+                bonus += 1;
+            }
         }
 
         let triggers_chase = chase_change
@@ -883,7 +1244,14 @@ impl Board {
                 .filter(|b| b.triggers_chase)
                 .all(|b| b.painted);
 
-        (triggers_chase, updated)
+        (triggers_chase, updated, bonus)
+    }
+
+    fn is_bonus_box(&self, gb: &GridBox) -> bool {
+        match (&self.bonus_junctions, self.tile_id(&gb.top_left)) {
+            (Some(bonus), Some(id)) => bonus.contains(&id),
+            _ => false,
+        }
     }
 
     fn check_paint(&mut self, player_history: &mut VecDeque<u32>) -> BoardUpdate {
@@ -914,8 +1282,10 @@ impl Board {
                     } else {
                         score_change.horizontal += (t2.tx - t1.tx).abs();
                     }
-                    let (triggers_chase, boxes_painted) = self.check_box_painting(&t1, &t2);
+                    let (triggers_chase, boxes_painted, bonus_painted) =
+                        self.check_box_painting(&t1, &t2);
                     score_change.num_boxes += boxes_painted;
+                    score_change.bonus_boxes += bonus_painted;
                     score_change.triggers_chase = triggers_chase;
                     score_change.junctions = Some((*start, *end));
                 }
@@ -977,15 +1347,21 @@ impl Board {
 
 impl State {
     pub fn try_new(config: &Amidar) -> Result<State, String> {
-        let board = Board::try_new(&config.board)?;
+        config.validate()?;
+        let board = Board::try_new(config.board_for_level(1))?;
         let mut config = config.clone();
 
+        let enemy_speed = config.enemy_speed_for_level(1);
         let enemies = config
             .enemies
             .iter()
-            .map(|ai| board.make_enemy(ai.clone(), config.enemy_starting_speed))
+            .map(|ai| board.make_enemy(ai.clone(), enemy_speed))
             .collect();
-        let player = Mob::new_player(config.player_start.to_world(), config.player_speed);
+        let player_start = board
+            .player_start
+            .clone()
+            .unwrap_or_else(|| config.player_start.clone());
+        let player = Mob::new_player(player_start.to_world(), config.player_speed);
 
         let core = StateCore {
             rand: random::Gen::new_child(&mut config.rand),
@@ -1007,10 +1383,17 @@ impl State {
         state.reset();
         Ok(state)
     }
-    pub fn reset(&mut self) {
+    /// Where does the player start on the current board?
+    pub fn player_start(&self) -> TilePoint {
         self.state
-            .player
-            .reset(&self.config.player_start, &self.state.board);
+            .board
+            .player_start
+            .clone()
+            .unwrap_or_else(|| self.config.player_start.clone())
+    }
+    pub fn reset(&mut self) {
+        let player_start = self.player_start();
+        self.state.player.reset(&player_start, &self.state.board);
         // On the default board, we imagine starting from below the initial place.
         // This way going up paints the first segment.
         if self.config.default_board_bugs {
@@ -1021,9 +1404,42 @@ impl State {
                     .unwrap(),
             );
         }
-        for enemy in &mut self.state.enemies {
-            enemy.reset(&self.config.player_start, &self.state.board);
+        let markers = self.state.board.enemy_starts.clone().unwrap_or_default();
+        for (i, enemy) in self.state.enemies.iter_mut().enumerate() {
+            enemy.reset(&player_start, &self.state.board);
+            // Enemies that choose where to start take their board marker, if there is one.
+            if let (Some(_), Some(marker)) = (enemy.ai.start(), markers.get(i)) {
+                enemy.position = marker.to_world();
+            }
+        }
+    }
+    /// Print the board in its file format, with the player as 'P' and enemies as 'E' (or 'e' once caught).
+    pub fn text_grid(&self) -> Vec<String> {
+        let mut grid: Vec<Vec<char>> = self
+            .state
+            .board
+            .tiles
+            .iter()
+            .map(|row| row.iter().map(|t| t.to_char()).collect())
+            .collect();
+        let mobs = self
+            .state
+            .enemies
+            .iter()
+            .map(|e| (e, if e.caught { 'e' } else { 'E' }))
+            .chain(std::iter::once((&self.state.player, 'P')));
+        for (mob, ch) in mobs {
+            let tile = mob.position.to_tile();
+            if let Some(cell) = grid
+                .get_mut(tile.ty as usize)
+                .and_then(|row| row.get_mut(tile.tx as usize))
+            {
+                *cell = ch;
+            }
         }
+        grid.into_iter()
+            .map(|row| row.into_iter().collect())
+            .collect()
     }
     pub fn board_size(&self) -> WorldPoint {
         let th = self.state.board.height as i32;
@@ -1056,8 +1472,20 @@ impl toybox_core::Simulation for Amidar {
     fn reset_seed(&mut self, seed: u32) {
         self.rand.reset_seed(seed)
     }
+    /// The default screen, grown to fit the largest board any level uses.
     fn game_size(&self) -> (i32, i32) {
-        screen::GAME_SIZE
+        let (tile_w, tile_h) = screen::TILE_SIZE;
+        let (offset_x, offset_y) = screen::BOARD_OFFSET;
+        self.level_boards()
+            .into_iter()
+            .fold(screen::GAME_SIZE, |(w, h), lines| {
+                let cols = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as i32;
+                let rows = lines.len() as i32;
+                (
+                    w.max(offset_x * 2 + cols * tile_w),
+                    h.max(offset_y + rows * tile_h + screen::HUD_HEIGHT),
+                )
+            })
     }
     fn new_game(&mut self) -> Box<dyn toybox_core::State + Send> {
         Box::new(State::try_new(self).expect("new_game should succeed."))
@@ -1100,6 +1528,7 @@ impl toybox_core::Simulation for Amidar {
         json_config: &str,
     ) -> Result<Box<dyn toybox_core::Simulation + Send>, serde_json::Error> {
         let config: Amidar = serde_json::from_str(json_config)?;
+        config.validate().map_err(serde::de::Error::custom)?;
         Ok(Box::new(config))
     }
 
@@ -1152,6 +1581,7 @@ where
                 // max 1 point for vertical, for some reason.
                 self.state.score += score_change.vertical.signum();
                 self.state.score += self.config.box_bonus * score_change.num_boxes;
+                self.state.score += self.config.bonus_box_score() * score_change.bonus_boxes;
             }
 
             if score_change.triggers_chase {
@@ -1226,14 +1656,15 @@ where
             self.reset();
         } else {
             if self.state.board.board_complete() {
-                self.reset();
                 // Increment the level
                 self.state.level += 1;
                 // If we triggered the chase counter immediately before
                 // advancing, it will still be on and will mess up the sprites. Reset to 0.
                 self.state.chase_timer = 0;
-                // Time to paint again!
-                self.state.board = Board::fast_new();
+                // Time to paint again, on whichever board this level uses!
+                self.state.board = Board::try_new(self.config.board_for_level(self.state.level))
+                    .expect("Level boards are checked by validate.");
+                self.reset();
                 // If you successfully complete a level, you can get a life back (up the maximum)
                 if self.lives() < self.config.start_lives {
                     self.state.lives += 1;
@@ -1244,16 +1675,7 @@ where
                     // of what level 3 looks like, so we are leaving this blank for now.
                 }
                 // Increase enemy speed.
-                // Make pretty later
-                let new_speed = {
-                    if self.state.level < 3 {
-                        self.config.enemy_starting_speed
-                    } else if self.state.level < 5 {
-                        self.config.enemy_starting_speed + 2
-                    } else {
-                        self.config.enemy_starting_speed + 4
-                    }
-                };
+                let new_speed = self.config.enemy_speed_for_level(self.state.level);
                 for e in &mut self.state.enemies {
                     e.change_speed(new_speed);
                 }
@@ -1290,7 +1712,7 @@ where
                                 &images::BLOCK_TILE_PAINTED_L2
                             }
                         }
-                        &Tile::Unpainted | &Tile::ChaseMarker => {
+                        &Tile::Unpainted | &Tile::ChaseMarker | &Tile::BonusMarker => {
                             if ghosts {
                                 &images::BLOCK_TILE_UNPAINTED_L1
                             } else {
@@ -1307,7 +1729,9 @@ where
                 } else {
                     let tile_color = match tile {
                         &Tile::Painted => self.config.painted_color,
-                        &Tile::Unpainted | &Tile::ChaseMarker => self.config.unpainted_color,
+                        &Tile::Unpainted | &Tile::ChaseMarker | &Tile::BonusMarker => {
+                            self.config.unpainted_color
+                        }
                         &Tile::Empty => continue,
                     };
                     output.push(Drawable::rect(
@@ -1425,16 +1849,18 @@ where
             }
         }
 
+        // Keep the score and lives pinned to the bottom of the screen, below the tallest board.
+        let hud_shift = toybox_core::Simulation::game_size(&self.config).1 - screen::GAME_SIZE.1;
         output.extend(draw_score(
             self.state.score,
             screen::SCORE_X_POS,
-            screen::SCORE_Y_POS + 1,
+            screen::SCORE_Y_POS + hud_shift + 1,
         ));
         for i in 0..self.state.lives {
             output.push(Drawable::rect(
                 self.config.player_color,
                 screen::LIVES_X_POS - i * screen::LIVES_X_STEP,
-                screen::LIVES_Y_POS,
+                screen::LIVES_Y_POS + hud_shift,
                 1,
                 DIGIT_HEIGHT + 1,
             ))
@@ -1514,6 +1940,7 @@ where
                 let tile = state.player.position.to_tile();
                 serde_json::to_string(&(tile.tx, tile.ty))?
             }
+            "text_grid" => serde_json::to_string(&self.text_grid())?,
             _ => Err(QueryError::NoSuchQuery)?,
         })
     }
@@ -1525,7 +1952,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use toybox_core::State;
+    use toybox_core::{Simulation, State};
 
     #[test]
     fn test_colors_unique_in_gray() {
@@ -1618,6 +2045,257 @@ mod tests {
         .unwrap()
     }
 
+    fn ring_board(width: usize, height: usize) -> Vec<String> {
+        (0..height)
+            .map(|y| {
+                if y == 0 || y == height - 1 {
+                    "=".repeat(width)
+                } else {
+                    format!("={}=", " ".repeat(width - 2))
+                }
+            })
+            .collect()
+    }
+
+    fn ring_config(boards: Vec<Vec<String>>) -> Amidar {
+        Amidar {
+            boards: Some(boards),
+            enemies: vec![MovementAI::EnemyPerimeterAI {
+                start: TilePoint::new(0, 0),
+            }],
+            player_start: TilePoint::new(0, 2),
+            default_board_bugs: false,
+            ..Amidar::default()
+        }
+    }
+
+    #[test]
+    fn test_level_progression_uses_level_boards() {
+        let config = Amidar {
+            enemy_speeds: Some(vec![3, 5]),
+            ..ring_config(vec![ring_board(5, 3), ring_board(7, 5)])
+        };
+        let mut state = super::State::try_new(&config).unwrap();
+        assert_eq!((5, 3), (state.state.board.width, state.state.board.height));
+        assert_eq!(3, state.state.enemies[0].speed);
+
+        for level in 2..4 {
+            for row in state.state.board.tiles.iter_mut() {
+                for tile in row.iter_mut().filter(|t| t.walkable()) {
+                    *tile = Tile::Painted;
+                }
+            }
+            state.update_mut(Input::default());
+            assert_eq!(level, state.level());
+            // The last board and speed repeat for every later level.
+            assert_eq!((7, 5), (state.state.board.width, state.state.board.height));
+            assert!(!state.state.board.board_complete());
+            assert_eq!(5, state.state.enemies[0].speed);
+        }
+    }
+
+    #[test]
+    fn test_default_enemy_speed_ladder() {
+        let config = Amidar::default();
+        let speeds: Vec<i32> = (1..7).map(|l| config.enemy_speed_for_level(l)).collect();
+        let s = config.enemy_starting_speed;
+        assert_eq!(vec![s, s, s + 2, s + 2, s + 4, s + 4], speeds);
+    }
+
+    #[test]
+    fn test_game_size_fits_board() {
+        assert_eq!(screen::GAME_SIZE, Amidar::default().game_size());
+        let config = Amidar {
+            boards: Some(vec![ring_board(5, 3), ring_board(40, 40)]),
+            ..Amidar::default()
+        };
+        assert_eq!((16 * 2 + 40 * 4, 37 + 40 * 5 + 58), config.game_size());
+    }
+
+    #[test]
+    fn test_validate_level_boards() {
+        assert!(Amidar::default().validate().is_ok());
+        assert!(ring_config(vec![ring_board(5, 3)]).validate().is_ok());
+        let bad_board = ring_config(vec![ring_board(5, 3), vec!["=x=".to_owned()]]);
+        assert!(bad_board.validate().unwrap_err().contains("level 2"));
+        let no_boards = Amidar {
+            boards: Some(Vec::new()),
+            ..Amidar::default()
+        };
+        assert!(no_boards.validate().is_err());
+        let bad_speed = Amidar {
+            enemy_speeds: Some(vec![4, 0]),
+            ..Amidar::default()
+        };
+        assert!(bad_speed.validate().is_err());
+    }
+
+    fn lines(rows: &[&str]) -> Vec<String> {
+        rows.iter().map(|r| r.to_string()).collect()
+    }
+
+    fn has_problem(problems: &[BoardDiagnostic], line: usize, column: usize, text: &str) -> bool {
+        problems
+            .iter()
+            .any(|p| p.line == line && p.column == column && p.message.contains(text))
+    }
+
+    #[test]
+    fn test_board_parse_errors_have_positions() {
+        let problems = Board::parse(&lines(&["==x", "=="])).err().unwrap();
+        assert_eq!(2, problems.len());
+        assert!(has_problem(&problems, 1, 3, "'x'"));
+        assert!(has_problem(&problems, 2, 3, "2 tiles wide"));
+        let err = Board::try_new(&lines(&["==x"])).err().unwrap();
+        assert!(err.starts_with("line 1, column 3:"));
+    }
+
+    #[test]
+    fn test_board_diagnostics() {
+        let config = Amidar {
+            enemies: vec![
+                MovementAI::EnemyRandomMvmt {
+                    start: TilePoint::new(9, 9),
+                    start_dir: Direction::Right,
+                    dir: Direction::Right,
+                },
+                MovementAI::EnemyRandomMvmt {
+                    start: TilePoint::new(1, 1),
+                    start_dir: Direction::Right,
+                    dir: Direction::Right,
+                },
+            ],
+            ..ring_config(Vec::new())
+        };
+        let board = lines(&[
+            "=P==c  =", //
+            "=   =   ", "==E==   ", "  =     ",
+        ]);
+        let problems = config.diagnose_board(&board);
+        // The first enemy takes the 'E'; the second keeps its own start, which is off the track.
+        assert!(has_problem(&problems, 2, 2, "enemy 1 starts off the track"));
+        assert!(has_problem(&problems, 1, 8, "not connected"));
+        assert!(has_problem(&problems, 1, 8, "dead-ends"));
+        assert!(has_problem(&problems, 4, 3, "dead-ends"));
+        assert!(has_problem(
+            &problems,
+            1,
+            5,
+            "'c' is not the top-left corner"
+        ));
+        assert_eq!(5, problems.len(), "{:?}", problems);
+        // Only the enemy off the track keeps the board from loading.
+        let errors: Vec<&BoardDiagnostic> = problems
+            .iter()
+            .filter(|p| p.severity == Severity::Error)
+            .collect();
+        assert_eq!(1, errors.len());
+        assert_eq!(2, errors[0].line);
+        let config = Amidar {
+            boards: Some(vec![board]),
+            ..config
+        };
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("enemy 1 starts off the track"));
+        assert!(!err.contains("dead-ends"));
+
+        // The player is stuck on the inner loop, away from the only box.
+        let config = Amidar {
+            player_start: TilePoint::new(2, 2),
+            ..ring_config(Vec::new())
+        };
+        let problems = config.diagnose_board(&lines(&[
+            "=======", //
+            "=     =", "= === =", "= = = =", "= === =", "=     =", "=======",
+        ]));
+        assert!(has_problem(&problems, 1, 1, "not connected"));
+        assert!(has_problem(&problems, 1, 1, "box can't be reached"));
+        assert_eq!(2, problems.len(), "{:?}", problems);
+        assert!(problems.iter().all(|p| p.severity == Severity::Warning));
+        assert_eq!(
+            "line 1, column 1: warning: box can't be reached from the player start",
+            problems[1].to_string()
+        );
+    }
+
+    #[test]
+    fn test_board_warnings_still_load() {
+        // A spur off the ring dead-ends, and the perimeter enemy can't use the 'E'.
+        let config = ring_config(vec![lines(&[
+            "=====", //
+            "=   =", "=====", "  E  ",
+        ])]);
+        let problems = config.diagnose_board(config.board_for_level(1));
+        assert!(has_problem(&problems, 4, 3, "dead-ends"));
+        assert!(has_problem(
+            &problems,
+            4,
+            3,
+            "enemy 0 is EnemyPerimeterAI and ignores this 'E'"
+        ));
+        assert!(problems.iter().all(|p| p.severity == Severity::Warning));
+        config.validate().unwrap();
+        let state = super::State::try_new(&config).unwrap();
+        assert_eq!(
+            TilePoint::new(0, 0),
+            state.state.enemies[0].position.to_tile()
+        );
+    }
+
+    #[test]
+    fn test_board_markers() {
+        let config = Amidar {
+            enemies: vec![MovementAI::EnemyRandomMvmt {
+                start: TilePoint::new(0, 0),
+                start_dir: Direction::Right,
+                dir: Direction::Right,
+            }],
+            ..ring_config(vec![lines(&[
+                "b======", //
+                "=     =", "=     P", "=     =", "===E===",
+            ])])
+        };
+        config.validate().unwrap();
+        let state = super::State::try_new(&config).unwrap();
+        assert_eq!((6, 2), player_tile(&state));
+        assert_eq!(
+            TilePoint::new(3, 4),
+            state.state.enemies[0].position.to_tile()
+        );
+
+        let mut board = state.state.board.clone();
+        for row in board.tiles.iter_mut() {
+            for tile in row.iter_mut().filter(|t| t.walkable()) {
+                *tile = Tile::Painted;
+            }
+        }
+        let (_, boxes, bonus) =
+            board.check_box_painting(&TilePoint::new(0, 0), &TilePoint::new(6, 0));
+        assert_eq!((1, 1), (boxes, bonus));
+        assert_eq!(config.box_bonus, config.bonus_box_score());
+    }
+
+    #[test]
+    fn test_text_grid_matches_board() {
+        let state = super::State::try_new(&Amidar::default()).unwrap();
+        let grid: Vec<String> = serde_json::from_str(
+            &state
+                .query_json("text_grid", &serde_json::Value::Null)
+                .unwrap(),
+        )
+        .unwrap();
+        let (px, py) = player_tile(&state);
+        for (y, (row, expected)) in grid.iter().zip(AMIDAR_BOARD.lines()).enumerate() {
+            for (x, (ch, board_ch)) in row.chars().zip(expected.chars()).enumerate() {
+                if (x as i32, y as i32) == (px, py) {
+                    assert_eq!('P', ch);
+                } else if ch != 'E' {
+                    assert_eq!(board_ch, ch);
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_q_num_tiles_unpainted() {
         let mut state = super::State::try_new(&Amidar::default()).unwrap();
@@ -12,4 +12,6 @@ mod digit_sprites;
 mod types;
 
 pub use crate::types::Amidar;
+pub use crate::types::BoardDiagnostic;
+pub use crate::types::Severity;
 pub use crate::types::State;
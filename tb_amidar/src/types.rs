@@ -11,7 +11,16 @@ use std::collections::{HashSet, VecDeque};
 pub struct Amidar {
     /// The random number generator that seeds new games.
     pub rand: random::Gen,
-    /// A representation of the board as a list of strings.
+    /// A representation of the board as a list of strings, one row of tiles per string:
+    ///
+    /// - `=` is unpainted track, `p` is track that starts painted and ` ` is the empty space between tracks.
+    /// - `c` is track marking the top-left corner of a chase-trigger box; painting every one of them starts chase mode.
+    /// - `b` is track marking the top-left corner of a bonus box, worth `bonus_box_score` extra points when filled.
+    /// - `P` is track where the player starts, overriding `player_start`.
+    /// - `E` is track where an enemy starts: the n-th `E` in reading order places the n-th enemy, if its AI has a `start`.
+    ///
+    /// Rows must all be the same width and everyone must start on the track; `validate` reports any such error by line and column.
+    /// Dead ends, disconnected track and boxes the player can't reach are only warnings from `diagnose_board`.
     pub board: Vec<String>,
     /// Where does the player start on a new life?
     pub player_start: TilePoint,
@@ -51,6 +60,12 @@ pub struct Amidar {
     pub enemy_starting_speed: i32,
     /// How fast does the player move?
     pub player_speed: i32,
+    /// Optional per-level boards: level 1 uses the first entry and the last entry repeats. When missing, every level replays `board`.
+    pub boards: Option<Vec<Vec<String>>>,
+    /// Optional per-level enemy speeds: level 1 uses the first entry and the last entry repeats. When missing, enemies speed up by 2 at level 3 and again at level 5.
+    pub enemy_speeds: Option<Vec<i32>>,
+    /// How many extra points do you get for filling a bonus box? Defaults to `box_bonus`.
+    pub bonus_box_score: Option<i32>,
 }
 
 /// When things are drawn, they are drawn in screen coordinates, i.e., pixels.
@@ -97,6 +112,8 @@ pub enum Tile {
     ChaseMarker,
     /// During play, the same as Empty; Walkable tiles you haven't painted yet. Used to form GridBox objects with triggers_chase=true.
     Painted,
+    /// During play, the same as Unpainted. Marks the top-left corner of a bonus box.
+    BonusMarker,
 }
 
 /// MovementAI represents Mob (enemy/player) logic for movement.
@@ -185,6 +202,34 @@ pub struct Board {
     pub chase_junctions: HashSet<u32>,
     /// The list of boxes (inside-portions) of the board.
     pub boxes: Vec<GridBox>,
+    /// Which junctions mark bonus boxes?
+    pub bonus_junctions: Option<HashSet<u32>>,
+    /// Where does the board say the player starts, if it has a `P`?
+    pub player_start: Option<TilePoint>,
+    /// Where does the board say enemies start, from its `E` markers in reading order?
+    pub enemy_starts: Option<Vec<TilePoint>>,
+}
+
+/// How serious is a ``BoardDiagnostic``?
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum Severity {
+    /// The board can't be played with this config, so `Amidar::validate` rejects it.
+    Error,
+    /// The board plays, but some of it may not behave as intended.
+    Warning,
+}
+
+/// A problem found while checking an Amidar board, located by the 1-based line and column of its text.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct BoardDiagnostic {
+    /// Which row of the board (1-based)?
+    pub line: usize,
+    /// Which character of that row (1-based)?
+    pub column: usize,
+    /// What is wrong there?
+    pub message: String,
+    /// Does this stop the board from loading?
+    pub severity: Severity,
 }
 
 /// This struct is temporarily used inside of the game logic, to ensure purely-functional behavior in certain points. Encodes any changes to the board that could happen in a single update.
@@ -196,6 +241,8 @@ pub struct BoardUpdate {
     pub horizontal: i32,
     /// The number of boxes filled in.
     pub num_boxes: i32,
+    /// How many of those boxes were bonus boxes.
+    pub bonus_boxes: i32,
     /// Whether we just triggered chase mode or not.
     pub triggers_chase: bool,
     /// If we just painted something, the start junction and the end junction as a tuple!
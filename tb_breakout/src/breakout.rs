@@ -2,11 +2,13 @@ use super::font::{draw_lives, draw_score, DIGIT_WIDTH};
 use crate::Body2D;
 use crate::Vec2D;
 use access_json::JSONQuery;
-use ordered_float::NotNan;
 use toybox_core;
+use toybox_core::collision::{sweep_circle_aabb, Aabb, Rect, SpatialGrid};
+use toybox_core::fixed::Fixed;
 use toybox_core::graphics::{Color, Drawable};
 use toybox_core::random;
-use toybox_core::{AleAction, Input, QueryError};
+use toybox_core::scalar::Scalar;
+use toybox_core::{AleAction, AnalogInput, ContinuousAxis, Input, QueryError};
 
 use serde_json;
 
@@ -77,6 +79,28 @@ mod screen {
 
     pub const BALL_ANGLE_MIN: f64 = 30.0;
     pub const BALL_ANGLE_RANGE: f64 = 120.0;
+
+    /// Power-ups are drawn as small bars, falling this many pixels per frame.
+    pub const POWER_UP_SIZE: (i32, i32) = (8, 4);
+    pub const POWER_UP_SPEED: f64 = 1.0;
+    /// The color of a falling wide-paddle power-up.
+    pub const WIDE_PADDLE_COLOR: (u8, u8, u8) = (84, 138, 210);
+    /// How much a wide-paddle power-up scales the level's paddle width.
+    pub const WIDE_PADDLE_SCALE: f64 = 1.5;
+    /// The color of a falling extra-life power-up.
+    pub const EXTRA_LIFE_COLOR: (u8, u8, u8) = (236, 236, 236);
+
+    /// Special bricks carry a mark this size in their middle; power-up bricks mark it in their power-up's color.
+    pub const BRICK_MARK_SIZE: (i32, i32) = (4, 2);
+    /// The mark of a brick that speeds the ball up.
+    pub const SPEED_UP_MARK_COLOR: (u8, u8, u8) = (252, 252, 84);
+    /// The mark of a brick that slows the ball down.
+    pub const SLOW_DOWN_MARK_COLOR: (u8, u8, u8) = (24, 26, 167);
+    /// The mark of a brick that splits the ball.
+    pub const SPLIT_MARK_COLOR: (u8, u8, u8) = (214, 92, 214);
+
+    /// How long the board stays empty after the wall is cleared, by default.
+    pub const LEVEL_TRANSITION_FRAMES: u32 = 60;
 }
 
 impl Breakout {
@@ -93,6 +117,254 @@ impl Breakout {
         let (w, _h) = screen::GAME_SIZE;
         Body2D::new_pos(f64::from(w) / 2.0, screen::PADDLE_START_Y.into())
     }
+
+    /// Which entry of ``levels`` applies to a 1-based level number, if any.
+    pub fn level_config(&self, level: i32) -> Option<&LevelConfig> {
+        let levels = self.levels.as_ref()?;
+        if levels.is_empty() {
+            return None;
+        }
+        let index = (level.max(1) - 1) as usize;
+        if self.loop_levels.unwrap_or(false) {
+            levels.get(index % levels.len())
+        } else {
+            levels.get(index.min(levels.len() - 1))
+        }
+    }
+    /// The wall of bricks used on a level.
+    pub fn brick_layout_at(&self, level: i32) -> Option<&BrickLayout> {
+        self.level_config(level)
+            .and_then(|l| l.brick_layout.as_ref())
+            .or(self.brick_layout.as_ref())
+    }
+    /// The ball speed at the start of a level.
+    pub fn ball_speed_slow_at(&self, level: i32) -> f64 {
+        self.level_config(level)
+            .and_then(|l| l.ball_speed_slow)
+            .unwrap_or(self.ball_speed_slow)
+    }
+    /// The ball speed on a level after breaking through to ``ball_speed_row_depth_at``.
+    pub fn ball_speed_fast_at(&self, level: i32) -> f64 {
+        self.level_config(level)
+            .and_then(|l| l.ball_speed_fast)
+            .unwrap_or(self.ball_speed_fast)
+    }
+    /// How deep the ball must break through on a level to speed up.
+    pub fn ball_speed_row_depth_at(&self, level: i32) -> u32 {
+        self.level_config(level)
+            .and_then(|l| l.ball_speed_row_depth)
+            .unwrap_or(self.ball_speed_row_depth)
+    }
+    /// The paddle width at the start of a level, and after each lost ball.
+    pub fn paddle_width_at(&self, level: i32) -> f64 {
+        self.level_config(level)
+            .and_then(|l| l.paddle_width)
+            .unwrap_or_else(|| screen::PADDLE_START_SIZE.0.into())
+    }
+
+    /// Build the wall of bricks for a new game, column by column, from ``brick_layout`` or the first of ``levels``.
+    pub fn make_bricks(&self) -> Vec<Brick> {
+        self.make_bricks_at(1)
+    }
+
+    /// Build the wall of bricks for a level, column by column.
+    pub fn make_bricks_at(&self, level: i32) -> Vec<Brick> {
+        let offset = Vec2D::new(
+            screen::BOARD_LEFT_X.into(),
+            (screen::BOARD_TOP_Y + screen::ROOF_SPACING).into(),
+        );
+        let bsize = Vec2D::new(screen::BRICK_WIDTH.into(), screen::BRICK_HEIGHT.into());
+        let grid_position = |row: usize, col: i32| {
+            Vec2D::new(f64::from(col) * bsize.x, (row as f64) * bsize.y).translate(&offset)
+        };
+        let mut bricks = Vec::new();
+
+        match self.brick_layout_at(level) {
+            None => {
+                let num_bricks_deep = self.row_colors.len();
+                for xi in 0..screen::BRICKS_ACROSS {
+                    for yi in 0..num_bricks_deep {
+                        let color_tuple = self.row_colors[yi];
+                        let score = self.row_scores[yi];
+                        // Reverse depth:
+                        let depth = num_bricks_deep - yi - 1;
+                        bricks.push(Brick::new(
+                            yi as i32,
+                            xi,
+                            grid_position(yi, xi),
+                            bsize.clone(),
+                            score,
+                            color_tuple,
+                            depth as u32,
+                        ));
+                    }
+                }
+            }
+            Some(BrickLayout::Ascii(rows)) => {
+                let num_bricks_deep = rows.len();
+                let num_across = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0);
+                for xi in 0..num_across {
+                    for (yi, row) in rows.iter().enumerate() {
+                        let ch = match row.chars().nth(xi) {
+                            None | Some('.') | Some(' ') => continue,
+                            Some(ch) => ch,
+                        };
+                        let style = yi % self.row_colors.len().max(1);
+                        let depth = num_bricks_deep - yi - 1;
+                        let mut brick = Brick::new(
+                            yi as i32,
+                            xi as i32,
+                            grid_position(yi, xi as i32),
+                            bsize.clone(),
+                            self.row_scores.get(style).cloned().unwrap_or(0),
+                            self.row_colors
+                                .get(style)
+                                .cloned()
+                                .unwrap_or(self.frame_color),
+                            depth as u32,
+                        );
+                        match ch {
+                            '#' => {
+                                brick.destructible = false;
+                                brick.color = self.frame_color;
+                            }
+                            '2'..='9' => {
+                                brick = brick.with_hit_points(ch.to_digit(10).unwrap());
+                            }
+                            '>' => brick.effect = Some(BrickEffect::SpeedUp),
+                            '<' => brick.effect = Some(BrickEffect::SlowDown),
+                            '*' => brick.effect = Some(BrickEffect::Split),
+                            '+' => {
+                                brick.effect = Some(BrickEffect::PowerUp(PowerUpKind::WidePaddle))
+                            }
+                            '^' => {
+                                brick.effect = Some(BrickEffect::PowerUp(PowerUpKind::ExtraLife))
+                            }
+                            _ => {}
+                        }
+                        bricks.push(brick);
+                    }
+                }
+            }
+            Some(BrickLayout::Bricks(specs)) => {
+                for spec in specs {
+                    let mut brick = Brick::new(
+                        ((spec.position.y - offset.y) / bsize.y).floor() as i32,
+                        ((spec.position.x - offset.x) / bsize.x).floor() as i32,
+                        spec.position.clone(),
+                        spec.size.clone(),
+                        spec.points,
+                        spec.color,
+                        spec.depth,
+                    );
+                    brick.destructible = spec.destructible;
+                    brick.effect = spec.effect;
+                    if let Some(hit_points) = spec.hit_points {
+                        brick = brick.with_hit_points(hit_points);
+                    }
+                    bricks.push(brick);
+                }
+            }
+        }
+        bricks
+    }
+
+    /// Check that every brick from ``brick_layout`` and ``levels`` lies inside the board and above the paddle, and that each level is playable.
+    pub fn validate(&self) -> Result<(), String> {
+        self.validate_bricks(&self.make_bricks())?;
+        if let Some(levels) = &self.levels {
+            if levels.is_empty() {
+                return Err("levels must not be empty; use None for the default.".to_string());
+            }
+            for (i, level) in levels.iter().enumerate() {
+                let number = (i + 1) as i32;
+                let speeds = [
+                    self.ball_speed_slow_at(number),
+                    self.ball_speed_fast_at(number),
+                    self.paddle_width_at(number),
+                ];
+                if !speeds.iter().all(|x| *x > 0.0) {
+                    return Err(format!(
+                        "Level {} must have positive ball speeds and paddle width.",
+                        number
+                    ));
+                }
+                if level.brick_layout.is_some() {
+                    self.validate_bricks(&self.make_bricks_at(number))
+                        .map_err(|e| format!("Level {}: {}", number, e))?;
+                }
+            }
+        }
+        match self.paddle_model {
+            None | Some(PaddleModel::Instant) => {}
+            Some(PaddleModel::Momentum {
+                acceleration,
+                max_speed,
+                friction,
+            }) => {
+                if !(acceleration > 0.0 && max_speed > 0.0) {
+                    return Err(
+                        "paddle_model Momentum needs positive acceleration and max_speed."
+                            .to_string(),
+                    );
+                }
+                if !(0.0..=1.0).contains(&friction) {
+                    return Err(format!(
+                        "paddle_model Momentum friction must be in 0..1, not {}.",
+                        friction
+                    ));
+                }
+            }
+            Some(PaddleModel::Analog { max_speed }) => {
+                if max_speed <= 0.0 || max_speed.is_nan() {
+                    return Err("paddle_model Analog needs a positive max_speed.".to_string());
+                }
+            }
+        }
+        if let Some(spin) = self.paddle_spin {
+            if !spin.is_finite() {
+                return Err(format!(
+                    "paddle_spin must be a finite number, not {}.",
+                    spin
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_bricks(&self, bricks: &[Brick]) -> Result<(), String> {
+        let (left, right) = (
+            f64::from(screen::BOARD_LEFT_X),
+            f64::from(screen::BOARD_RIGHT_X),
+        );
+        let (top, bottom) = (
+            f64::from(screen::BOARD_TOP_Y),
+            f64::from(screen::PADDLE_START_Y),
+        );
+        for (i, brick) in bricks.iter().enumerate() {
+            let (x, y) = (brick.position.x, brick.position.y);
+            let (w, h) = (brick.size.x, brick.size.y);
+            if !(w > 0.0 && h > 0.0) {
+                return Err(format!("Brick {} has an empty size: {}x{}.", i, w, h));
+            }
+            if brick.hit_points == Some(0) {
+                return Err(format!("Brick {} must take at least one hit.", i));
+            }
+            if brick.effect == Some(BrickEffect::Split) && !brick.destructible {
+                return Err(format!(
+                    "Brick {} can't split the ball: it is indestructible.",
+                    i
+                ));
+            }
+            if !(x >= left && x + w <= right && y >= top && y + h <= bottom) {
+                return Err(format!(
+                    "Brick {} (row {}, column {}) at ({}, {}) size {}x{} must fit inside x={}..{}, y={}..{}.",
+                    i, brick.row, brick.col, x, y, w, h, left, right, top, bottom
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Default for Breakout {
@@ -124,6 +396,24 @@ impl Default for Breakout {
                 StartBall::new(0.9 * w, y, 150.0),
             ],
             paddle_discrete_segments: Some(5),
+            brick_layout: None,
+            levels: None,
+            loop_levels: None,
+            multi_ball: None,
+            paddle_model: None,
+            paddle_spin: None,
+            level_transition_frames: None,
+            fixed_point: None,
+        }
+    }
+}
+
+impl BallStats {
+    fn new(id: u32) -> BallStats {
+        BallStats {
+            id,
+            score: 0,
+            bricks_broken: 0,
         }
     }
 }
@@ -148,7 +438,68 @@ impl Brick {
             depth,
             alive: true,
             destructible: true,
+            hit_points: None,
+            max_hit_points: None,
+            effect: None,
+        }
+    }
+
+    /// A brick that takes ``hit_points`` hits to break.
+    pub fn with_hit_points(mut self, hit_points: u32) -> Brick {
+        self.hit_points = Some(hit_points);
+        self.max_hit_points = Some(hit_points);
+        self
+    }
+
+    /// Weaken this brick by one hit; returns true if that breaks it. Indestructible bricks never break.
+    pub fn take_hit(&mut self) -> bool {
+        if !self.destructible {
+            return false;
+        }
+        let left = self.hit_points.unwrap_or(1).saturating_sub(1);
+        if self.hit_points.is_some() {
+            self.hit_points = Some(left);
         }
+        if left == 0 {
+            self.alive = false;
+        }
+        !self.alive
+    }
+
+    /// Bring this brick back at full strength for a new level.
+    pub fn revive(&mut self) {
+        self.alive = true;
+        self.hit_points = self.max_hit_points;
+    }
+
+    /// Multi-hit bricks fade toward the background as they weaken, down to half strength.
+    pub fn current_color(&self, bg_color: Color) -> Color {
+        match (self.hit_points, self.max_hit_points) {
+            (Some(left), Some(max)) if left < max => {
+                let alpha = 255 * (max + left) / (2 * max);
+                self.color.with_alpha(alpha as u8).blend_over(bg_color)
+            }
+            _ => self.color,
+        }
+    }
+
+    /// The color of the mark drawn on a special brick, so that special bricks can be told apart on screen.
+    pub fn mark_color(&self) -> Option<Color> {
+        let rgb = match self.effect? {
+            BrickEffect::PowerUp(kind) => power_up_color(kind),
+            BrickEffect::SpeedUp => &screen::SPEED_UP_MARK_COLOR,
+            BrickEffect::SlowDown => &screen::SLOW_DOWN_MARK_COLOR,
+            BrickEffect::Split => &screen::SPLIT_MARK_COLOR,
+        };
+        Some(rgb.into())
+    }
+
+    /// The center of this brick, where power-ups and split balls appear.
+    pub fn center(&self) -> Vec2D {
+        Vec2D::new(
+            self.position.x + self.size.x / 2.0,
+            self.position.y + self.size.y / 2.0,
+        )
     }
 
     /// Now that we have non-breakable bricks, we can use this everywhere to tell if a brick is completed or not.
@@ -189,37 +540,16 @@ impl toybox_core::Simulation for Breakout {
         actions
     }
 
+    fn continuous_action_space(&self) -> Vec<ContinuousAxis> {
+        vec![ContinuousAxis::full(
+            "horizontal",
+            "Paddle movement as a fraction of full speed; negative is left.",
+        )]
+    }
+
     /// Create a new game of breakout.
     fn new_game(&mut self) -> Box<dyn toybox_core::State + Send> {
-        let mut bricks = Vec::new();
-
-        let offset = Vec2D::new(
-            screen::BOARD_LEFT_X.into(),
-            (screen::BOARD_TOP_Y + screen::ROOF_SPACING).into(),
-        );
-        let num_bricks_deep = self.row_colors.len();
-        let bsize = Vec2D::new(screen::BRICK_WIDTH.into(), screen::BRICK_HEIGHT.into());
-        let xs = bsize.x;
-        let ys = bsize.y;
-        for xi in 0..screen::BRICKS_ACROSS {
-            let x = f64::from(xi);
-            for yi in 0..num_bricks_deep {
-                let color_tuple = self.row_colors[yi];
-                let score = self.row_scores[yi];
-                let bpos = Vec2D::new(x * xs, (yi as f64) * ys).translate(&offset);
-                // Reverse depth:
-                let depth = num_bricks_deep - yi - 1;
-                bricks.push(Brick::new(
-                    yi as i32,
-                    xi,
-                    bpos,
-                    bsize.clone(),
-                    score,
-                    color_tuple,
-                    depth as u32,
-                ));
-            }
-        }
+        let bricks = self.make_bricks();
 
         let state = State {
             config: self.clone(),
@@ -233,11 +563,18 @@ impl toybox_core::Simulation for Breakout {
                 score: 0,
                 level: 1,
                 ball_radius: 2.0,
-                paddle_width: screen::PADDLE_START_SIZE.0.into(),
+                paddle_width: self.paddle_width_at(1),
                 paddle_speed: 4.0,
                 rand: random::Gen::new_child(&mut self.rand),
                 bricks,
                 reset: false,
+                level_transition: None,
+                level_complete: Some(false),
+                power_ups: Some(Vec::new()),
+                ball_stats: Some(Vec::new()),
+                next_ball_id: Some(0),
+                multi_ball_cooldown: Some(0),
+                paddle_target: None,
             },
         };
 
@@ -260,6 +597,7 @@ impl toybox_core::Simulation for Breakout {
         json_str: &str,
     ) -> Result<Box<dyn toybox_core::Simulation + Send>, serde_json::Error> {
         let config: Breakout = serde_json::from_str(json_str)?;
+        config.validate().map_err(serde::de::Error::custom)?;
         Ok(Box::new(config))
     }
 
@@ -277,323 +615,680 @@ impl toybox_core::Simulation for Breakout {
     }
 }
 
+/// A ball bounces at most this many times in one frame; any motion left after that is lost.
+const MAX_BOUNCES_PER_FRAME: usize = 16;
+
+/// Cell size, in pixels, of the grid bricks are indexed in for collision checks; a little larger than a brick.
+const BRICK_GRID_CELL_SIZE: i32 = 16;
+
+/// The smallest whole-pixel rectangle covering a box.
+fn pixel_bounds<S: Scalar>(position: &Vec2D<S>, size: &Vec2D<S>) -> Rect {
+    let x = position.x.floor();
+    let y = position.y.floor();
+    let w = (position.x + size.x).ceil() - x;
+    let h = (position.y + size.y).ceil() - y;
+    let pixels = |value: S| value.to_f64() as i32;
+    Rect::new(pixels(x), pixels(y), pixels(w), pixels(h))
+}
+
+/// The color a power-up falls in, and marks the brick that drops it.
+fn power_up_color(kind: PowerUpKind) -> &'static (u8, u8, u8) {
+    match kind {
+        PowerUpKind::WidePaddle => &screen::WIDE_PADDLE_COLOR,
+        PowerUpKind::ExtraLife => &screen::EXTRA_LIFE_COLOR,
+    }
+}
+
+/// What a ball bounced off; see ``State::obstacles``.
+#[derive(Debug, Clone, Copy)]
+enum Obstacle {
+    Wall,
+    Roof,
+    Paddle,
+    Brick(usize),
+}
+
+/// Side effects of the balls' movement during one frame, applied once every ball has moved.
+#[derive(Default)]
+struct BallEvents {
+    /// Power-ups dropped by broken bricks.
+    dropped: Vec<PowerUp>,
+    /// New balls from split bricks.
+    split_balls: Vec<Body2D>,
+    /// Which ball broke a brick worth how many points.
+    broken_by: Vec<(usize, i32)>,
+}
+
 impl State {
-    fn start_ball(&mut self) {
+    fn start_ball<S: Scalar>(&mut self) {
         let option: &StartBall = self
             .config
             .ball_start_positions
             .choose(&mut self.state.rand)
             .unwrap();
 
-        let mut ball = Body2D::new_pos(option.x, option.y);
+        let mut ball: Body2D<S> = Body2D::new_pos(S::from_f64(option.x), S::from_f64(option.y));
         ball.velocity = Vec2D::from_polar(
-            self.config.ball_speed_slow,
-            option.angle_degrees.to_radians(),
+            S::from_f64(self.config.ball_speed_slow_at(self.state.level)),
+            S::from_f64(option.angle_degrees).to_radians(),
         );
-        self.state.balls.push(ball);
+        self.push_ball(ball.cast());
     }
-    fn update_paddle_movement(&mut self, buttons: Input) {
-        let left = buttons.left;
-        let right = buttons.right;
-
-        if left {
-            self.state.paddle.velocity.x = -self.state.paddle_speed;
-        } else if right {
-            self.state.paddle.velocity.x = self.state.paddle_speed;
-        } else {
-            self.state.paddle.velocity.x = 0.0;
+    /// Stats for every ball in play, rebuilt if they are missing or out of step (e.g., from an older state).
+    fn ball_stats_mut(&mut self) -> &mut Vec<BallStats> {
+        let count = self.state.balls.len();
+        let next_ball_id = self.state.next_ball_id.get_or_insert(0);
+        let stats = self.state.ball_stats.get_or_insert_with(Vec::new);
+        if stats.len() != count {
+            stats.clear();
+            for _ in 0..count {
+                stats.push(BallStats::new(*next_ball_id));
+                *next_ball_id += 1;
+            }
         }
+        stats
+    }
+    /// Put a ball into play with a fresh id.
+    fn push_ball(&mut self, ball: Body2D) {
+        self.ball_stats_mut();
+        let id = self.state.next_ball_id.unwrap_or(0);
+        self.state.next_ball_id = Some(id + 1);
+        self.ball_stats_mut().push(BallStats::new(id));
+        self.state.balls.push(ball);
+    }
+    fn clear_balls(&mut self) {
+        self.state.balls.clear();
+        self.state.ball_stats = Some(Vec::new());
     }
-    fn keep_paddle_on_screen(&mut self) {
-        let left = screen::BOARD_LEFT_X as f64 - self.state.paddle_width / 2.0;
-        let right = screen::BOARD_RIGHT_X as f64 + self.state.paddle_width / 2.0;
-        if self.state.paddle.position.x < left {
-            self.state.paddle.position.x = left;
-            self.state.paddle.velocity.x = 0.0;
-        } else if self.state.paddle.position.x > right {
-            self.state.paddle.position.x = right;
-            self.state.paddle.velocity.x = 0.0;
+    /// The second button launches another ball, if ``multi_ball`` allows it.
+    fn launch_extra_ball<S: Scalar>(&mut self) {
+        let rules = match &self.config.multi_ball {
+            Some(rules) => rules.clone(),
+            None => return,
+        };
+        if self.state.is_dead
+            || self.state.multi_ball_cooldown.unwrap_or(0) > 0
+            || self.state.balls.len() >= rules.max_balls as usize
+        {
+            return;
+        }
+        if rules.costs_life {
+            if self.state.lives <= 0 {
+                return;
+            }
+            self.state.lives -= 1;
         }
+        self.start_ball::<S>();
+        self.state.multi_ball_cooldown = Some(rules.cooldown_frames);
     }
-    fn check_bounce_paddle(&mut self) {
-        let radius = self.state.ball_radius;
-
-        for ball in self.state.balls.iter_mut() {
-            // Only check balls going downwards.
-            if ball.velocity.y <= 0.0 {
-                continue;
+    /// Move the paddle according to the buttons, or the analog ``axis`` (-1.0 to 1.0) when no direction is held.
+    fn update_paddle_movement<S: Scalar>(&mut self, buttons: Input, axis: f64) {
+        let direction = if buttons.left {
+            -S::ONE
+        } else if buttons.right {
+            S::ONE
+        } else {
+            S::from_f64(axis)
+        };
+        let speed = S::from_f64(self.state.paddle_speed);
+        let velocity = S::from_f64(self.state.paddle.velocity.x);
+
+        let velocity = match self.config.paddle_model {
+            None | Some(PaddleModel::Instant) => direction * speed,
+            Some(PaddleModel::Momentum {
+                acceleration,
+                max_speed,
+                friction,
+            }) => {
+                let acceleration = S::from_f64(acceleration);
+                if direction == S::ZERO {
+                    velocity * S::from_f64(friction)
+                } else {
+                    // Accelerate toward the fraction of top speed asked for.
+                    velocity
+                        + (direction * S::from_f64(max_speed) - velocity)
+                            .clamp(-acceleration, acceleration)
+                }
+            }
+            Some(PaddleModel::Analog { max_speed }) => {
+                let max_speed = S::from_f64(max_speed);
+                let (left, right) = self.paddle_limits::<S>();
+                let position = S::from_f64(self.state.paddle.position.x);
+                let target = self.state.paddle_target.map_or(position, S::from_f64);
+                let target = (target + direction * speed).clamp(left, right);
+                self.state.paddle_target = Some(target.to_f64());
+                (target - position).clamp(-max_speed, max_speed)
             }
+        };
+        self.state.paddle.velocity.x = velocity.to_f64();
+    }
+    /// The range of x positions the center of the paddle may take.
+    fn paddle_limits<S: Scalar>(&self) -> (S, S) {
+        let half = S::from_f64(self.state.paddle_width) / S::from_f64(2.0);
+        (
+            S::from_f64(screen::BOARD_LEFT_X.into()) - half,
+            S::from_f64(screen::BOARD_RIGHT_X.into()) + half,
+        )
+    }
+    /// Move the paddle by its velocity, stopping it at the edges of the board.
+    fn move_paddle<S: Scalar>(&mut self) {
+        let (left, right) = self.paddle_limits::<S>();
+        let mut paddle: Body2D<S> = self.state.paddle.cast();
+        paddle.integrate_mut(S::ONE);
+        if paddle.position.x < left {
+            paddle.position.x = left;
+            paddle.velocity.x = S::ZERO;
+        } else if paddle.position.x > right {
+            paddle.position.x = right;
+            paddle.velocity.x = S::ZERO;
+        }
+        self.state.paddle = paddle.cast();
+    }
+    /// Send a ball back up at an angle that depends on where along the paddle it landed.
+    fn bounce_off_paddle<S: Scalar>(&self, ball: &mut Body2D<S>) {
+        let two = S::from_f64(2.0);
+        let paddle: Body2D<S> = self.state.paddle.cast();
+        let paddle_width = S::from_f64(self.state.paddle_width);
+        // get x location of ball hit relative to paddle
+        let ball_hit_x = ball.position.x - (paddle.position.x - (paddle_width / two));
+        // get normalized location of ball hit along paddle
+        let mut paddle_normalized_relative_intersect_x = S::ONE - ball_hit_x / paddle_width;
+
+        // If we have discrete segments, discretize that.
+        if let Some(segments) = self.config.paddle_discrete_segments {
+            let segments = S::from_f64(segments.into());
+            // Multiply to get a whole segment id.
+            let segment_id = (paddle_normalized_relative_intersect_x * segments).floor();
+            // Center within segments.
+            let shift = S::ONE / (two * segments);
+            // Divide to go back to a number from 0..1.0
+            let relative = segment_id / segments + shift;
+            // Overwrite continuous value.
+            paddle_normalized_relative_intersect_x = relative;
+        }
 
-            // check paddle:
-            let paddle_ball_same_x = (ball.position.x - self.state.paddle.position.x).abs()
-                < radius + self.state.paddle_width / 2.0;
-            let paddle_ball_same_y =
-                (self.state.paddle.position.y - ball.position.y).abs() < radius;
-
-            if paddle_ball_same_x && paddle_ball_same_y {
-                // get x location of ball hit relative to paddle
-                let ball_hit_x = ball.position.x
-                    - (self.state.paddle.position.x - (self.state.paddle_width / 2.0));
-                // get normalized location of ball hit along paddle
-                let mut paddle_normalized_relative_intersect_x =
-                    1.0 - ball_hit_x / self.state.paddle_width;
-
-                // If we have discrete segments, discretize that.
-                if let Some(segments) = self.config.paddle_discrete_segments {
-                    // Multiply to get an integer segment id.
-                    let segment_id =
-                        (paddle_normalized_relative_intersect_x * segments as f64).floor() as i32;
-                    // Center within segments.
-                    let shift = 1.0 / (2.0 * segments as f64);
-                    // Divide to go back to a number from 0..1.0
-                    let relative = (segment_id as f64) / (segments as f64) + shift;
-                    // Overwrite continuous value.
-                    paddle_normalized_relative_intersect_x = relative;
-                }
+        // convert this normalized parameter to the degree of the bounce angle
+        let bounce_angle = paddle_normalized_relative_intersect_x
+            * S::from_f64(screen::BALL_ANGLE_RANGE)
+            + S::from_f64(screen::BALL_ANGLE_MIN);
 
-                // convert this normalized parameter to the degree of the bounce angle
-                let bounce_angle = paddle_normalized_relative_intersect_x
-                    * screen::BALL_ANGLE_RANGE
-                    + screen::BALL_ANGLE_MIN;
+        let speed = ball.velocity.magnitude();
+        let mut velocity = Vec2D::from_polar(speed, bounce_angle.to_radians());
 
-                ball.velocity =
-                    Vec2D::from_polar(ball.velocity.magnitude(), bounce_angle.to_radians());
-                // calculations use non-graphics polar orientation
-                // to quickly fix, we reflect over the x-axis
-                ball.velocity.y *= -1.0;
-            }
+        // Put some English on the ball: push it along with the paddle, but keep its speed and a playable angle.
+        if let Some(spin) = self.config.paddle_spin {
+            velocity.x += S::from_f64(spin) * paddle.velocity.x;
+            let angle = velocity.y.atan2(velocity.x).to_degrees().clamp(
+                S::from_f64(screen::BALL_ANGLE_MIN),
+                S::from_f64(screen::BALL_ANGLE_MIN + screen::BALL_ANGLE_RANGE),
+            );
+            velocity = Vec2D::from_polar(speed, angle.to_radians());
         }
+
+        // calculations use non-graphics polar orientation
+        // to quickly fix, we reflect over the x-axis
+        velocity.y *= -S::ONE;
+        ball.velocity = velocity;
     }
 
-    fn check_ball_death(&mut self) -> bool {
-        let radius = self.state.ball_radius;
+    /// How wide the paddle is at the start of the current level.
+    fn level_paddle_width<S: Scalar>(&self) -> S {
+        S::from_f64(self.config.paddle_width_at(self.state.level))
+    }
+    /// Is a wide-paddle power-up in effect? Nothing else makes the paddle wider than the level sets it.
+    fn paddle_is_wide<S: Scalar>(&self) -> bool {
+        S::from_f64(self.state.paddle_width) > self.level_paddle_width::<S>()
+    }
+    fn check_ball_death<S: Scalar>(&mut self) -> bool {
+        let radius = S::from_f64(self.state.ball_radius);
+        let bottom = S::from_f64(screen::BOARD_BOTTOM_Y.into());
 
         let mut died = Vec::new();
-        for (i, ball) in self.state.balls.iter_mut().enumerate() {
+        for (i, ball) in self.state.balls.iter().enumerate() {
+            let ball: Body2D<S> = ball.cast();
             // Only those balls downward:
-            if ball.velocity.y < 0.0 {
+            if ball.velocity.y < S::ZERO {
                 continue;
             }
-            if ball.position.y + radius > screen::BOARD_BOTTOM_Y.into() {
+            if ball.position.y + radius > bottom {
                 died.push(i);
             }
         }
         for index in died.iter().rev() {
+            self.ball_stats_mut().remove(*index);
             self.state.balls.remove(*index);
         }
+        if !died.is_empty() && self.paddle_is_wide::<S>() {
+            self.state.paddle_width = self.level_paddle_width::<S>().to_f64();
+        }
 
         // Death when no more balls!
         self.state.balls.is_empty()
     }
 
-    fn update_time_slice(&mut self, time_step: f64) {
-        // Update positions.
-
-        for ball in self.state.balls.iter_mut() {
-            ball.integrate_mut(time_step);
-        }
-        self.state.paddle.integrate_mut(time_step);
-
-        self.keep_paddle_on_screen();
-        self.check_bounce_paddle();
-
-        // check lose?
-        if self.check_ball_death() && !self.state.is_dead {
-            if !self.state.is_dead {
-                self.state.lives -= 1;
-                self.state.paddle_width = screen::PADDLE_START_SIZE.0.into();
+    /// Index the living bricks by position, so each ball only sweeps against the bricks near its path.
+    fn brick_grid(&self) -> SpatialGrid<usize> {
+        let mut grid = SpatialGrid::new(BRICK_GRID_CELL_SIZE);
+        for (i, brick) in self.state.bricks.iter().enumerate() {
+            if brick.alive {
+                grid.insert(pixel_bounds(&brick.position, &brick.size), i);
             }
-            self.state.is_dead = true;
-            return;
         }
+        grid
+    }
 
-        for ball in self.state.balls.iter_mut() {
-            let radius = self.state.ball_radius;
-
-            // Handle collisions:
-            if ball.velocity.y < 0.0 {
-                // bounce ceiling?
-                if ball.position.y - radius < screen::BOARD_TOP_Y.into() {
-                    ball.velocity.y *= -1.0;
-                    self.state.paddle_width = screen::PADDLE_SMALL_SIZE.0.into();
-                }
+    /// Everything a ball can bounce off within ``region``: the walls, the roof, the paddle (only when falling) and each living brick from ``bricks``.
+    fn obstacles<S: Scalar>(
+        &self,
+        falling: bool,
+        bricks: &SpatialGrid<usize>,
+        region: &Rect,
+    ) -> Vec<(Obstacle, Aabb<S>)> {
+        let (w, h) = screen::GAME_SIZE;
+        let (w, h) = (S::from_f64(w.into()), S::from_f64(h.into()));
+        let three = S::from_f64(3.0);
+        let left = S::from_f64(screen::BOARD_LEFT_X.into());
+        let right = S::from_f64(screen::BOARD_RIGHT_X.into());
+        let top = S::from_f64(screen::BOARD_TOP_Y.into());
+        let mut obstacles = vec![
+            (Obstacle::Wall, Aabb::new(left - w, -h, w, three * h)),
+            (Obstacle::Wall, Aabb::new(right, -h, w, three * h)),
+            (Obstacle::Roof, Aabb::new(-w, top - h, three * w, h)),
+        ];
+        if falling {
+            let paddle = self.state.paddle.position.cast::<S>();
+            let width = S::from_f64(self.state.paddle_width);
+            obstacles.push((
+                Obstacle::Paddle,
+                Aabb::new(
+                    paddle.x - width / S::from_f64(2.0),
+                    paddle.y,
+                    width,
+                    S::from_f64(screen::PADDLE_START_SIZE.1.into()),
+                ),
+            ));
+        }
+        for &i in bricks.query(region) {
+            let brick = &self.state.bricks[i];
+            // Bricks broken earlier this frame are still in the grid.
+            if brick.alive {
+                let aabb = Aabb {
+                    position: brick.position.cast(),
+                    size: brick.size.cast(),
+                };
+                obstacles.push((Obstacle::Brick(i), aabb));
             }
+        }
+        obstacles
+    }
 
-            // check living bricks:
-            let ball_bounce_y = Vec2D::new(
-                ball.position.x,
-                ball.position.y + ball.velocity.y.signum() * radius,
-            );
-            let ball_bounce_x = Vec2D::new(
-                ball.position.x + ball.velocity.x.signum() * radius,
-                ball.position.y,
+    /// Move one ball through a whole frame, bouncing off everything it touches in the order it touches them.
+    fn move_ball<S: Scalar>(
+        &mut self,
+        index: usize,
+        bricks: &SpatialGrid<usize>,
+        events: &mut BallEvents,
+    ) {
+        let two = S::from_f64(2.0);
+        let radius = S::from_f64(self.state.ball_radius);
+        let mut ball: Body2D<S> = self.state.balls[index].cast();
+        let mut time_left = S::ONE;
+        for _ in 0..MAX_BOUNCES_PER_FRAME {
+            let motion = ball.velocity.scale(time_left);
+            // The box swept out by the ball this step; nothing outside it can be hit.
+            let end = ball.position.translate(&motion);
+            let corner = Vec2D::new(
+                ball.position.x.min(end.x) - radius,
+                ball.position.y.min(end.y) - radius,
             );
-
-            for brick in self.state.bricks.iter_mut().filter(|b| b.alive) {
-                let mut hit = false;
-                if brick.contains(&ball_bounce_x) {
-                    hit = true;
-                    ball.velocity.x *= -1.0;
-                } else if brick.contains(&ball_bounce_y) {
-                    hit = true;
-                    ball.velocity.y *= -1.0;
+            let size = Vec2D::new(motion.x.abs() + two * radius, motion.y.abs() + two * radius);
+            let region = pixel_bounds(&corner, &size);
+            let first = self
+                .obstacles(ball.velocity.y > S::ZERO, bricks, &region)
+                .into_iter()
+                .filter_map(|(what, aabb)| {
+                    sweep_circle_aabb(&ball.position, radius, &motion, &aabb)
+                        .map(|contact| (what, contact))
+                })
+                .min_by(|(_, a), (_, b)| {
+                    a.time
+                        .partial_cmp(&b.time)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+            let (what, contact) = match first {
+                Some(first) => first,
+                None => {
+                    ball.position += motion;
+                    break;
                 }
-                if hit {
-                    if brick.destructible {
-                        brick.alive = false;
-                        self.state.score += brick.points;
+            };
+            ball.position += motion.scale(contact.time);
+            time_left *= S::ONE - contact.time;
+
+            match what {
+                Obstacle::Wall => ball.velocity.x *= -S::ONE,
+                Obstacle::Roof => {
+                    ball.velocity.y *= -S::ONE;
+                    // A wide paddle keeps its size until a ball is lost.
+                    if !self.paddle_is_wide::<S>() {
+                        self.state.paddle_width = (self.level_paddle_width::<S>()
+                            * S::from_f64(screen::PADDLE_SMALL_SIZE.0.into())
+                            / S::from_f64(screen::PADDLE_START_SIZE.0.into()))
+                        .to_f64();
                     }
-                    if brick.depth >= self.config.ball_speed_row_depth {
-                        // Potentially speed up the ball. This will be a no-op if it's already fast.
-                        let theta = ball.velocity.angle();
-                        ball.velocity = Vec2D::from_polar(self.config.ball_speed_fast, theta);
+                }
+                Obstacle::Paddle => self.bounce_off_paddle(&mut ball),
+                Obstacle::Brick(brick) => {
+                    if contact.normal.x.abs() > contact.normal.y.abs() {
+                        ball.velocity.x *= -S::ONE;
+                    } else {
+                        ball.velocity.y *= -S::ONE;
                     }
-                    break;
+                    self.hit_brick(brick, index, &mut ball, events);
                 }
             }
+        }
+        self.state.balls[index] = ball.cast();
+    }
 
-            // bounce right wall?
-            if ball.velocity.x > 0.0 {
-                if ball.position.x + radius > screen::BOARD_RIGHT_X.into() {
-                    ball.velocity.x *= -1.0;
-                }
-            } else {
-                // bounce left wall?
-                if ball.position.x - radius < screen::BOARD_LEFT_X.into() {
-                    ball.velocity.x *= -1.0;
-                }
+    /// Damage a brick the ball has just bounced off, and apply whatever it does to the ball.
+    fn hit_brick<S: Scalar>(
+        &mut self,
+        index: usize,
+        ball_index: usize,
+        ball: &mut Body2D<S>,
+        events: &mut BallEvents,
+    ) {
+        let level = self.state.level;
+        let brick = &mut self.state.bricks[index];
+        let broken = brick.take_hit();
+        if broken {
+            self.state.score += brick.points;
+            events.broken_by.push((ball_index, brick.points));
+        }
+        let fast = S::from_f64(self.config.ball_speed_fast_at(level));
+        if brick.depth >= self.config.ball_speed_row_depth_at(level) {
+            // Potentially speed up the ball. This will be a no-op if it's already fast.
+            let theta = ball.velocity.angle();
+            ball.velocity = Vec2D::from_polar(fast, theta);
+        }
+        match brick.effect {
+            Some(BrickEffect::PowerUp(kind)) if broken => {
+                let center = brick.center().cast::<S>();
+                let mut body = Body2D::new_pos(center.x, center.y);
+                body.velocity.y = S::from_f64(screen::POWER_UP_SPEED);
+                events.dropped.push(PowerUp {
+                    kind,
+                    body: body.cast(),
+                });
             }
+            Some(BrickEffect::SpeedUp) => {
+                let theta = ball.velocity.angle();
+                ball.velocity = Vec2D::from_polar(fast, theta);
+            }
+            Some(BrickEffect::SlowDown) => {
+                let theta = ball.velocity.angle();
+                let slow = S::from_f64(self.config.ball_speed_slow_at(level));
+                ball.velocity = Vec2D::from_polar(slow, theta);
+            }
+            Some(BrickEffect::Split) if broken => {
+                let mut split = ball.clone();
+                split.velocity.x *= -S::ONE;
+                events.split_balls.push(split.cast());
+            }
+            _ => {}
         }
     }
-}
 
-impl toybox_core::State for State
-where
-    State: Clone,
-{
-    fn lives(&self) -> i32 {
-        self.state.lives
-    }
-    fn level(&self) -> i32 {
-        self.state.level
-    }
-    fn score(&self) -> i32 {
-        self.state.score
+    /// Credit broken bricks to the balls that broke them, then add new balls and power-ups.
+    fn apply_ball_events(&mut self, events: BallEvents) {
+        let stats = self.ball_stats_mut();
+        for (i, points) in events.broken_by {
+            stats[i].score += points;
+            stats[i].bricks_broken += 1;
+        }
+        let max_balls = self
+            .config
+            .multi_ball
+            .as_ref()
+            .map(|rules| rules.max_balls as usize);
+        for ball in events.split_balls {
+            if let Some(max_balls) = max_balls {
+                if self.state.balls.len() >= max_balls {
+                    break;
+                }
+            }
+            self.push_ball(ball);
+        }
+        self.state
+            .power_ups
+            .get_or_insert_with(Vec::new)
+            .extend(events.dropped);
     }
 
-    /// Mutably update the game state.
-    fn update_mut(&mut self, buttons: Input) {
-        self.update_paddle_movement(buttons);
-
+    /// One frame of play: launch balls, move everything, and handle bricks and lost balls.
+    fn update_play<S: Scalar>(&mut self, buttons: Input) {
         if self.state.is_dead {
             if buttons.button1 {
                 // Delete old ball(s).
-                self.state.balls.clear();
+                self.clear_balls();
                 // New ball.
-                self.start_ball();
+                self.start_ball::<S>();
                 self.state.is_dead = false;
             }
         }
 
+        if let Some(cooldown) = self.state.multi_ball_cooldown.as_mut() {
+            *cooldown = cooldown.saturating_sub(1);
+        }
         if buttons.button2 {
-            self.start_ball();
+            self.launch_extra_ball::<S>();
         }
 
-        let distance_limit = self.state.ball_radius as i32;
-        let total_time = 1.0;
-        let distance_limit = distance_limit as f64; // m
-        let speed: f64 = self
-            .state
-            .balls
-            .iter()
-            .map(|ball| NotNan::new(ball.velocity.magnitude()).expect("NaN velocity magnitude!"))
-            .max()
-            .map(|nn| nn.into_inner())
-            .unwrap_or(distance_limit); // m/s
-
-        // if your speed is 30, and your radius is 5, we want to do about 6 steps.
-        let time_step = distance_limit / speed; // (m) / (m/s) = m * s / m = s
-
-        // Update positions, checking for collisions in as many increments as is needed.
-        let mut time_simulated = 0.0;
-        while time_simulated < 1.0 {
-            let time_left = total_time - time_simulated;
-            if time_left < time_step {
-                self.update_time_slice(time_left);
-                break;
-            } else {
-                self.update_time_slice(time_step);
-                if self.state.is_dead {
-                    // Don't simulate if dead.
-                    break;
-                }
-                time_simulated += time_step;
-            }
+        self.move_paddle::<S>();
+
+        let mut events = BallEvents::default();
+        let bricks = self.brick_grid();
+        for i in 0..self.state.balls.len() {
+            self.move_ball::<S>(i, &bricks, &mut events);
+        }
+        self.apply_ball_events(events);
+
+        // check lose?
+        if self.check_ball_death::<S>() && !self.state.is_dead {
+            self.state.lives -= 1;
+            self.state.paddle_width = self.level_paddle_width::<S>().to_f64();
+            self.state.is_dead = true;
+            self.state.power_ups = Some(Vec::new());
+        } else {
+            self.update_power_ups(S::ONE);
         }
+    }
+
+    /// Has the player broken every brick that can be broken? A wall with nothing breakable is never cleared.
+    fn wall_cleared(&self) -> bool {
+        let bricks = &self.state.bricks;
+        bricks.iter().any(|b| b.destructible) && bricks.iter().all(|b| b.completed())
+    }
 
-        let reset_level = self.state.bricks.iter().all(|b| b.completed());
-        if reset_level && self.state.reset {
-            // Win level; reset all bricks; reset balls:
+    /// The wall is down: take the balls and power-ups off the board and start the transition to the next level.
+    fn complete_level(&mut self) {
+        self.state.level_complete = Some(true);
+        self.state.reset = true;
+        self.state.level_transition = Some(
+            self.config
+                .level_transition_frames
+                .unwrap_or(screen::LEVEL_TRANSITION_FRAMES),
+        );
+        self.state.power_ups = Some(Vec::new());
+        self.clear_balls();
+    }
+
+    /// Count down the level transition, and start the next level when it is over.
+    fn update_level_transition<S: Scalar>(&mut self) {
+        let frames_left = self.state.level_transition.unwrap_or(0).saturating_sub(1);
+        if frames_left == 0 {
+            self.advance_level::<S>();
+        } else {
+            self.state.level_transition = Some(frames_left);
+        }
+    }
+
+    /// Win level: move on to the next of ``levels``, or revive all bricks; reset balls.
+    fn advance_level<S: Scalar>(&mut self) {
+        self.state.level += 1;
+        let level = self.state.level;
+        self.state.paddle_width = self.level_paddle_width::<S>().to_f64();
+        if self.config.levels.is_some() {
+            self.state.bricks = self.config.make_bricks_at(level);
+        } else {
             for b in self.state.bricks.iter_mut() {
-                b.alive = true;
+                b.revive();
             }
-            // Delete old ball(s).
-            self.state.balls.clear();
-            // New ball.
-            self.start_ball();
-            self.state.reset = false;
-            self.state.level += 1;
         }
+        self.state.power_ups = Some(Vec::new());
+        // Delete old ball(s).
+        self.clear_balls();
+        // New ball.
+        self.start_ball::<S>();
+        self.state.is_dead = false;
+        self.state.reset = false;
+        self.state.level_transition = None;
     }
 
-    fn draw(&self) -> Vec<Drawable> {
-        let mut output = Vec::new();
-        output.push(Drawable::Clear(self.config.bg_color));
+    /// Move falling power-ups; apply those the paddle catches and drop those that fall off the board.
+    fn update_power_ups<S: Scalar>(&mut self, time_step: S) {
+        let mut power_ups = match self.state.power_ups.take() {
+            Some(power_ups) => power_ups,
+            None => return,
+        };
+        let two = S::from_f64(2.0);
+        let (w, h) = screen::POWER_UP_SIZE;
+        let (w, h) = (S::from_f64(w.into()), S::from_f64(h.into()));
+        let bottom = S::from_f64(screen::BOARD_BOTTOM_Y.into());
+        let paddle = self.state.paddle.position.cast::<S>();
+        let paddle_width = S::from_f64(self.state.paddle_width);
+        let paddle_height = S::from_f64(screen::PADDLE_START_SIZE.1.into());
+
+        for power_up in power_ups.iter_mut() {
+            let mut body: Body2D<S> = power_up.body.cast();
+            body.integrate_mut(time_step);
+            power_up.body = body.cast();
+        }
+        let mut caught = Vec::new();
+        power_ups.retain(|power_up| {
+            let p = power_up.body.position.cast::<S>();
+            let same_x = (p.x - paddle.x).abs() < (paddle_width + w) / two;
+            let same_y = p.y + h / two >= paddle.y && p.y - h / two <= paddle.y + paddle_height;
+            if same_x && same_y {
+                caught.push(power_up.kind);
+                return false;
+            }
+            p.y - h / two < bottom
+        });
+        self.state.power_ups = Some(power_ups);
+
+        for kind in caught {
+            match kind {
+                PowerUpKind::WidePaddle => {
+                    self.state.paddle_width = (self.level_paddle_width::<S>()
+                        * S::from_f64(screen::WIDE_PADDLE_SCALE))
+                    .to_f64()
+                }
+                PowerUpKind::ExtraLife => self.state.lives += 1,
+            }
+        }
+    }
 
-        // Draw frame top:
-        output.push(Drawable::rect(
-            self.config.frame_color,
-            0,
-            screen::FRAME_OFFSET,
-            screen::GAME_SIZE.0,
-            screen::FRAME_THICKNESS,
-        ));
+    /// One frame of the game, with the physics done in ``S``; see ``Breakout::fixed_point``.
+    fn update_frame<S: Scalar>(&mut self, buttons: Input, analog: AnalogInput) {
+        self.update_paddle_movement::<S>(buttons, analog.horizontal);
+        self.state.level_complete = Some(false);
 
-        // Draw frame left:
-        output.push(Drawable::rect(
-            self.config.frame_color,
-            0,
-            screen::FRAME_OFFSET,
-            screen::FRAME_SUPPORT_WIDTH,
-            screen::FRAME_LEFT_HEIGHT,
-        ));
+        if self.state.reset {
+            // Between levels, only the paddle moves.
+            self.move_paddle::<S>();
+            self.update_level_transition::<S>();
+        } else {
+            self.update_play::<S>(buttons);
+            if self.wall_cleared() {
+                self.complete_level();
+            }
+        }
+    }
+}
 
-        // Draw frame right:
-        output.push(Drawable::rect(
-            (&screen::FRAME_COLOR).into(),
-            screen::BOARD_RIGHT_X,
-            screen::FRAME_OFFSET,
-            screen::FRAME_SUPPORT_WIDTH,
-            screen::FRAME_RIGHT_HEIGHT,
-        ));
+impl toybox_core::State for State
+where
+    State: Clone,
+{
+    fn lives(&self) -> i32 {
+        self.state.lives
+    }
+    fn level(&self) -> i32 {
+        self.state.level
+    }
+    fn score(&self) -> i32 {
+        self.state.score
+    }
 
-        // Draw frame left "colored spot"
-        output.push(Drawable::rect(
-            (&screen::FRAME_LEFT_SUPPORT_COLOR).into(),
-            0,
-            screen::FRAME_OFFSET + screen::FRAME_LEFT_HEIGHT - screen::FRAME_LEFT_SUPPORT.1,
-            screen::FRAME_LEFT_SUPPORT.0,
-            screen::FRAME_LEFT_SUPPORT.1,
-        ));
+    /// Mutably update the game state.
+    fn update_mut(&mut self, buttons: Input) {
+        self.update_mut_analog(buttons, AnalogInput::default());
+    }
 
-        // Draw frame right "colored spot"
-        output.push(Drawable::rect(
-            (&screen::FRAME_RIGHT_SUPPORT_COLOR).into(),
-            screen::BOARD_RIGHT_X,
-            screen::FRAME_OFFSET + screen::FRAME_RIGHT_HEIGHT - screen::FRAME_RIGHT_SUPPORT.1,
-            screen::FRAME_RIGHT_SUPPORT.0,
-            screen::FRAME_RIGHT_SUPPORT.1,
-        ));
+    fn update_mut_analog(&mut self, buttons: Input, analog: AnalogInput) {
+        if self.config.fixed_point.unwrap_or(false) {
+            self.update_frame::<Fixed>(buttons, analog);
+        } else {
+            self.update_frame::<f64>(buttons, analog);
+        }
+    }
+
+    fn draw(&self) -> Vec<Drawable> {
+        let mut output = vec![
+            Drawable::Clear(self.config.bg_color),
+            // Draw frame top:
+            Drawable::rect(
+                self.config.frame_color,
+                0,
+                screen::FRAME_OFFSET,
+                screen::GAME_SIZE.0,
+                screen::FRAME_THICKNESS,
+            ),
+            // Draw frame left:
+            Drawable::rect(
+                self.config.frame_color,
+                0,
+                screen::FRAME_OFFSET,
+                screen::FRAME_SUPPORT_WIDTH,
+                screen::FRAME_LEFT_HEIGHT,
+            ),
+            // Draw frame right:
+            Drawable::rect(
+                (&screen::FRAME_COLOR).into(),
+                screen::BOARD_RIGHT_X,
+                screen::FRAME_OFFSET,
+                screen::FRAME_SUPPORT_WIDTH,
+                screen::FRAME_RIGHT_HEIGHT,
+            ),
+            // Draw frame left "colored spot"
+            Drawable::rect(
+                (&screen::FRAME_LEFT_SUPPORT_COLOR).into(),
+                0,
+                screen::FRAME_OFFSET + screen::FRAME_LEFT_HEIGHT - screen::FRAME_LEFT_SUPPORT.1,
+                screen::FRAME_LEFT_SUPPORT.0,
+                screen::FRAME_LEFT_SUPPORT.1,
+            ),
+            // Draw frame right "colored spot"
+            Drawable::rect(
+                (&screen::FRAME_RIGHT_SUPPORT_COLOR).into(),
+                screen::BOARD_RIGHT_X,
+                screen::FRAME_OFFSET + screen::FRAME_RIGHT_HEIGHT - screen::FRAME_RIGHT_SUPPORT.1,
+                screen::FRAME_RIGHT_SUPPORT.0,
+                screen::FRAME_RIGHT_SUPPORT.1,
+            ),
+        ];
 
         if self.state.lives < 0 {
             return output;
@@ -603,7 +1298,35 @@ where
             let (x, y) = brick.position.pixels();
             let (w, h) = brick.size.pixels();
 
-            output.push(Drawable::rect(brick.color, x, y, w, h));
+            output.push(Drawable::rect(
+                brick.current_color(self.config.bg_color),
+                x,
+                y,
+                w,
+                h,
+            ));
+            if let Some(color) = brick.mark_color() {
+                let (mark_w, mark_h) = screen::BRICK_MARK_SIZE;
+                output.push(Drawable::rect(
+                    color,
+                    x + (w - mark_w) / 2,
+                    y + (h - mark_h) / 2,
+                    mark_w,
+                    mark_h,
+                ));
+            }
+        }
+
+        let (power_up_w, power_up_h) = screen::POWER_UP_SIZE;
+        for power_up in self.state.power_ups.iter().flatten() {
+            let (x, y) = power_up.body.position.pixels();
+            output.push(Drawable::rect(
+                power_up_color(power_up.kind).into(),
+                x - power_up_w / 2,
+                y - power_up_h / 2,
+                power_up_w,
+                power_up_h,
+            ));
         }
 
         let (paddle_x, paddle_y) = self.state.paddle.position.pixels();
@@ -667,11 +1390,34 @@ where
                     Err(QueryError::BadInputArg)?
                 }
             }
+            "brick_hit_points_by_index" => {
+                if let Some(brick) = args.as_u64().and_then(|i| state.bricks.get(i as usize)) {
+                    let left = if brick.alive {
+                        brick.hit_points.unwrap_or(1)
+                    } else {
+                        0
+                    };
+                    serde_json::to_string(&left)?
+                } else {
+                    Err(QueryError::BadInputArg)?
+                }
+            }
+            "balls_in_play" => serde_json::to_string(&state.balls.len())?,
+            "ball_scores" => serde_json::to_string(&state.ball_scores())?,
+            "multi_ball_cooldown" => {
+                serde_json::to_string(&state.multi_ball_cooldown.unwrap_or(0))?
+            }
+            "special_bricks" => serde_json::to_string(&state.special_bricks())?,
+            "power_ups" => {
+                serde_json::to_string(&state.power_ups.iter().flatten().collect::<Vec<_>>())?
+            }
             "count_channels" => serde_json::to_string(&state.find_channels().len())?,
             "channels" => serde_json::to_string(&state.find_channels())?,
-            "num_columns" => serde_json::to_string(&screen::BRICKS_ACROSS)?,
-            "num_rows" => serde_json::to_string(&screen::ROW_SCORES.len())?,
+            "num_columns" => serde_json::to_string(&state.num_columns())?,
+            "num_rows" => serde_json::to_string(&state.num_rows())?,
             "level" => serde_json::to_string(&state.level)?,
+            "level_complete" => serde_json::to_string(&state.level_complete.unwrap_or(false))?,
+            "level_transition" => serde_json::to_string(&state.level_transition.unwrap_or(0))?,
             "is_dead" => serde_json::to_string(&state.is_dead)?,
             "config.ball_start_positions" => serde_json::to_string(&config.ball_start_positions)?,
             _ => Err(QueryError::NoSuchQuery)?,
@@ -685,25 +1431,52 @@ where
 
 /// Define some queries on StateCore.
 impl StateCore {
+    /// How many columns of bricks are there? Custom layouts may leave some columns empty.
+    fn num_columns(&self) -> i32 {
+        self.bricks.iter().map(|b| b.col + 1).max().unwrap_or(0)
+    }
+    /// How many rows of bricks are there? Custom layouts may leave some rows empty.
+    fn num_rows(&self) -> i32 {
+        self.bricks.iter().map(|b| b.row + 1).max().unwrap_or(0)
+    }
+    /// Who scored what, for each ball in play. Stats missing from an older state count as nothing scored.
+    fn ball_scores(&self) -> Vec<BallStats> {
+        match &self.ball_stats {
+            Some(stats) if stats.len() == self.balls.len() => stats.clone(),
+            _ => (0..self.balls.len() as u32).map(BallStats::new).collect(),
+        }
+    }
+    /// Indices of the living bricks that take more than one hit or have an effect, with what is left of them.
+    fn special_bricks(&self) -> Vec<serde_json::Value> {
+        self.bricks
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.alive && (b.max_hit_points.unwrap_or(1) > 1 || b.effect.is_some()))
+            .map(|(index, b)| {
+                serde_json::json!({
+                    "index": index,
+                    "row": b.row,
+                    "col": b.col,
+                    "hit_points": b.hit_points.unwrap_or(1),
+                    "effect": b.effect,
+                })
+            })
+            .collect()
+    }
     /// Returns a set of numbers corresponding to the stacks that are channels.
     fn find_channels(&self) -> Vec<i32> {
-        let across = screen::BRICKS_ACROSS as i32;
-        let down = screen::ROW_SCORES.len() as i32;
         let mut retval = Vec::new();
 
-        for offset in 0..across {
-            let all_dead = (0..down)
-                .map(|row| {
-                    let i = row + offset * down;
-                    !self.bricks[i as usize].alive
-                })
-                .all(|c| c);
+        for offset in 0..self.num_columns() {
+            let all_dead = self
+                .bricks
+                .iter()
+                .filter(|b| b.col == offset)
+                .all(|b| !b.alive);
             if all_dead {
                 retval.push(offset);
-                assert!(retval.len() <= (across as usize));
             }
         }
-        assert!(retval.len() <= (across as usize));
         retval
     }
 }
@@ -712,7 +1485,9 @@ impl StateCore {
 mod tests {
     use super::*;
     use std::collections::HashSet;
+    use toybox_core::graphics::ImageBuffer;
     use toybox_core::Simulation;
+    use toybox_core::State as _;
 
     #[test]
     fn test_colors_unique_in_gray() {
@@ -750,6 +1525,705 @@ mod tests {
         assert_eq!(bricks_remaining, num_columns * num_rows);
     }
 
+    #[test]
+    fn test_ascii_brick_layout() {
+        let mut breakout = super::Breakout {
+            brick_layout: Some(BrickLayout::Ascii(vec![
+                "...XX...".to_owned(),
+                "..XXXX..".to_owned(),
+                ".XX..XX.".to_owned(),
+                "########".to_owned(),
+            ])),
+            ..Default::default()
+        };
+        assert!(breakout.validate().is_ok());
+        let bricks = breakout.make_bricks();
+        assert_eq!(18, bricks.len());
+        assert_eq!(8, bricks.iter().filter(|b| !b.destructible).count());
+        assert!(bricks
+            .iter()
+            .filter(|b| b.row == 1)
+            .all(|b| b.color == breakout.row_colors[1] && b.points == breakout.row_scores[1]));
+
+        let state = new_state(&mut breakout);
+        assert_eq!("10", query(&state, "bricks_remaining"));
+        assert_eq!("8", query(&state, "num_columns"));
+        assert_eq!("4", query(&state, "num_rows"));
+    }
+
+    #[test]
+    fn test_ascii_brick_layout_too_wide() {
+        let mut breakout = super::Breakout {
+            brick_layout: Some(BrickLayout::Ascii(vec!["X".repeat(19)])),
+            ..Default::default()
+        };
+        assert!(breakout.validate().is_err());
+        let json = serde_json::to_string(&breakout).unwrap();
+        assert!(breakout.from_json(&json).is_err());
+
+        breakout.brick_layout = Some(BrickLayout::Ascii(vec!["X".repeat(18)]));
+        let json = serde_json::to_string(&breakout).unwrap();
+        assert!(breakout.from_json(&json).is_ok());
+    }
+
+    #[test]
+    fn test_explicit_brick_layout() {
+        let wall = |x: f64, y: f64| BrickSpec {
+            position: Vec2D::new(x, y),
+            size: Vec2D::new(30.0, 6.0),
+            points: 3,
+            color: Color::white(),
+            depth: 0,
+            destructible: false,
+            hit_points: None,
+            effect: None,
+        };
+        let mut breakout = super::Breakout {
+            brick_layout: Some(BrickLayout::Bricks(vec![wall(40.0, 60.0)])),
+            ..Default::default()
+        };
+        assert!(breakout.validate().is_ok());
+        let bricks = breakout.make_bricks();
+        assert_eq!(1, bricks.len());
+        assert_eq!(Vec2D::new(30.0, 6.0), bricks[0].size);
+        assert!(!bricks[0].destructible);
+
+        // Off the left edge of the board, and overlapping the paddle.
+        for spec in &[wall(5.0, 60.0), wall(40.0, 140.0)] {
+            breakout.brick_layout = Some(BrickLayout::Bricks(vec![spec.clone()]));
+            assert!(breakout.validate().is_err());
+        }
+    }
+
+    /// A new game of ``breakout`` as a concrete State, so tests can reach into it.
+    fn new_state(breakout: &mut Breakout) -> State {
+        State {
+            config: breakout.clone(),
+            state: serde_json::from_str(&breakout.new_game().to_json()).unwrap(),
+        }
+    }
+
+    fn query(state: &State, q: &str) -> String {
+        state.query_json(q, &serde_json::Value::Null).unwrap()
+    }
+
+    /// Break every brick except those listed in ``keep``, without scoring them.
+    fn break_bricks_except(state: &mut State, keep: &[usize]) {
+        for (i, brick) in state.state.bricks.iter_mut().enumerate() {
+            if !keep.contains(&i) {
+                brick.alive = false;
+            }
+        }
+    }
+
+    /// One ball heading straight up into the brick at ``index``.
+    fn ball_under_brick(state: &mut State, index: usize) {
+        let center = state.state.bricks[index].center();
+        let bottom = state.state.bricks[index].position.y + state.state.bricks[index].size.y;
+        state.state.is_dead = false;
+        state.state.balls = vec![Body2D::new_detailed(center.x, bottom + 3.0, 0.0, -1.0)];
+    }
+
+    #[test]
+    fn test_multi_hit_brick() {
+        let mut breakout = super::Breakout {
+            brick_layout: Some(BrickLayout::Ascii(vec!["3".to_owned()])),
+            ..Default::default()
+        };
+        let mut state = new_state(&mut breakout);
+        let full_color = state.state.bricks[0].color;
+        let hit_points = |state: &State| {
+            state
+                .query_json("brick_hit_points_by_index", &serde_json::json!(0))
+                .unwrap()
+        };
+        assert_eq!("3", hit_points(&state));
+
+        for left in &["2", "1"] {
+            ball_under_brick(&mut state, 0);
+            state.update_mut(Input::default());
+            assert_eq!(*left, hit_points(&state));
+            assert_eq!(0, state.state.score);
+            assert!(state.state.bricks[0].alive);
+        }
+        let weak = state.state.bricks[0].current_color(breakout.bg_color);
+        assert_ne!(full_color, weak);
+        assert!(weak.grayscale_byte() < full_color.grayscale_byte());
+
+        ball_under_brick(&mut state, 0);
+        state.update_mut(Input::default());
+        assert_eq!("0", hit_points(&state));
+        assert_eq!(breakout.row_scores[0], state.state.score);
+        assert_eq!("[]", query(&state, "special_bricks"));
+
+        state.state.bricks[0].revive();
+        assert_eq!("3", hit_points(&state));
+    }
+
+    #[test]
+    fn test_split_and_speed_bricks() {
+        let mut breakout = super::Breakout {
+            brick_layout: Some(BrickLayout::Ascii(vec!["*.<.>.X".to_owned()])),
+            ..Default::default()
+        };
+        let mut state = new_state(&mut breakout);
+        let specials: serde_json::Value =
+            serde_json::from_str(&query(&state, "special_bricks")).unwrap();
+        assert_eq!(3, specials.as_array().unwrap().len());
+        assert_eq!("Split", specials[0]["effect"]);
+        // The last brick is plain, so the wall isn't cleared by the special ones.
+
+        ball_under_brick(&mut state, 0);
+        state.update_mut(Input::default());
+        assert_eq!(2, state.state.balls.len());
+
+        ball_under_brick(&mut state, 2);
+        state.update_mut(Input::default());
+        assert_eq!(
+            breakout.ball_speed_fast,
+            state.state.balls[0].velocity.magnitude()
+        );
+        ball_under_brick(&mut state, 1);
+        state.state.balls[0].velocity = Vec2D::new(0.0, -breakout.ball_speed_fast);
+        state.update_mut(Input::default());
+        assert_eq!(
+            breakout.ball_speed_slow,
+            state.state.balls[0].velocity.magnitude()
+        );
+    }
+
+    #[test]
+    fn test_special_bricks_are_marked() {
+        let mut breakout = super::Breakout {
+            brick_layout: Some(BrickLayout::Ascii(vec!["*.<.>.+.^.X".to_owned()])),
+            ..Default::default()
+        };
+        let state = new_state(&mut breakout);
+        let (w, h) = screen::GAME_SIZE;
+        let mut image = ImageBuffer::alloc(w, h);
+        image.render(&state.draw());
+        let drawn = |index: usize| {
+            let (x, y) = state.state.bricks[index].center().pixels();
+            let start = ((y * w + x) * 4) as usize;
+            Color::rgb(
+                image.data[start],
+                image.data[start + 1],
+                image.data[start + 2],
+            )
+        };
+
+        let marks = [
+            &screen::SPLIT_MARK_COLOR,
+            &screen::SLOW_DOWN_MARK_COLOR,
+            &screen::SPEED_UP_MARK_COLOR,
+            &screen::WIDE_PADDLE_COLOR,
+            &screen::EXTRA_LIFE_COLOR,
+        ];
+        for (index, &mark) in marks.iter().enumerate() {
+            assert_eq!(Color::from(mark), drawn(index));
+        }
+        assert_eq!(state.state.bricks[5].color, drawn(5));
+
+        // Marks stay apart from each other and from the bricks, even in gray.
+        let mut grays: HashSet<u8> = marks
+            .iter()
+            .map(|&mark| Color::from(mark).grayscale_byte())
+            .collect();
+        assert_eq!(marks.len(), grays.len());
+        for color in breakout.row_colors.iter() {
+            assert!(grays.insert(color.grayscale_byte()));
+        }
+    }
+
+    #[test]
+    fn test_split_only_when_broken() {
+        let splitter = |x: f64, hit_points: Option<u32>, destructible: bool| BrickSpec {
+            position: Vec2D::new(x, 60.0),
+            size: Vec2D::new(12.0, 4.0),
+            points: 1,
+            color: Color::white(),
+            depth: 0,
+            destructible,
+            hit_points,
+            effect: Some(BrickEffect::Split),
+        };
+        let mut breakout = super::Breakout {
+            brick_layout: Some(BrickLayout::Bricks(vec![
+                splitter(40.0, Some(2), true),
+                splitter(80.0, None, true),
+                splitter(120.0, None, true),
+            ])),
+            multi_ball: Some(MultiBall {
+                max_balls: 2,
+                cooldown_frames: 0,
+                costs_life: false,
+            }),
+            ..Default::default()
+        };
+        assert!(breakout.validate().is_ok());
+        let mut state = new_state(&mut breakout);
+
+        // The first hit only damages the brick; the breaking hit splits.
+        ball_under_brick(&mut state, 0);
+        state.update_mut(Input::default());
+        assert_eq!(1, state.state.balls.len());
+        ball_under_brick(&mut state, 0);
+        state.update_mut(Input::default());
+        assert_eq!(2, state.state.balls.len());
+
+        // At max_balls, breaking another splitter adds nothing.
+        let parked = Body2D::new_detailed(150.0, 100.0, 0.0, 0.0);
+        ball_under_brick(&mut state, 1);
+        state.state.balls.push(parked);
+        state.update_mut(Input::default());
+        assert!(!state.state.bricks[1].alive);
+        assert_eq!(2, state.state.balls.len());
+
+        breakout.brick_layout = Some(BrickLayout::Bricks(vec![splitter(40.0, None, false)]));
+        assert!(breakout.validate().is_err());
+    }
+
+    #[test]
+    fn test_wide_paddle() {
+        let mut breakout = super::Breakout::default();
+        let mut state = new_state(&mut breakout);
+        let base = state.state.paddle_width;
+        let wide = base * screen::WIDE_PADDLE_SCALE;
+        let catch_wide_paddle = |state: &mut State| {
+            let paddle = state.state.paddle.position.clone();
+            state.state.power_ups = Some(vec![PowerUp {
+                kind: PowerUpKind::WidePaddle,
+                body: Body2D::new_pos(paddle.x, paddle.y),
+            }]);
+            state.update_power_ups(0.0);
+        };
+        let ball_into_roof = |state: &mut State| {
+            state.state.is_dead = false;
+            let y = f64::from(screen::BOARD_TOP_Y) + 3.0;
+            state.state.balls = vec![Body2D::new_detailed(100.0, y, 0.0, -2.0)];
+            state.update_mut(Input::default());
+        };
+
+        // Catching it twice doesn't compound.
+        catch_wide_paddle(&mut state);
+        catch_wide_paddle(&mut state);
+        assert_eq!(wide, state.state.paddle_width);
+
+        // Hitting the roof doesn't shrink a wide paddle...
+        ball_into_roof(&mut state);
+        assert!(state.state.balls[0].velocity.y > 0.0);
+        assert_eq!(wide, state.state.paddle_width);
+
+        // ... but losing a ball ends the power-up.
+        state.state.balls[0].position.y = 200.0;
+        state.update_mut(Input::default());
+        assert_eq!(base, state.state.paddle_width);
+
+        // A normal paddle still shrinks on the roof.
+        ball_into_roof(&mut state);
+        assert!(state.state.paddle_width < base);
+    }
+
+    #[test]
+    fn test_power_up_drop_and_catch() {
+        let mut breakout = super::Breakout {
+            brick_layout: Some(BrickLayout::Ascii(vec!["^.X".to_owned()])),
+            ..Default::default()
+        };
+        let mut state = new_state(&mut breakout);
+        // The plain brick keeps the wall from being cleared.
+        ball_under_brick(&mut state, 0);
+        state.update_mut(Input::default());
+        let power_ups = state.state.power_ups.clone().unwrap();
+        assert_eq!(1, power_ups.len());
+        assert_eq!(PowerUpKind::ExtraLife, power_ups[0].kind);
+
+        // Park the ball out of the way and put the paddle under the power-up.
+        state.state.balls = vec![Body2D::new_detailed(100.0, 100.0, 0.0, 0.0)];
+        state.state.paddle.position.x = power_ups[0].body.position.x;
+        let lives = state.state.lives;
+        for _ in 0..200 {
+            state.update_mut(Input::default());
+        }
+        assert_eq!(lives + 1, state.state.lives);
+        assert_eq!("[]", query(&state, "power_ups"));
+    }
+
+    #[test]
+    fn test_level_progression() {
+        let level = |layout: &str, slow: f64, paddle_width: f64| LevelConfig {
+            brick_layout: Some(BrickLayout::Ascii(vec![layout.to_owned()])),
+            ball_speed_slow: Some(slow),
+            ball_speed_fast: None,
+            ball_speed_row_depth: Some(0),
+            paddle_width: Some(paddle_width),
+        };
+        let mut breakout = super::Breakout {
+            levels: Some(vec![level("XXX", 1.0, 30.0), level("X.X.X", 3.0, 20.0)]),
+            ..Default::default()
+        };
+        assert!(breakout.validate().is_ok());
+        let mut state = new_state(&mut breakout);
+        assert_eq!(3, state.state.bricks.len());
+        assert_eq!(30.0, state.state.paddle_width);
+
+        let clear_level = |state: &mut State| {
+            break_bricks_except(state, &[]);
+            let level = state.state.level;
+            while state.state.level == level {
+                state.update_mut(Input::default());
+            }
+        };
+        clear_level(&mut state);
+        assert_eq!(2, state.state.level);
+        assert_eq!(3, state.state.bricks.len());
+        assert_eq!(4, state.state.bricks[2].col);
+        assert_eq!(20.0, state.state.paddle_width);
+        assert_eq!(3.0, state.state.balls[0].velocity.magnitude());
+        assert_eq!(breakout.ball_speed_fast, breakout.ball_speed_fast_at(2));
+
+        // Without looping, the last level repeats.
+        clear_level(&mut state);
+        assert_eq!(3, state.state.level);
+        assert_eq!(4, state.state.bricks[2].col);
+
+        state.config.loop_levels = Some(true);
+        clear_level(&mut state);
+        assert_eq!(4, state.state.level);
+        assert_eq!(4, state.state.bricks[2].col);
+        clear_level(&mut state);
+        assert_eq!(5, state.state.level);
+        assert_eq!(2, state.state.bricks[2].col);
+
+        breakout.levels = Some(vec![level("X", 0.0, 30.0)]);
+        assert!(breakout.validate().is_err());
+        breakout.levels = Some(Vec::new());
+        assert!(breakout.validate().is_err());
+    }
+
+    #[test]
+    fn test_level_complete() {
+        let mut breakout = super::Breakout::default();
+        let mut state = new_state(&mut breakout);
+        let fire = Input {
+            button1: true,
+            ..Default::default()
+        };
+        state.update_mut(fire);
+        assert_eq!(1, state.state.balls.len());
+        assert_eq!("false", query(&state, "level_complete"));
+
+        // Break the last brick with the ball.
+        break_bricks_except(&mut state, &[0]);
+        ball_under_brick(&mut state, 0);
+        state.update_mut(Input::default());
+        assert_eq!("true", query(&state, "level_complete"));
+        assert_eq!("0", query(&state, "bricks_remaining"));
+        assert_eq!("60", query(&state, "level_transition"));
+        assert!(state.state.balls.is_empty());
+        let lives = state.state.lives;
+
+        // The board stays empty for the transition, and the signal only lasts one frame.
+        for _ in 0..59 {
+            state.update_mut(fire);
+            assert_eq!("false", query(&state, "level_complete"));
+            assert_eq!(1, state.state.level);
+            assert!(state.state.balls.is_empty());
+        }
+        state.update_mut(Input::default());
+        assert_eq!(2, state.state.level);
+        assert_eq!(lives, state.state.lives);
+        assert_eq!("0", query(&state, "level_transition"));
+        assert!(state.state.bricks.iter().all(|b| b.alive));
+        assert_eq!(1, state.state.balls.len());
+        assert!(!state.state.is_dead);
+
+        // A wall with nothing to break never completes.
+        for brick in state.state.bricks.iter_mut() {
+            brick.destructible = false;
+        }
+        state.update_mut(Input::default());
+        assert_eq!("false", query(&state, "level_complete"));
+    }
+
+    #[test]
+    fn test_multi_ball() {
+        let mut breakout = super::Breakout {
+            multi_ball: Some(MultiBall {
+                max_balls: 3,
+                cooldown_frames: 5,
+                costs_life: true,
+            }),
+            ..Default::default()
+        };
+        let mut state = new_state(&mut breakout);
+        let fire = Input {
+            button1: true,
+            ..Input::default()
+        };
+        let extra = Input {
+            button2: true,
+            ..Input::default()
+        };
+
+        // No extra balls before the first one is served.
+        state.update_mut(extra);
+        assert_eq!("0", query(&state, "balls_in_play"));
+        state.update_mut(fire);
+        assert_eq!("1", query(&state, "balls_in_play"));
+
+        state.update_mut(extra);
+        assert_eq!("2", query(&state, "balls_in_play"));
+        assert_eq!(breakout.start_lives - 1, state.state.lives);
+        assert_eq!("5", query(&state, "multi_ball_cooldown"));
+        for _ in 0..4 {
+            state.update_mut(extra);
+        }
+        assert_eq!("2", query(&state, "balls_in_play"));
+        state.update_mut(extra);
+        assert_eq!("3", query(&state, "balls_in_play"));
+        for _ in 0..10 {
+            state.update_mut(extra);
+        }
+        assert_eq!("3", query(&state, "balls_in_play"));
+        assert_eq!(breakout.start_lives - 2, state.state.lives);
+
+        // Bricks broken by the second ball are credited to it; aim at the bottom row.
+        let index = 5;
+        let center = state.state.bricks[index].center();
+        let bottom = state.state.bricks[index].position.y + state.state.bricks[index].size.y;
+        state.state.balls[1] = Body2D::new_detailed(center.x, bottom + 3.0, 0.0, -1.0);
+        state.state.balls[0] = Body2D::new_detailed(100.0, 100.0, 0.0, 0.0);
+        state.state.balls[2] = Body2D::new_detailed(150.0, 100.0, 0.0, 0.0);
+        let before: Vec<BallStats> = serde_json::from_str(&query(&state, "ball_scores")).unwrap();
+        state.update_mut(Input::default());
+        let scores: Vec<BallStats> = serde_json::from_str(&query(&state, "ball_scores")).unwrap();
+        assert_eq!(
+            vec![0, 1, 2],
+            scores.iter().map(|s| s.id).collect::<Vec<_>>()
+        );
+        assert_eq!(before[0], scores[0]);
+        assert_eq!(
+            before[1].score + state.state.bricks[index].points,
+            scores[1].score
+        );
+        assert_eq!(before[1].bricks_broken + 1, scores[1].bricks_broken);
+
+        // Losing a ball drops its stats with it.
+        state.state.balls[0].position.y = 200.0;
+        state.state.balls[0].velocity.y = 1.0;
+        state.update_mut(Input::default());
+        let scores: Vec<BallStats> = serde_json::from_str(&query(&state, "ball_scores")).unwrap();
+        assert_eq!(vec![1, 2], scores.iter().map(|s| s.id).collect::<Vec<_>>());
+
+        // Without multi-ball rules the second button does nothing.
+        breakout.multi_ball = None;
+        state.config = breakout;
+        state.update_mut(extra);
+        assert_eq!("2", query(&state, "balls_in_play"));
+    }
+
+    #[test]
+    fn test_fast_ball_does_not_tunnel() {
+        let mut breakout = super::Breakout::default();
+        let mut state = new_state(&mut breakout);
+        // Bottom row of the first column, hit from 30 pixels below at 40 pixels per frame.
+        let index = 5;
+        ball_under_brick(&mut state, index);
+        state.state.balls[0].position.y += 27.0;
+        state.state.balls[0].velocity.y = -40.0;
+        state.update_mut(Input::default());
+
+        let broken: Vec<usize> = (0..state.state.bricks.len())
+            .filter(|i| !state.state.bricks[*i].alive)
+            .collect();
+        assert_eq!(vec![index], broken);
+        let ball = &state.state.balls[0];
+        assert_eq!(40.0, ball.velocity.y);
+        let bottom = state.state.bricks[index].position.y + state.state.bricks[index].size.y;
+        assert_eq!(bottom + 2.0 + 12.0, ball.position.y);
+    }
+
+    #[test]
+    fn test_brick_grid_cell_boundary() {
+        // The brick's right edge lies on a grid cell boundary.
+        let edge = f64::from(4 * BRICK_GRID_CELL_SIZE);
+        let mut breakout = super::Breakout {
+            brick_layout: Some(BrickLayout::Bricks(vec![BrickSpec {
+                position: Vec2D::new(edge - 12.0, 60.0),
+                size: Vec2D::new(12.0, 4.0),
+                points: 1,
+                color: Color::white(),
+                depth: 0,
+                destructible: true,
+                hit_points: None,
+                effect: None,
+            }])),
+            ..Default::default()
+        };
+        assert!(breakout.validate().is_ok());
+        let mut state = new_state(&mut breakout);
+        let radius = state.state.ball_radius;
+
+        // Half a pixel clear of the edge, the ball shares only the next cell with the brick and flies past.
+        ball_under_brick(&mut state, 0);
+        state.state.balls[0].position.x = edge + radius + 0.5;
+        for _ in 0..5 {
+            state.update_mut(Input::default());
+        }
+        assert!(state.state.bricks[0].alive);
+
+        // Half a pixel over the edge, it clips the corner.
+        ball_under_brick(&mut state, 0);
+        state.state.balls[0].position.x = edge + radius - 0.5;
+        for _ in 0..5 {
+            state.update_mut(Input::default());
+        }
+        assert!(!state.state.bricks[0].alive);
+    }
+
+    #[test]
+    fn test_paddle_models() {
+        let held = |left: bool| Input {
+            left,
+            ..Default::default()
+        };
+
+        let mut breakout = super::Breakout {
+            paddle_model: Some(PaddleModel::Momentum {
+                acceleration: 1.0,
+                max_speed: 2.5,
+                friction: 0.5,
+            }),
+            ..Default::default()
+        };
+        let mut state = new_state(&mut breakout);
+        let speeds: Vec<f64> = [true, true, true, false, false]
+            .iter()
+            .map(|left| {
+                state.update_mut(held(*left));
+                state.state.paddle.velocity.x
+            })
+            .collect();
+        assert_eq!(vec![-1.0, -2.0, -2.5, -1.25, -0.625], speeds);
+
+        breakout.paddle_model = Some(PaddleModel::Analog { max_speed: 3.0 });
+        let mut state = new_state(&mut breakout);
+        let start = state.state.paddle.position.x;
+        // The target moves 4 pixels a frame, but the paddle only 3; it catches up once released.
+        for _ in 0..2 {
+            state.update_mut(held(true));
+        }
+        assert_eq!(Some(start - 8.0), state.state.paddle_target);
+        assert_eq!(start - 6.0, state.state.paddle.position.x);
+        state.update_mut(held(false));
+        assert_eq!(start - 8.0, state.state.paddle.position.x);
+        assert_eq!(-2.0, state.state.paddle.velocity.x);
+        state.update_mut(held(false));
+        assert_eq!(0.0, state.state.paddle.velocity.x);
+    }
+
+    #[test]
+    fn test_analog_paddle() {
+        let mut breakout = super::Breakout::default();
+        let mut state = new_state(&mut breakout);
+        let start = state.state.paddle.position.x;
+        state.update_mut_analog(Input::default(), AnalogInput::new(0.5, 0.0));
+        assert_eq!(2.0, state.state.paddle.velocity.x);
+        assert_eq!(start + 2.0, state.state.paddle.position.x);
+
+        state.config.paddle_model = Some(PaddleModel::Momentum {
+            acceleration: 1.0,
+            max_speed: 5.0,
+            friction: 0.5,
+        });
+        state.state.paddle.velocity.x = 0.0;
+        let speeds: Vec<f64> = (0..4)
+            .map(|_| {
+                state.update_mut_analog(Input::default(), AnalogInput::new(-0.4, 0.0));
+                state.state.paddle.velocity.x
+            })
+            .collect();
+        assert_eq!(vec![-1.0, -2.0, -2.0, -2.0], speeds);
+        assert_eq!(
+            vec!["horizontal".to_string()],
+            breakout
+                .continuous_action_space()
+                .into_iter()
+                .map(|axis| axis.name)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_paddle_spin() {
+        let mut breakout = super::Breakout::default();
+        let state = new_state(&mut breakout);
+        let mut spinning = State {
+            config: super::Breakout {
+                paddle_spin: Some(0.5),
+                ..breakout.clone()
+            },
+            state: state.state.clone(),
+        };
+        spinning.state.paddle.velocity.x = 4.0;
+
+        // A ball landing in the middle of the paddle goes straight up, unless the paddle is moving.
+        let mut ball = Body2D::new_detailed(state.state.paddle.position.x, 0.0, 0.0, 3.0);
+        state.bounce_off_paddle(&mut ball);
+        assert!(ball.velocity.x.abs() < 1e-9);
+        assert!((ball.velocity.y + 3.0).abs() < 1e-9);
+
+        let mut ball = Body2D::new_detailed(state.state.paddle.position.x, 0.0, 0.0, 3.0);
+        spinning.bounce_off_paddle(&mut ball);
+        assert!(ball.velocity.x > 1.0);
+        assert!(ball.velocity.y < 0.0);
+        assert!((ball.velocity.magnitude() - 3.0).abs() < 1e-9);
+
+        // Lots of spin is limited to the steepest normal bounce.
+        spinning.config.paddle_spin = Some(100.0);
+        let mut ball = Body2D::new_detailed(state.state.paddle.position.x, 0.0, 0.0, 3.0);
+        spinning.bounce_off_paddle(&mut ball);
+        let angle = (-ball.velocity.y).atan2(ball.velocity.x).to_degrees();
+        assert!((angle - screen::BALL_ANGLE_MIN).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        let mut breakout = super::Breakout {
+            fixed_point: Some(true),
+            paddle_spin: Some(0.25),
+            ..Default::default()
+        };
+        breakout.reset_seed(7);
+        let mut state = breakout.new_game();
+        let on_grid = |x: f64| (x * 256.0).fract() == 0.0;
+        for frame in 0..1000 {
+            state.update_mut_analog(
+                Input {
+                    button1: true,
+                    left: frame % 50 < 20,
+                    ..Default::default()
+                },
+                AnalogInput::new(0.3, 0.0),
+            );
+
+            let frame: StateCore = serde_json::from_str(&state.to_json()).unwrap();
+            let mut bodies = frame.balls.clone();
+            bodies.push(frame.paddle.clone());
+            for body in bodies.iter() {
+                assert!(on_grid(body.position.x) && on_grid(body.position.y));
+                assert!(on_grid(body.velocity.x) && on_grid(body.velocity.y));
+            }
+            assert!(on_grid(frame.paddle_width));
+        }
+        // Integer physics plays out the same everywhere, so this is pinned exactly.
+        let frame: StateCore = serde_json::from_str(&state.to_json()).unwrap();
+        assert_eq!((1, -7), (frame.score, frame.lives));
+        assert_eq!(Vec2D::new(203.25, 128.0), frame.balls[0].position);
+        assert_eq!(Vec2D::new(1.734375, 1.0), frame.balls[0].velocity);
+    }
+
     #[test]
     fn test_q_breakout_channels() {
         let mut breakout = super::Breakout::default();
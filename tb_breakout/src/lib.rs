@@ -25,4 +25,7 @@ mod font;
 /// This module contains the core data structures used in the game.
 mod types;
 
-pub use crate::types::{Breakout, Brick, StartBall, State, StateCore};
+pub use crate::types::{
+    BallStats, Breakout, Brick, BrickEffect, BrickLayout, BrickSpec, LevelConfig, MultiBall,
+    PaddleModel, PowerUp, PowerUpKind, StartBall, State, StateCore,
+};
@@ -26,6 +26,120 @@ impl StartBall {
     }
 }
 
+/// A single brick in a custom layout; see `BrickLayout::Bricks`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BrickSpec {
+    /// Upper-left corner of the brick, in screen pixels.
+    pub position: Vec2D,
+    /// Width and height of the brick, in screen pixels.
+    pub size: Vec2D,
+    /// How many points is this brick worth?
+    pub points: i32,
+    /// What color is this brick?
+    pub color: Color,
+    /// Hitting a brick at least ``ball_speed_row_depth`` deep speeds up the ball.
+    pub depth: u32,
+    /// If false, this brick can never be destroyed; it still counts as completed.
+    pub destructible: bool,
+    /// How many hits does this brick take to break? None means one.
+    pub hit_points: Option<u32>,
+    /// What happens when the ball hits this brick? None means nothing special.
+    pub effect: Option<BrickEffect>,
+}
+
+/// Special behavior of a brick when the ball hits it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum BrickEffect {
+    /// When this brick breaks it drops a power-up that the paddle can catch.
+    PowerUp(PowerUpKind),
+    /// Every hit sets the ball to ``ball_speed_fast``.
+    SpeedUp,
+    /// Every hit sets the ball to ``ball_speed_slow``.
+    SlowDown,
+    /// Breaking this brick launches a second ball, mirrored horizontally from the first, unless ``MultiBall::max_balls`` are already in play. Indestructible bricks can't split.
+    Split,
+}
+
+/// What a caught power-up does.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum PowerUpKind {
+    /// The paddle grows to half again its width for the level, and stays that size, even through roof hits, until the next ball is lost.
+    WidePaddle,
+    /// The player gains a life.
+    ExtraLife,
+}
+
+/// A power-up falling from a broken brick toward the paddle.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PowerUp {
+    /// What happens when the paddle catches this.
+    pub kind: PowerUpKind,
+    /// Position describes the center of the power-up.
+    pub body: Body2D,
+}
+
+/// Describes the shape of the wall of bricks in a new game, instead of the Atari default.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub enum BrickLayout {
+    /// One string per row of bricks, top to bottom, on the default grid of up to 18 columns, each brick 12 pixels wide and 4 tall. Each character is one column: '.' or ' ' is a gap, '#' is an indestructible brick drawn in the frame color, and anything else is a brick colored and scored like the default row with the same index (see ``row_colors`` and ``row_scores``).
+    /// Some characters make special bricks: '2' to '9' take that many hits, '>' speeds the ball up, '<' slows it down, '*' splits the ball, '+' drops a wide-paddle power-up and '^' drops an extra life.
+    Ascii(Vec<String>),
+    /// Bricks placed anywhere inside the board, above the paddle.
+    Bricks(Vec<BrickSpec>),
+}
+
+/// One entry in ``Breakout::levels``; anything left as None uses the value from the top-level config.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LevelConfig {
+    /// The wall of bricks for this level.
+    pub brick_layout: Option<BrickLayout>,
+    /// How fast does the ball move at the start of this level?
+    pub ball_speed_slow: Option<f64>,
+    /// How fast does the ball move after breaking through to ``ball_speed_row_depth``?
+    pub ball_speed_fast: Option<f64>,
+    /// How deep must the ball break through on this level to speed up?
+    pub ball_speed_row_depth: Option<u32>,
+    /// How wide is the paddle at the start of this level? It still shrinks by a third when the ball hits the roof.
+    pub paddle_width: Option<f64>,
+}
+
+/// How the paddle moves in response to the controls; see ``Breakout::paddle_model``.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum PaddleModel {
+    /// The paddle moves at ``StateCore::paddle_speed`` while a direction is held, and stops as soon as it is released. This is the Atari behavior.
+    Instant,
+    /// The paddle speeds up by ``acceleration`` each frame a direction is held, up to ``max_speed``; otherwise its velocity is multiplied by ``friction`` each frame.
+    Momentum {
+        acceleration: f64,
+        max_speed: f64,
+        friction: f64,
+    },
+    /// Like the Atari paddle controller: the controls set a target position and the paddle chases it at up to ``max_speed``. Holding a direction moves the target by ``StateCore::paddle_speed`` each frame.
+    Analog { max_speed: f64 },
+}
+
+/// Rules for launching extra balls with the second button; see ``Breakout::multi_ball``.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MultiBall {
+    /// How many balls may the player have in play at once, counting the first? Split bricks also stop adding balls at this limit.
+    pub max_balls: u32,
+    /// How many frames must pass after a launch before the next one?
+    pub cooldown_frames: u32,
+    /// If true, each extra ball costs a life when it is launched, and cannot be launched on the last life.
+    pub costs_life: bool,
+}
+
+/// Per-ball bookkeeping, kept in the same order as ``StateCore::balls``.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct BallStats {
+    /// Unique within a game; the first ball is 0.
+    pub id: u32,
+    /// Points scored by bricks this ball broke.
+    pub score: i32,
+    /// How many bricks this ball broke.
+    pub bricks_broken: u32,
+}
+
 /// This struct represents all the static data needed to create a new game of Breakout.
 /// The data in this struct represents the Toybox config for this game.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -56,6 +170,22 @@ pub struct Breakout {
     pub ball_start_positions: Vec<StartBall>,
     /// When this is None, the paddle uses continuous logic for bouncing (imagining the paddle is kind of a circle). In the real game, some discrete math was used; i.e. the paddle behaves like a n-polygon. This could affect learning speed.
     pub paddle_discrete_segments: Option<i32>,
+    /// When this is None, new games use the Atari wall of bricks; otherwise, this describes a custom wall.
+    pub brick_layout: Option<BrickLayout>,
+    /// When this is None, every level is the same; otherwise level N is played with the Nth entry of this list.
+    pub levels: Option<Vec<LevelConfig>>,
+    /// After the last entry of ``levels``, go back to the first (true) or keep playing the last one (false or None).
+    pub loop_levels: Option<bool>,
+    /// When this is None, the second button does nothing; otherwise it launches extra balls under these rules.
+    pub multi_ball: Option<MultiBall>,
+    /// How the paddle responds to the controls; None is ``PaddleModel::Instant``. With no direction held, the horizontal analog axis stands in for one: 0.4 is 40% of a held direction.
+    pub paddle_model: Option<PaddleModel>,
+    /// When set, this fraction of the paddle's horizontal velocity is added to the ball when it bounces off the paddle ("English"). The ball keeps its speed.
+    pub paddle_spin: Option<f64>,
+    /// How many frames pass between clearing the wall and the next level starting; None is one second.
+    pub level_transition_frames: Option<u32>,
+    /// When true, the physics runs on ``toybox_core::fixed::Fixed`` numbers (1/256 px) instead of floats, so it plays out the same on every platform and the ball, paddle and power-up state stays exact in JSON.
+    pub fixed_point: Option<bool>,
 }
 
 /// This data structure represents a Brick in the breakout game. Bricks are present in state even if they are destroyed, thus the presence of the "alive" boolean.
@@ -79,6 +209,12 @@ pub struct Brick {
     pub depth: u32,
     /// Destructible: if false, never let this brick die.
     pub destructible: bool,
+    /// How many more hits until this brick breaks? None means one.
+    pub hit_points: Option<u32>,
+    /// How many hits did this brick take to break when the level started? The brick fades as it weakens.
+    pub max_hit_points: Option<u32>,
+    /// What happens when the ball hits this brick?
+    pub effect: Option<BrickEffect>,
 }
 
 /// This struct contains the per-frame snapshot of mutable state in a Breakout game.
@@ -106,8 +242,22 @@ pub struct StateCore {
     pub paddle_speed: f64,
     /// Bricks are available in a flat list.
     pub bricks: Vec<Brick>,
-    /// When set to true (from beating the level or dying), the bricks are reset to alive and a new ball is generated.
+    /// True between clearing the wall and starting the next level; the bricks are then rebuilt and a new ball is launched.
     pub reset: bool,
+    /// How many more frames of the level transition are left while ``reset`` is true.
+    pub level_transition: Option<u32>,
+    /// True only on the frame the wall was cleared.
+    pub level_complete: Option<bool>,
+    /// Power-ups dropped by broken bricks that are still falling.
+    pub power_ups: Option<Vec<PowerUp>>,
+    /// Who scored what, one entry for each ball in ``balls``.
+    pub ball_stats: Option<Vec<BallStats>>,
+    /// The id the next ball launched will get.
+    pub next_ball_id: Option<u32>,
+    /// How many frames until the second button can launch another ball.
+    pub multi_ball_cooldown: Option<u32>,
+    /// Where the paddle is heading under ``PaddleModel::Analog``; None until the paddle first moves.
+    pub paddle_target: Option<f64>,
 }
 
 /// The breakout game's true state has both the configuration that launched the game and information about the current frame.
@@ -131,6 +131,40 @@ impl FrameState {
     fn terminal(&self, tx: i32, ty: i32) -> bool {
         self.get_tile(tx, ty).map(|t| t.terminal).unwrap_or(false)
     }
+    /// Print the logical grid using the config's tile characters, with the player as 'P'.
+    fn text_grid(&self, config: &GridWorld) -> Vec<String> {
+        // The frame stores tile indices; recover the character that defines each tile.
+        let tile_chars: Vec<char> = self
+            .tiles
+            .iter()
+            .map(|tile| {
+                config
+                    .tiles
+                    .iter()
+                    .filter(|(_, desc)| *desc == tile)
+                    .map(|(ch, _)| *ch)
+                    .min()
+                    .unwrap_or('?')
+            })
+            .collect();
+        let (px, py) = self.player;
+        self.grid
+            .iter()
+            .enumerate()
+            .map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(x, tile_id)| {
+                        if (x as i32, y as i32) == (px, py) {
+                            'P'
+                        } else {
+                            tile_chars[*tile_id]
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
     /// Take a step if the destination is walkable.
     fn walk_once(&mut self, dx: i32, dy: i32) {
         let (px, py) = self.player;
@@ -331,6 +365,7 @@ impl toybox_core::State for State {
                 let (px, py) = self.frame.player;
                 serde_json::to_string(&(px, py, self.frame.step))?
             }
+            "text_grid" => serde_json::to_string(&self.frame.text_grid(&self.config))?,
             _ => Err(QueryError::NoSuchQuery)?,
         })
     }
@@ -339,3 +374,33 @@ impl toybox_core::State for State {
         Box::new(self.clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toybox_core::Simulation;
+
+    #[test]
+    fn test_text_grid_matches_config() {
+        let mut config = GridWorld::default();
+        let state = config.new_game();
+        let grid: Vec<String> = serde_json::from_str(
+            &state
+                .query_json("text_grid", &serde_json::Value::Null)
+                .unwrap(),
+        )
+        .unwrap();
+        let (px, py) = config.player_start;
+        assert_eq!(config.grid.len(), grid.len());
+        for (y, (row, expected)) in grid.iter().zip(config.grid.iter()).enumerate() {
+            assert_eq!(expected.len(), row.len());
+            for (x, (ch, config_ch)) in row.chars().zip(expected.chars()).enumerate() {
+                if (x as i32, y as i32) == (px, py) {
+                    assert_eq!('P', ch);
+                } else {
+                    assert_eq!(config_ch, ch);
+                }
+            }
+        }
+    }
+}
@@ -1,10 +1,12 @@
 use crate::types::*;
 use crate::{Body2D, Vec2D};
-use toybox_core::collision::Rect;
+use toybox_core::collision::{sweep_aabb, Aabb};
+use toybox_core::fixed::Fixed;
 use toybox_core::graphics::{load_bitmap_sprites, BitmapSpriteData};
+use toybox_core::scalar::Scalar;
 use toybox_core::{
     graphics::{Color, Drawable},
-    AleAction,
+    AleAction, AnalogInput, ContinuousAxis,
 };
 
 lazy_static! {
@@ -43,6 +45,7 @@ impl Default for Pong {
             paddle_speed: 2.0,
             max_paddle_speed: 6.0,
             game_points: 21,
+            fixed_point: None,
         }
     }
 }
@@ -52,31 +55,9 @@ impl toybox_core::Simulation for Pong {
         // No randomness in Pong.
     }
     fn new_game(&mut self) -> Box<dyn toybox_core::State + Send> {
-        let (ball_sx, ball_sy) = screen::BALL_START_POSITION;
-        let (ball_dx, ball_dy) = screen::BALL_START_VELOCITY;
-        let state = FrameState {
-            reset: true,
-            p1_score: 0,
-            p2_score: 0,
-            ball: Body2D::new_detailed(
-                ball_sx as f64,
-                ball_sy as f64,
-                ball_dx as f64,
-                ball_dy as f64,
-            ),
-            p1_paddle: Body2D::new_pos(
-                screen::P1_START_POSITION.0 as f64,
-                screen::P1_START_POSITION.1 as f64,
-            ),
-            p2_paddle: Body2D::new_pos(
-                screen::P2_START_POSITION.0 as f64,
-                // start off-screen
-                -100.0,
-            ),
-        };
         Box::new(State {
             config: self.clone(),
-            state,
+            state: FrameState::new(),
         })
     }
     fn new_state_from_json(
@@ -112,6 +93,12 @@ impl toybox_core::Simulation for Pong {
         actions.sort();
         actions
     }
+    fn continuous_action_space(&self) -> Vec<ContinuousAxis> {
+        vec![ContinuousAxis::full(
+            "vertical",
+            "Player paddle speed as a fraction of max_paddle_speed; negative is up.",
+        )]
+    }
     fn schema_for_state(&self) -> String {
         let schema = schema_for!(State);
         serde_json::to_string(&schema).expect("JSONSchema should be flawless.")
@@ -123,170 +110,246 @@ impl toybox_core::Simulation for Pong {
 }
 
 /// Trying to base this on our Breakout implementation, but turned sideways.
-fn reflect_ball(ball: &mut Body2D, paddle: &Body2D) {
-    let paddle_size = screen::PADDLE_SHAPE.1 as f64;
-    let paddle_center_y = paddle.position.y + (paddle_size / 2.0);
-    let ball_center_y: f64 = ball.position.y + (screen::BALL_SHAPE.1 as f64 / 2.0);
+impl FrameState {
+    /// The first frame of a game, about to serve.
+    fn new() -> FrameState {
+        let (ball_sx, ball_sy) = screen::BALL_START_POSITION;
+        let (ball_dx, ball_dy) = screen::BALL_START_VELOCITY;
+        FrameState {
+            reset: true,
+            p1_score: 0,
+            p2_score: 0,
+            ball: Body2D::new_detailed(
+                ball_sx as f64,
+                ball_sy as f64,
+                ball_dx as f64,
+                ball_dy as f64,
+            ),
+            p1_paddle: Body2D::new_pos(
+                screen::P1_START_POSITION.0 as f64,
+                screen::P1_START_POSITION.1 as f64,
+            ),
+            p2_paddle: Body2D::new_pos(
+                screen::P2_START_POSITION.0 as f64,
+                // start off-screen
+                -100.0,
+            ),
+        }
+    }
+}
+
+fn reflect_ball<S: Scalar>(ball: &mut Body2D<S>, paddle: &Body2D<S>) {
+    let two = S::from_f64(2.0);
+    let paddle_size = S::from_f64(screen::PADDLE_SHAPE.1.into());
+    let paddle_center_y = paddle.position.y + (paddle_size / two);
+    let ball_center_y = ball.position.y + (S::from_f64(screen::BALL_SHAPE.1.into()) / two);
     let relative_y = ball_center_y - paddle_center_y;
 
     // should be going the opposite direction of the way we're currently going.
-    let expected_y_sign = ball.velocity.y.signum() as i32;
-    let expected_x_sign = -ball.velocity.x.signum() as i32;
+    let expected_y_sign = ball.velocity.y.signum();
+    let expected_x_sign = -ball.velocity.x.signum();
 
     // get normalized location of ball hit along paddle
-    let norm_y = relative_y / (paddle_size / 2.0);
+    let norm_y = relative_y / (paddle_size / two);
     // convert this normalized parameter to the degree of the bounce angle
-    let bounce_angle = norm_y * 60.0;
+    let bounce_angle = norm_y * S::from_f64(60.0);
 
     // the breakout equation:
     let velocity = Vec2D::from_polar(ball.velocity.magnitude(), bounce_angle.to_radians());
     // calculations use non-graphics polar orientation
     // to quickly fix, we reflect over the x-axis
     let mut velocity = Vec2D::new(velocity.x, -velocity.y);
-    if velocity.y.signum() as i32 != expected_y_sign {
-        velocity.y *= -1.0;
+    if velocity.y.signum() != expected_y_sign {
+        velocity.y *= -S::ONE;
     }
-    if velocity.x.signum() as i32 != expected_x_sign {
-        velocity.x *= -1.0;
+    if velocity.x.signum() != expected_x_sign {
+        velocity.x *= -S::ONE;
     }
     // try this?
     ball.velocity = velocity;
 }
 
+/// A ball bounces at most this many times in one frame; any motion left after that is lost.
+const MAX_BOUNCES_PER_FRAME: usize = 4;
+
+/// Move the ball through one frame, bouncing off the frame and the paddle it is heading toward, in the order it touches them.
+fn move_ball<S: Scalar>(ball: &mut Body2D<S>, p1_paddle: &Body2D<S>, p2_paddle: &Body2D<S>) {
+    let (w, h) = screen::GAME_SIZE;
+    let (w, h) = (S::from_f64(w.into()), S::from_f64(h.into()));
+    let three = S::from_f64(3.0);
+    let ceiling_y = S::from_f64((screen::TOP_FRAME_Y + screen::TOP_FRAME_H).into());
+    let ceiling = Aabb::new(-w, ceiling_y - h, three * w, h);
+    // The ball sinks into the bottom frame and bounces once its top edge reaches it.
+    let floor_y = S::from_f64((screen::BOTTOM_FRAME_Y + screen::BALL_SHAPE.1).into());
+    let floor = Aabb::new(-w, floor_y, three * w, h);
+
+    let mut time_left = S::ONE;
+    for _ in 0..MAX_BOUNCES_PER_FRAME {
+        let motion = ball.velocity.scale(time_left);
+        let moving = Aabb::new(
+            ball.position.x,
+            ball.position.y,
+            S::from_f64(screen::BALL_SHAPE.0.into()),
+            S::from_f64(screen::BALL_SHAPE.1.into()),
+        );
+        // Only check the paddle the ball is heading toward.
+        let paddle = if ball.velocity.x < S::ZERO {
+            p2_paddle
+        } else {
+            p1_paddle
+        };
+        let paddle_box = Aabb::new(
+            paddle.position.x,
+            paddle.position.y,
+            S::from_f64(screen::PADDLE_SHAPE.0.into()),
+            S::from_f64(screen::PADDLE_SHAPE.1.into()),
+        );
+        let first = [
+            (Some(paddle), paddle_box),
+            (None, ceiling.clone()),
+            (None, floor.clone()),
+        ]
+        .iter()
+        .filter_map(|(paddle, target)| {
+            sweep_aabb(&moving, &motion, target).map(|contact| (*paddle, contact))
+        })
+        .min_by(|(_, a), (_, b)| {
+            a.time
+                .partial_cmp(&b.time)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        match first {
+            None => {
+                ball.position += motion;
+                return;
+            }
+            Some((paddle, contact)) => {
+                ball.position += motion.scale(contact.time);
+                time_left *= S::ONE - contact.time;
+                match paddle {
+                    Some(paddle) => reflect_ball(ball, paddle),
+                    None => ball.velocity.y *= -S::ONE,
+                }
+            }
+        }
+    }
+}
+
 /// Make this a separate method in case we want to support 2p someday.
-fn paddle_momentum(paddle: &mut Body2D, max_speed: f64, buttons: &toybox_core::Input) {
+/// With no button held, the analog ``axis`` (-1.0 to 1.0, positive is down) picks a fraction of max_speed to head toward.
+fn paddle_momentum<S: Scalar>(
+    paddle: &mut Body2D<S>,
+    max_speed: S,
+    buttons: &toybox_core::Input,
+    axis: S,
+) {
+    let step = S::from_f64(0.5);
     if buttons.left {
         if paddle.velocity.y < max_speed {
-            paddle.velocity.y += 0.5;
+            paddle.velocity.y += step;
         }
     } else if buttons.right {
         if paddle.velocity.y > -max_speed {
-            paddle.velocity.y -= 0.5;
+            paddle.velocity.y -= step;
         }
+    } else if axis != S::ZERO {
+        let target = axis * max_speed;
+        paddle.velocity.y += (target - paddle.velocity.y).clamp(-step, step);
     } else {
         // slow down:
-        paddle.velocity.y *= 0.7;
+        paddle.velocity.y *= S::from_f64(0.7);
     }
-    paddle.integrate_mut(1.0);
+    paddle.integrate_mut(S::ONE);
 
-    let paddle_center = paddle.position.y + (screen::PADDLE_SHAPE.1 as f64) / 2.0;
-    let top = (screen::TOP_FRAME_Y + screen::TOP_FRAME_H) as f64;
-    let bottom = (screen::BOTTOM_FRAME_Y) as f64;
-    if paddle_center < top && paddle.velocity.y < 0.0 {
-        paddle.velocity.y = 0.0;
-    } else if paddle_center > bottom && paddle.velocity.y > 0.0 {
-        paddle.velocity.y = 0.0;
+    let paddle_center =
+        paddle.position.y + S::from_f64(screen::PADDLE_SHAPE.1.into()) / S::from_f64(2.0);
+    let top = S::from_f64((screen::TOP_FRAME_Y + screen::TOP_FRAME_H).into());
+    let bottom = S::from_f64(screen::BOTTOM_FRAME_Y.into());
+    if paddle_center < top && paddle.velocity.y < S::ZERO {
+        paddle.velocity.y = S::ZERO;
+    } else if paddle_center > bottom && paddle.velocity.y > S::ZERO {
+        paddle.velocity.y = S::ZERO;
     }
 }
 
-impl toybox_core::State for State {
-    fn lives(&self) -> i32 {
-        // how many more points can p1 lose?
-        self.config.game_points - self.state.p2_score
-    }
-    fn score(&self) -> i32 {
-        // how many points do we have?
-        self.state.p1_score
-    }
-    fn level(&self) -> i32 {
-        0
-    }
-    fn update_mut(&mut self, buttons: toybox_core::Input) {
+impl State {
+    /// Everything that happens in one frame, with the physics done in ``S``; see ``Pong::fixed_point``.
+    fn update_frame<S: Scalar>(&mut self, buttons: toybox_core::Input, analog: AnalogInput) {
+        let mut ball: Body2D<S> = self.state.ball.cast();
+        let mut p1_paddle: Body2D<S> = self.state.p1_paddle.cast();
+        let mut p2_paddle: Body2D<S> = self.state.p2_paddle.cast();
         if self.state.reset {
+            let (ball_sx, ball_sy) = screen::BALL_START_POSITION;
+            let (ball_dx, ball_dy) = screen::BALL_START_VELOCITY;
             // reset enemy paddle.
-            self.state.p2_paddle.position.y = screen::P2_START_POSITION.1 as f64;
+            p2_paddle.position.y = S::from_f64(screen::P2_START_POSITION.1.into());
             // re-launch ball:
-            self.state.ball.position.x = screen::BALL_START_POSITION.0 as f64;
-            self.state.ball.position.y = screen::BALL_START_POSITION.1 as f64;
+            ball.position = Vec2D::new(S::from_f64(ball_sx.into()), S::from_f64(ball_sy.into()));
             // reset velocity
-            self.state.ball.velocity.x = screen::BALL_START_VELOCITY.0 as f64;
-            self.state.ball.velocity.y = screen::BALL_START_VELOCITY.1 as f64;
+            ball.velocity = Vec2D::new(S::from_f64(ball_dx.into()), S::from_f64(ball_dy.into()));
             // don't keep doing this!
             self.state.reset = false;
         }
         paddle_momentum(
-            &mut self.state.p1_paddle,
-            self.config.max_paddle_speed,
+            &mut p1_paddle,
+            S::from_f64(self.config.max_paddle_speed),
             &buttons,
+            S::from_f64(analog.vertical),
         );
 
-        let ball_x = self.state.ball.position.x;
-        let ball_y = self.state.ball.position.y;
-        let ball_dx = self.state.ball.velocity.x;
-        let ball_dy = self.state.ball.velocity.y;
-        let p2_y = self.state.p2_paddle.position.y;
-
-        if ball_x < 0.0 {
+        if ball.position.x < S::ZERO {
             self.state.p1_score += 1;
             self.state.reset = true;
-            return;
-        } else if ball_x >= screen::GAME_SIZE.1 as f64 {
+        } else if ball.position.x >= S::from_f64(screen::GAME_SIZE.1.into()) {
             self.state.p2_score += 1;
             self.state.reset = true;
-            return;
-        }
-
-        // P2 AI:
-        // AI clearly tries to hit ball in same spot of paddle each time:
-        // roughly the size of the ball down from the top.
-        let target = screen::BALL_SHAPE.1 as f64;
-        let dest = ball_y - target;
-        let speed = self.config.paddle_speed;
-        // move towards ball constantly, limited by ball-speed.
-        if (p2_y - dest).abs() <= speed {
-            self.state.p2_paddle.position.y = dest;
-        } else if p2_y < dest {
-            self.state.p2_paddle.position.y += speed;
         } else {
-            self.state.p2_paddle.position.y -= speed;
-        }
-
-        let ball = Rect::new(
-            self.state.ball.position.x as i32,
-            self.state.ball.position.y as i32,
-            screen::BALL_SHAPE.0,
-            screen::BALL_SHAPE.1,
-        );
-        let p1 = Rect::new(
-            self.state.p1_paddle.position.x as i32,
-            self.state.p1_paddle.position.y as i32,
-            screen::PADDLE_SHAPE.0,
-            screen::PADDLE_SHAPE.1,
-        );
-        let p2 = Rect::new(
-            self.state.p2_paddle.position.x as i32,
-            self.state.p2_paddle.position.y as i32,
-            screen::PADDLE_SHAPE.0,
-            screen::PADDLE_SHAPE.1,
-        );
-
-        // Now check bouncing.
-        if ball_dx < 0.0 {
-            // compare to p2
-            if ball.intersects(&p2) {
-                reflect_ball(&mut self.state.ball, &self.state.p2_paddle);
-            }
-        } else {
-            // compare to p1
-            if ball.intersects(&p1) {
-                reflect_ball(&mut self.state.ball, &self.state.p1_paddle);
+            // P2 AI:
+            // AI clearly tries to hit ball in same spot of paddle each time:
+            // roughly the size of the ball down from the top.
+            let target = S::from_f64(screen::BALL_SHAPE.1.into());
+            let dest = ball.position.y - target;
+            let speed = S::from_f64(self.config.paddle_speed);
+            let p2_y = p2_paddle.position.y;
+            // move towards ball constantly, limited by ball-speed.
+            if (p2_y - dest).abs() <= speed {
+                p2_paddle.position.y = dest;
+            } else if p2_y < dest {
+                p2_paddle.position.y += speed;
+            } else {
+                p2_paddle.position.y -= speed;
             }
+
+            move_ball(&mut ball, &p1_paddle, &p2_paddle);
         }
 
-        // check floor/ceiling bounce:
-        if ball_dy < 0.0 {
-            // ceiling:
-            if ball_y < (screen::TOP_FRAME_Y + screen::TOP_FRAME_H) as f64 {
-                self.state.ball.velocity.y *= -1.0;
-            }
+        self.state.ball = ball.cast();
+        self.state.p1_paddle = p1_paddle.cast();
+        self.state.p2_paddle = p2_paddle.cast();
+    }
+}
+
+impl toybox_core::State for State {
+    fn lives(&self) -> i32 {
+        // how many more points can p1 lose?
+        self.config.game_points - self.state.p2_score
+    }
+    fn score(&self) -> i32 {
+        // how many points do we have?
+        self.state.p1_score
+    }
+    fn level(&self) -> i32 {
+        0
+    }
+    fn update_mut(&mut self, buttons: toybox_core::Input) {
+        self.update_mut_analog(buttons, AnalogInput::default());
+    }
+    fn update_mut_analog(&mut self, buttons: toybox_core::Input, analog: AnalogInput) {
+        if self.config.fixed_point.unwrap_or(false) {
+            self.update_frame::<Fixed>(buttons, analog);
         } else {
-            if ball_y > screen::BOTTOM_FRAME_Y as f64 {
-                self.state.ball.velocity.y *= -1.0;
-            }
+            self.update_frame::<f64>(buttons, analog);
         }
-
-        // Update ball:
-        self.state.ball.integrate_mut(1.0);
     }
     fn draw(&self) -> Vec<toybox_core::graphics::Drawable> {
         let mut output = Vec::new();
@@ -386,3 +449,135 @@ fn draw_number(output: &mut Vec<Drawable>, x: i32, y: i32, color: Color, value:
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toybox_core::{Input, State as _};
+
+    fn new_state() -> State {
+        let mut state = State {
+            config: Pong::default(),
+            state: FrameState::new(),
+        };
+        state.state.reset = false;
+        state
+    }
+
+    #[test]
+    fn test_fast_ball_does_not_tunnel_through_paddle() {
+        let paddle = Body2D::new_pos(
+            screen::P1_START_POSITION.0.into(),
+            screen::P1_START_POSITION.1.into(),
+        );
+        let far = Body2D::new_pos(screen::P2_START_POSITION.0.into(), -100.0);
+        // Five times the paddle's thickness per frame, starting just short of it.
+        let speed = f64::from(screen::PADDLE_SHAPE.0 * 5);
+        let mut ball =
+            Body2D::new_detailed(paddle.position.x - 6.0, paddle.position.y + 6.0, speed, 0.0);
+        move_ball(&mut ball, &paddle, &far);
+        assert!(ball.velocity.x < 0.0);
+        assert!(ball.position.x + f64::from(screen::BALL_SHAPE.0) <= paddle.position.x);
+    }
+
+    #[test]
+    fn test_ceiling_and_floor_bounce() {
+        let far = Body2D::new_pos(0.0, -100.0);
+        let ceiling = f64::from(screen::TOP_FRAME_Y + screen::TOP_FRAME_H);
+        let mut ball = Body2D::new_detailed(80.0, ceiling + 1.0, 0.0, -3.0);
+        move_ball(&mut ball, &far, &far);
+        assert_eq!(3.0, ball.velocity.y);
+        assert_eq!(ceiling + 2.0, ball.position.y);
+
+        // The floor bounce waits for the top of the ball to reach the frame.
+        let floor = f64::from(screen::BOTTOM_FRAME_Y);
+        let mut ball = Body2D::new_detailed(80.0, floor - 1.0, 0.0, 3.0);
+        move_ball(&mut ball, &far, &far);
+        assert_eq!(-3.0, ball.velocity.y);
+        assert_eq!(floor - 2.0, ball.position.y);
+    }
+
+    #[test]
+    fn test_scoring_at_both_goals() {
+        let mut state = new_state();
+        state.state.ball.position.x = -1.0;
+        state.update_mut(Input::default());
+        assert_eq!((1, 0), (state.state.p1_score, state.state.p2_score));
+        assert!(state.state.reset);
+
+        state.update_mut(Input::default());
+        assert!(!state.state.reset);
+        state.state.ball.position.x = f64::from(screen::GAME_SIZE.1);
+        state.update_mut(Input::default());
+        assert_eq!((1, 1), (state.state.p1_score, state.state.p2_score));
+        assert_eq!(Pong::default().game_points - 1, state.lives());
+    }
+
+    #[test]
+    fn test_analog_paddle_momentum() {
+        let middle = f64::from(screen::P1_START_POSITION.1);
+        let mut paddle = Body2D::new_pos(0.0, middle);
+        // Half the stick heads for half of max_speed, half a pixel per frame at a time.
+        let speeds: Vec<f64> = (0..8)
+            .map(|_| {
+                paddle_momentum(&mut paddle, 6.0, &Input::default(), 0.5);
+                paddle.velocity.y
+            })
+            .collect();
+        assert_eq!(vec![0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.0, 3.0], speeds);
+
+        // Pushing the other way slows the paddle half a pixel per frame rather than stopping it dead.
+        paddle_momentum(&mut paddle, 6.0, &Input::default(), -0.5);
+        assert_eq!(2.5, paddle.velocity.y);
+
+        // Buttons win over the stick.
+        let up = Input {
+            right: true,
+            ..Default::default()
+        };
+        paddle_momentum(&mut paddle, 6.0, &up, 1.0);
+        assert_eq!(2.0, paddle.velocity.y);
+    }
+
+    #[test]
+    fn test_analog_moves_player_paddle() {
+        let mut state = new_state();
+        let start = state.state.p1_paddle.position.y;
+        for _ in 0..10 {
+            state.update_mut_analog(Input::default(), AnalogInput::new(0.0, 1.0));
+        }
+        assert!(state.state.p1_paddle.position.y > start);
+        assert!(state.state.p1_paddle.velocity.y > 0.0);
+        // The horizontal axis means nothing to Pong.
+        let mut still = new_state();
+        still.update_mut_analog(Input::default(), AnalogInput::new(1.0, 0.0));
+        assert_eq!(start, still.state.p1_paddle.position.y);
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        let mut state = new_state();
+        state.config.fixed_point = Some(true);
+        let on_grid = |x: f64| (x * 256.0).fract() == 0.0;
+        for frame in 0..1500 {
+            let axis = if frame % 80 < 40 { 0.6 } else { -0.3 };
+            state.update_mut_analog(Input::default(), AnalogInput::new(0.0, axis));
+            let bodies = [
+                &state.state.ball,
+                &state.state.p1_paddle,
+                &state.state.p2_paddle,
+            ];
+            for body in bodies.iter() {
+                assert!(on_grid(body.position.x) && on_grid(body.position.y));
+                assert!(on_grid(body.velocity.x) && on_grid(body.velocity.y));
+            }
+        }
+        // Integer physics plays out the same everywhere, so this is pinned exactly.
+        assert_eq!((1, 16), (state.state.p1_score, state.state.p2_score));
+        assert_eq!(Vec2D::new(185.828125, 172.4375), state.state.ball.position);
+        assert_eq!(
+            Vec2D::new(3.08984375, 0.69140625),
+            state.state.ball.velocity
+        );
+    }
+}
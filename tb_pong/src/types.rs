@@ -20,6 +20,8 @@ pub struct Pong {
     pub paddle_speed: f64,
     /// Maximum paddle_speed:
     pub max_paddle_speed: f64,
+    /// When true, the physics runs on ``toybox_core::fixed::Fixed`` numbers (1/256 px) instead of floats, so it plays out the same on every platform and the ball and paddle state stays exact in JSON.
+    pub fixed_point: Option<bool>,
 }
 
 /// This represents the per-frame snapshot of mutable state in a Pong game.
@@ -1,3 +1,4 @@
+use crate::types::Erosion;
 use toybox_core::collision::Rect;
 use toybox_core::graphics::Color;
 
@@ -5,11 +6,27 @@ pub fn collision_fallout(laser: &Rect, x: i32, y: i32) -> bool {
     laser.contains_xy(x, y)
 }
 
-/// Detect a collision between a rectangle and a sprite, and delete pixels in the intersection.
-pub fn destructive_collide(laser: &Rect, x: i32, y: i32, sprite: &mut Vec<Vec<Color>>) -> bool {
+/// Detect a collision between a rectangle and a sprite, and erode pixels around the intersection.
+pub fn destructive_collide(
+    laser: &Rect,
+    x: i32,
+    y: i32,
+    sprite: &mut [Vec<Color>],
+    erosion: &Erosion,
+) -> bool {
     let mut hit = Vec::new();
-    for (yi, row) in sprite.iter().enumerate() {
-        for (xi, color) in row.iter().enumerate() {
+    // Only look at the rows and columns the laser covers, rather than every pixel of the sprite.
+    let rows = (laser.y1() - y).max(0)..=(laser.y2() - y);
+    for (yi, row) in sprite.iter().enumerate().skip(*rows.start() as usize) {
+        if yi as i32 > *rows.end() {
+            break;
+        }
+        let first = (laser.x1() - x).max(0) as usize;
+        let last = laser.x2() - x;
+        for (xi, color) in row.iter().enumerate().skip(first) {
+            if xi as i32 > last {
+                break;
+            }
             if color.is_visible() {
                 let px = (xi as i32) + x;
                 let py = (yi as i32) + y;
@@ -21,17 +38,43 @@ pub fn destructive_collide(laser: &Rect, x: i32, y: i32, sprite: &mut Vec<Vec<Co
         }
     }
 
-    for (x, y) in hit.iter() {
-        // Align any laser hits to 2x2 grid.
-        let px = ((x / 2) * 2) as usize;
-        let py = ((y / 2) * 2) as usize;
-
-        // Take 2x2 bites out of the shield:
-        sprite[py][px] = Color::invisible();
-        sprite[py + 1][px] = Color::invisible();
-        sprite[py][px + 1] = Color::invisible();
-        sprite[py + 1][px + 1] = Color::invisible();
+    for &(x, y) in hit.iter() {
+        let (x, y) = (x as i32, y as i32);
+        match *erosion {
+            Erosion::Block { width, height } => {
+                // Align any laser hits to the grid, and take a bite out of the shield:
+                let (px, py) = ((x / width) * width, (y / height) * height);
+                for by in py..py + height {
+                    for bx in px..px + width {
+                        clear_pixel(sprite, bx, by);
+                    }
+                }
+            }
+            Erosion::Radius { radius } => {
+                for dy in -radius..=radius {
+                    for dx in -radius..=radius {
+                        if dx * dx + dy * dy <= radius * radius {
+                            clear_pixel(sprite, x + dx, y + dy);
+                        }
+                    }
+                }
+            }
+            Erosion::Indestructible => {}
+        }
     }
 
     !hit.is_empty()
 }
+
+/// Make a pixel of the sprite invisible, if it is inside the sprite.
+fn clear_pixel(sprite: &mut [Vec<Color>], x: i32, y: i32) {
+    if x < 0 || y < 0 {
+        return;
+    }
+    if let Some(px) = sprite
+        .get_mut(y as usize)
+        .and_then(|row| row.get_mut(x as usize))
+    {
+        *px = Color::invisible();
+    }
+}
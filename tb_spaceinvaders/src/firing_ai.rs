@@ -1,31 +1,179 @@
 /// A collection of the possible firing protocols
+use crate::spaceinvaders::screen;
 use crate::types::{SpaceInvaders, StateCore};
 use rand::Rng;
 use schemars::JsonSchema;
 
+/// How many frames pass between enemy shots, unless the firing AI says otherwise.
+pub const DEFAULT_SHOT_DELAY: i32 = 50;
+/// How many enemy lasers may be on screen at once, unless the firing AI says otherwise.
+const DEFAULT_MAX_LASERS: usize = 2;
+
 /// This enum represents the different enemy AI for firing in Space Invaders.
-#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
 pub enum FiringAI {
     /// The default AI trades off between aiming at the user and random firing based on config.jitter.
     TargetPlayer,
+    /// Every enemy that can fire is equally likely to.
+    Random,
+    /// Columns take turns firing, left to right, then back to the leftmost.
+    RoundRobin,
+    /// Like TargetPlayer, but aims where the ship will be when the laser gets there, if it keeps moving the way it just did.
+    LeadTarget,
+    /// Fire ``shots`` lasers ``gap`` frames apart, then wait ``cooldown`` frames; shooters are picked as by TargetPlayer.
+    Burst { shots: u32, gap: i32, cooldown: i32 },
+    /// Follow a fixed list of shots, starting over at the end.
+    Scripted { schedule: Vec<ScriptedShot> },
+}
+
+/// One step of a ``FiringAI::Scripted`` schedule.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+pub struct ScriptedShot {
+    /// How many frames to wait after the previous shot.
+    pub wait: i32,
+    /// Which column fires; if it has no enemies left, the enemy closest to the player fires instead.
+    pub column: i32,
+}
+
+impl FiringAI {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            FiringAI::Burst {
+                shots,
+                gap,
+                cooldown,
+            } => {
+                if *shots == 0 || *gap < 1 || *cooldown < 1 {
+                    return Err(format!(
+                        "Burst needs at least one shot and positive gap and cooldown; got shots={}, gap={}, cooldown={}.",
+                        shots, gap, cooldown
+                    ));
+                }
+            }
+            FiringAI::Scripted { schedule } => {
+                if schedule.is_empty() {
+                    return Err("Scripted firing needs at least one shot.".to_string());
+                }
+                if let Some(i) = schedule.iter().position(|shot| shot.wait < 1) {
+                    return Err(format!("Scripted shot {} must wait at least one frame.", i));
+                }
+            }
+            FiringAI::TargetPlayer
+            | FiringAI::Random
+            | FiringAI::RoundRobin
+            | FiringAI::LeadTarget => {}
+        }
+        Ok(())
+    }
+
+    /// How many frames before the first enemy shot of a game.
+    pub fn first_shot_delay(&self) -> i32 {
+        match self {
+            FiringAI::Scripted { schedule } => schedule[0].wait,
+            _ => DEFAULT_SHOT_DELAY,
+        }
+    }
+
+    /// How many enemy lasers may be on screen at once.
+    pub fn max_lasers(&self) -> usize {
+        match self {
+            FiringAI::Burst { shots, .. } => DEFAULT_MAX_LASERS.max(*shots as usize),
+            _ => DEFAULT_MAX_LASERS,
+        }
+    }
+
+    /// How many frames to wait after a shot, given how many shots have now been fired.
+    pub fn next_shot_delay(&self, shots_fired: u32) -> i32 {
+        match self {
+            FiringAI::Burst {
+                shots,
+                gap,
+                cooldown,
+            } => {
+                if shots_fired % shots == 0 {
+                    *cooldown
+                } else {
+                    *gap
+                }
+            }
+            FiringAI::Scripted { schedule } => schedule[shots_fired as usize % schedule.len()].wait,
+            _ => DEFAULT_SHOT_DELAY,
+        }
+    }
+}
+
+/// Pick any enemy that can fire.
+fn random_shooter(state: &mut StateCore) -> u32 {
+    let active_ids = state.active_weapon_enemy_ids();
+    active_ids[state.rand.gen_range(0, active_ids.len())]
 }
 
 // Note: using the RNG makes us mutable.
 fn target_player(state: &mut StateCore, config: &SpaceInvaders) -> u32 {
     let p = config.jitter;
     let r: f64 = state.rand.gen();
-    assert!(r >= 0. && r < 1.);
+    assert!((0.0..1.0).contains(&r));
     if r < p {
-        let active_ids = state.active_weapon_enemy_ids();
-        active_ids[state.rand.gen_range(0, active_ids.len()) as usize]
+        random_shooter(state)
     } else {
         // Get active enemy closest to the player
         state.closest_enemy_id()
     }
 }
 
+/// The next column to the right of the last shooter that can fire, wrapping around.
+fn round_robin(state: &StateCore) -> u32 {
+    let mut active_ids = state.active_weapon_enemy_ids();
+    active_ids.sort_by_key(|id| state.enemies[*id as usize].col);
+    let last_col = state
+        .last_shooter
+        .map(|id| state.enemies[id as usize].col)
+        .unwrap_or(-1);
+    active_ids
+        .iter()
+        .find(|id| state.enemies[**id as usize].col > last_col)
+        .or_else(|| active_ids.first())
+        .cloned()
+        .unwrap_or(0)
+}
+
+fn lead_target(state: &mut StateCore, config: &SpaceInvaders) -> u32 {
+    let r: f64 = state.rand.gen();
+    if r < config.jitter {
+        return random_shooter(state);
+    }
+    let ship = &state.ship;
+    let ship_x = ship.x + ship.w / 2;
+    let mut best = (i32::MAX, 0);
+    for id in state.active_weapon_enemy_ids() {
+        let shooter = state.enemies[id as usize].rect();
+        // Lasers fall a few pixels a frame from the middle of the shooter.
+        let frames = (ship.y - shooter.center_y()).max(0) / screen::LASER_SPEED;
+        let predicted = ship_x + ship.last_move.unwrap_or(0) * frames;
+        let miss = (shooter.center_x() - predicted).abs();
+        if miss < best.0 {
+            best = (miss, id);
+        }
+    }
+    best.1
+}
+
+/// The bottom enemy of the scheduled column, or the closest one to the player.
+fn scripted(state: &StateCore, schedule: &[ScriptedShot]) -> u32 {
+    let shot = &schedule[state.shots_fired.unwrap_or(0) as usize % schedule.len()];
+    state
+        .active_weapon_enemy_ids()
+        .into_iter()
+        .find(|id| state.enemies[*id as usize].col == shot.column)
+        .unwrap_or_else(|| state.closest_enemy_id())
+}
+
 pub fn enemy_fire_lasers(state: &mut StateCore, config: &SpaceInvaders) -> u32 {
-    match config.enemy_protocol {
-        FiringAI::TargetPlayer => target_player(state, config),
+    match &config.enemy_protocol {
+        FiringAI::TargetPlayer | FiringAI::Burst { .. } => target_player(state, config),
+        FiringAI::Random => random_shooter(state),
+        FiringAI::RoundRobin => round_robin(state),
+        FiringAI::LeadTarget => lead_target(state, config),
+        FiringAI::Scripted { schedule } => scripted(state, schedule),
     }
 }
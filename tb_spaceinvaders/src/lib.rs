@@ -18,10 +18,19 @@ mod types;
 
 // All types are essentially "public" API.
 pub use crate::firing_ai::FiringAI;
+pub use crate::firing_ai::ScriptedShot;
 pub use crate::types::Enemy;
+pub use crate::types::Erosion;
+pub use crate::types::GroundRule;
 pub use crate::types::Laser;
 pub use crate::types::Player;
+pub use crate::types::PlayerSlot;
+pub use crate::types::ShieldShape;
 pub use crate::types::SpaceInvaders;
+pub use crate::types::Spread;
 pub use crate::types::State;
 pub use crate::types::StateCore;
+pub use crate::types::TwoPlayerMode;
 pub use crate::types::Ufo;
+pub use crate::types::Wave;
+pub use crate::types::Weapon;
@@ -5,10 +5,12 @@ use crate::firing_ai::{enemy_fire_lasers, FiringAI};
 use access_json::JSONQuery;
 use itertools::Itertools;
 use serde_json;
-use toybox_core::collision::Rect;
+use std::borrow::Cow;
+use toybox_core::collision::{Rect, SpatialGrid};
+use toybox_core::fixed::Fixed;
 use toybox_core::graphics::{Color, Drawable, FixedSpriteData, SpriteData};
 use toybox_core::random;
-use toybox_core::{AleAction, Direction, Input, QueryError};
+use toybox_core::{AleAction, AnalogInput, ContinuousAxis, Direction, Input, QueryError};
 
 pub mod screen {
     pub const GAME_SIZE: (i32, i32) = (320, 210);
@@ -57,6 +59,8 @@ pub mod screen {
 
     pub const LASER_SIZE_W: i32 = 2;
     pub const LASER_SIZE_H1: i32 = 11;
+    /// Lasers move this many pixels per frame.
+    pub const LASER_SPEED: i32 = 3;
 
     // Colors:
     pub const LEFT_GAME_DOT_COLOR: (u8, u8, u8) = (64, 124, 64);
@@ -199,8 +203,13 @@ pub fn load_sprite(
         }
         pixels.push(pixel_row);
     }
-    let width = pixels[0].len();
-    debug_assert!(pixels.iter().all(|row| row.len() == width));
+    let width = pixels
+        .first()
+        .map(|row: &Vec<Color>| row.len())
+        .unwrap_or(0);
+    if width == 0 || pixels.iter().any(|row| row.len() != width) {
+        return Err("Sprite rows must all be the same, non-zero width.".to_string());
+    }
     Ok(SpriteData::new(pixels))
 }
 pub fn load_sprite_dynamic(data: &str, on_color: Color) -> Result<SpriteData, String> {
@@ -211,7 +220,7 @@ pub fn load_sprite_default(data: &str, on_color: Color) -> FixedSpriteData {
 }
 
 pub fn get_invader_sprite(enemy: &Enemy, orientation: bool) -> FixedSpriteData {
-    let row = enemy.row;
+    let row = enemy.kind.unwrap_or(enemy.row);
     if let Some(dc) = enemy.death_counter {
         for i in 0..4 {
             let bound = screen::DEATH_TIME - (screen::DEATH_HIT_1 + i * screen::DEATH_HIT_N);
@@ -302,10 +311,331 @@ impl Default for SpaceInvaders {
                 screen::SHIELD2_POS,
                 screen::SHIELD3_POS,
             ],
+            shield_shape: None,
+            shield_erosion: None,
+            descent_step: None,
+            speedups: None,
+            ground_rule: None,
+            formation: None,
+            two_player: None,
+            weapon: None,
+            waves: None,
+            shield: None,
         }
     }
 }
 
+impl SpaceInvaders {
+    /// Decode the shield unless it already was, then validate; games are only started from prepared configs.
+    pub fn prepare(&mut self) -> Result<(), String> {
+        if let Cow::Owned(sprite) = self.shield()? {
+            self.shield = Some(DecodedShield {
+                shape: self.shield_shape.clone(),
+                sprite,
+            });
+        }
+        self.validate()
+    }
+
+    /// Check that the firing AI, shields, weapon, formation and waves make sense before a game starts.
+    pub fn validate(&self) -> Result<(), String> {
+        self.enemy_protocol.validate()?;
+        self.validate_shields()?;
+        let weapon = self.weapon();
+        if weapon.max_shots == 0 || weapon.speed < 1 || weapon.cooldown < 0 {
+            return Err(format!(
+                "The weapon needs at least one shot, a positive speed and no negative cooldown; got {:?}.",
+                weapon
+            ));
+        }
+        if weapon.size.0 < 1 || weapon.size.1 < 1 {
+            return Err(format!(
+                "Laser size must be positive; got {:?}.",
+                weapon.size
+            ));
+        }
+        if let Some(spread) = &weapon.spread {
+            if spread.shots == 0 || spread.shots > weapon.max_shots {
+                return Err(format!(
+                    "A spread of {} shots does not fit in max_shots={}.",
+                    spread.shots, weapon.max_shots
+                ));
+            }
+            if spread.shots % 2 == 0 && spread.drift % 2 != 0 {
+                return Err(format!(
+                    "An even spread of {} shots needs an even drift to stay symmetric; got drift={}.",
+                    spread.shots, spread.drift
+                ));
+            }
+        }
+        if let Some(formation) = &self.formation {
+            parse_formation(formation)?;
+        }
+        let speedups = self.speedups();
+        if speedups
+            .iter()
+            .any(|&(kills, frames)| kills < 0 || frames < 1)
+            || speedups.windows(2).any(|pair| pair[0].0 >= pair[1].0)
+        {
+            return Err(format!(
+                "speedups must be (kills, frames) with increasing kills and positive frames; got {:?}.",
+                speedups
+            ));
+        }
+        if let Some(waves) = &self.waves {
+            if waves.is_empty() {
+                return Err("waves must not be empty; leave it out to use the default.".to_string());
+            }
+            for (i, wave) in waves.iter().enumerate() {
+                self.validate_wave(wave)
+                    .map_err(|e| format!("Wave {}: {}", i, e))?;
+            }
+        } else {
+            self.validate_wave(&self.wave(1))?;
+        }
+        Ok(())
+    }
+
+    fn validate_wave(&self, wave: &Wave) -> Result<(), String> {
+        let enemies = parse_formation(self.wave_formation(wave))?;
+        if wave.start_y < 0 {
+            return Err(format!(
+                "start_y must not be negative; got {}.",
+                wave.start_y
+            ));
+        }
+        if wave.march_period < 1 {
+            return Err(format!(
+                "march_period must be at least 1; got {}.",
+                wave.march_period
+            ));
+        }
+        let (dx, dy) = wave.march_delta;
+        if dx < 1 || dy < 0 {
+            return Err(format!(
+                "march_delta must move right and not up; got {:?}.",
+                wave.march_delta
+            ));
+        }
+        // The formation needs room to march sideways, or it would only ever step down.
+        let cols = enemies
+            .iter()
+            .map(|&(_, col, _)| col + 1)
+            .max()
+            .unwrap_or(0);
+        let width = cols * (screen::ENEMY_SIZE.0 + screen::ENEMY_SPACE.0) - screen::ENEMY_SPACE.0;
+        if width + dx > screen::ENEMY_END_POS.0 - screen::ENEMY_START_POS.0 {
+            return Err(format!(
+                "A formation {} invaders wide has no room to march.",
+                cols
+            ));
+        }
+        // Nor may it start already on top of the shields or the ground.
+        let rows = enemies
+            .iter()
+            .map(|&(row, _, _)| row + 1)
+            .max()
+            .unwrap_or(0);
+        let bottom = wave.start_y + rows * (screen::ENEMY_SIZE.1 + screen::ENEMY_SPACE.1)
+            - screen::ENEMY_SPACE.1;
+        let floor = self
+            .shields
+            .iter()
+            .map(|&(_, y)| y)
+            .fold(screen::SKY_TO_GROUND, i32::min);
+        if bottom > floor {
+            return Err(format!(
+                "A formation {} invaders deep starting at y={} reaches y={}, past the shields or ground at y={}.",
+                rows, wave.start_y, bottom, floor
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_shields(&self) -> Result<(), String> {
+        let sprite = self.shield()?;
+        let (w, h) = (sprite.width(), sprite.height());
+        // Shields have to stay clear of the ship, or it could never move past them.
+        let ship_lane = screen::SKY_TO_GROUND - screen::SHIP_SIZE.1;
+        for &(x, y) in self.shields.iter() {
+            if x < 0 || y < 0 || x + w > screen::GAME_SIZE.0 || y + h > ship_lane {
+                return Err(format!(
+                    "A {}x{} shield at ({}, {}) must be on screen and above the ship lane at y={}.",
+                    w, h, x, y, ship_lane
+                ));
+            }
+        }
+        match self.shield_erosion() {
+            Erosion::Block { width, height } if width < 1 || height < 1 => Err(format!(
+                "Erosion blocks must be at least 1x1; got {}x{}.",
+                width, height
+            )),
+            Erosion::Radius { radius } if radius < 0 => Err(format!(
+                "Erosion radius must not be negative; got {}.",
+                radius
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// A shield at the origin, in the configured shape or the Atari's.
+    pub fn shield_sprite(&self) -> Result<SpriteData, String> {
+        let sprite = match &self.shield_shape {
+            None => SHIELD_SPRITE.clone(),
+            Some(ShieldShape::Ascii(rows)) => {
+                load_sprite_dynamic(&rows.join("\n"), (&screen::SHIELD_COLOR).into())?
+            }
+            Some(ShieldShape::Png(path)) => {
+                let bytes = std::fs::read(path)
+                    .map_err(|e| format!("Could not read shield png {:?}: {}", path, e))?;
+                let fixed = FixedSpriteData::try_load_png(&bytes)
+                    .map_err(|e| format!("Could not load shield png {:?}: {}", path, e))?;
+                SpriteData::new(fixed.data.as_ref().clone())
+            }
+        };
+        if sprite
+            .data
+            .first()
+            .map(|row| row.is_empty())
+            .unwrap_or(true)
+        {
+            return Err("A shield needs at least one pixel.".to_string());
+        }
+        Ok(sprite)
+    }
+
+    /// The prepared shield, or a freshly decoded one if the shape changed since.
+    fn shield(&self) -> Result<Cow<'_, SpriteData>, String> {
+        match &self.shield {
+            Some(decoded) if decoded.shape == self.shield_shape => {
+                Ok(Cow::Borrowed(&decoded.sprite))
+            }
+            _ => self.shield_sprite().map(Cow::Owned),
+        }
+    }
+
+    /// How lasers wear down shields.
+    pub fn shield_erosion(&self) -> Erosion {
+        self.shield_erosion.clone().unwrap_or(Erosion::Block {
+            width: 2,
+            height: 2,
+        })
+    }
+
+    /// How the invaders speed up as they die: (enemies killed, frames between steps).
+    pub fn speedups(&self) -> Vec<(i32, i32)> {
+        self.speedups
+            .clone()
+            .unwrap_or_else(|| screen::ENEMY_SPEEDUPS.to_vec())
+    }
+
+    /// The player's weapon, or the Atari's single laser.
+    pub fn weapon(&self) -> Weapon {
+        self.weapon.clone().unwrap_or(Weapon {
+            max_shots: 1,
+            speed: screen::LASER_SPEED,
+            size: (screen::LASER_SIZE_W, screen::LASER_SIZE_H1),
+            cooldown: 0,
+            spread: None,
+        })
+    }
+
+    /// The wave for a (1-based) level; the last wave repeats.
+    /// Without configured waves, every level starts the formation two pixels lower than the last.
+    pub fn wave(&self, level: i32) -> Wave {
+        match &self.waves {
+            Some(waves) if !waves.is_empty() => {
+                let index = ((level - 1).max(0) as usize).min(waves.len() - 1);
+                waves[index].clone()
+            }
+            _ => Wave {
+                formation: None,
+                start_y: screen::ENEMY_START_POS.1 + level * 2,
+                march_period: screen::ENEMY_PERIOD,
+                march_delta: (
+                    screen::ENEMY_DELTA.0,
+                    self.descent_step.unwrap_or(screen::ENEMY_DELTA.1),
+                ),
+            },
+        }
+    }
+
+    /// How many frames the invaders wait between steps once ``num_killed`` of them have died.
+    fn march_wait(&self, wave: &Wave, num_killed: i32) -> i32 {
+        let mut wait = 0;
+        for (num_dead, speedup) in self.speedups() {
+            if num_killed >= num_dead {
+                wait = speedup.min(wave.march_period);
+            }
+        }
+        // If we haven't killed enough enemies to accelerate, reset to the original period.
+        if wait == 0 {
+            wait = wave.march_period;
+        }
+        wait
+    }
+
+    /// The rows of a wave's formation: its own, the game's, or the classic six by six block.
+    fn wave_formation<'a>(&'a self, wave: &'a Wave) -> &'a [String] {
+        wave.formation
+            .as_ref()
+            .or(self.formation.as_ref())
+            .map(|rows| rows.as_slice())
+            .unwrap_or(&DEFAULT_FORMATION)
+    }
+}
+
+/// One step of a formation within ``bounds`` that is heading ``dir``: the (dx, dy) to move it, and where it heads next.
+fn march_step(
+    dir: Direction,
+    bounds: &Rect,
+    (deltax, deltay): (i32, i32),
+) -> (i32, i32, Direction) {
+    let at_left = bounds.x1() <= screen::ENEMY_START_POS.0;
+    let at_right = bounds.x2() >= screen::ENEMY_END_POS.0;
+    match dir {
+        Direction::Left if at_left => (0, 0, Direction::Down),
+        Direction::Left => (-deltax, 0, Direction::Left),
+        Direction::Right if at_right => (0, 0, Direction::Down),
+        Direction::Right => (deltax, 0, Direction::Right),
+        Direction::Down if at_left => (0, deltay, Direction::Right),
+        Direction::Down => (0, deltay, Direction::Left),
+        Direction::Up => unreachable!(),
+    }
+}
+
+lazy_static! {
+    static ref DEFAULT_FORMATION: Vec<String> = (0..screen::ENEMIES_NUM)
+        .map(|row| {
+            let kind = std::char::from_digit((row + 1) as u32, 10).unwrap();
+            kind.to_string().repeat(screen::ENEMIES_PER_ROW as usize)
+        })
+        .collect();
+}
+
+/// Read the (row, column, kind) of every invader in a formation.
+fn parse_formation(rows: &[String]) -> Result<Vec<(i32, i32, i32)>, String> {
+    let mut enemies = Vec::new();
+    for (row, line) in rows.iter().enumerate() {
+        for (col, ch) in line.chars().enumerate() {
+            match ch {
+                '1'..='6' => enemies.push((row as i32, col as i32, ch as i32 - '1' as i32)),
+                ' ' | '.' => {}
+                _ => {
+                    return Err(format!(
+                        "Unexpected {:?} in formation row {}, column {}; use 1-6 for invaders and ' ' or '.' for gaps.",
+                        ch, row, col
+                    ))
+                }
+            }
+        }
+    }
+    if enemies.is_empty() {
+        return Err("A formation needs at least one invader.".to_string());
+    }
+    Ok(enemies)
+}
+
 impl Default for EnemiesMovementState {
     fn default() -> Self {
         EnemiesMovementState {
@@ -329,6 +659,8 @@ impl Player {
             alive: false,
             death_counter: None,
             death_hit_1: true,
+            sub_x: None,
+            last_move: None,
         }
     }
     fn _rect(&self) -> Rect {
@@ -348,7 +680,19 @@ impl Laser {
             t: 0,
             color: (&screen::LASER_COLOR).into(),
             movement: dir,
-            speed: 3,
+            speed: screen::LASER_SPEED,
+            dx: None,
+        }
+    }
+    /// A player laser fired from (x, y) by this weapon, drifting sideways by dx each frame.
+    fn from_weapon(x: i32, y: i32, weapon: &Weapon, dx: i32) -> Laser {
+        let (w, h) = weapon.size;
+        Laser {
+            w,
+            h,
+            speed: weapon.speed,
+            dx: if dx == 0 { None } else { Some(dx) },
+            ..Laser::new(x, y, Direction::Up)
         }
     }
     /// Every other frame a laser is visible.
@@ -390,7 +734,7 @@ impl Ufo {
     }
 }
 impl Enemy {
-    fn new(x: i32, y: i32, row: i32, col: i32, id: u32) -> Enemy {
+    fn new(x: i32, y: i32, row: i32, col: i32, id: u32, kind: i32, points: i32) -> Enemy {
         Enemy {
             x,
             y,
@@ -398,11 +742,13 @@ impl Enemy {
             col,
             id,
             alive: true,
-            points: screen::ENEMY_POINTS[row as usize],
+            points,
             death_counter: None,
+            shot_by: None,
+            kind: Some(kind),
         }
     }
-    fn rect(&self) -> Rect {
+    pub fn rect(&self) -> Rect {
         Rect::new(self.x, self.y, screen::ENEMY_SIZE.0, screen::ENEMY_SIZE.1)
     }
 }
@@ -419,14 +765,31 @@ impl StateCore {
             level: 1,
             score: 0,
             ship: Player::new(player_start_x, player_start_y),
+            ship_lasers: Some(Vec::new()),
             ship_laser: None,
-            enemy_shot_delay: 50,
+            ship_cooldown: Some(0),
+            enemy_shot_delay: config.enemy_protocol.first_shot_delay(),
+            shots_fired: Some(0),
+            last_shooter: None,
             shields: Vec::new(),
             enemies: Vec::new(),
             enemies_movement: Default::default(),
             enemy_lasers: Vec::new(),
             ufo: Ufo::new(),
+            second_player: None,
+            current_player: None,
         };
+        if config.two_player.is_some() {
+            state.current_player = Some(0);
+            state.second_player = Some(PlayerSlot {
+                ship: Player::new(player_start_x, player_start_y),
+                ship_lasers: Vec::new(),
+                ship_cooldown: 0,
+                score: 0,
+                lives: state.lives,
+                life_display_timer: screen::NEW_LIFE_TIME,
+            });
+        }
 
         state.reset_board(config);
         state
@@ -435,30 +798,117 @@ impl StateCore {
     fn reset_board(&mut self, config: &SpaceInvaders) {
         self.shields.clear();
         self.enemies.clear();
-        self.life_display_timer = screen::NEW_LIFE_TIME;
-        self.ship.x = screen::SHIP_LIMIT_X1;
+        self.reset_ship();
+        if self.second_player.is_some() {
+            self.swap_players();
+            self.reset_ship();
+            self.swap_players();
+        }
         self.enemy_lasers.clear();
-        self.ship_laser = None;
         self.ufo = Ufo::new();
 
+        let shield = config
+            .shield()
+            .expect("Shield shape should have been validated.");
         for &(x, y) in config.shields.iter() {
-            self.shields.push(SHIELD_SPRITE.translate(x, y))
+            self.shields.push(shield.translate(x, y))
         }
 
-        let (x, mut y) = screen::ENEMY_START_POS;
-        // start position should get lower with each level
-        y += self.level * 2;
+        let wave = config.wave(self.level);
+        self.enemies_movement = EnemiesMovementState {
+            move_counter: wave.march_period,
+            ..Default::default()
+        };
+        let x = screen::ENEMY_START_POS.0;
+        let y = wave.start_y;
         let (w, h) = screen::ENEMY_SIZE;
         let x_offset = w + screen::ENEMY_SPACE.0;
         let y_offset = h + screen::ENEMY_SPACE.1;
-        for j in 0..screen::ENEMIES_NUM {
-            for i in 0..screen::ENEMIES_PER_ROW {
-                let x = x + (i * x_offset);
-                let y = y + (j * y_offset);
-                let id = self.enemies.len() as u32;
-                self.enemies.push(Enemy::new(x, y, j, i, id));
+        let formation = parse_formation(config.wave_formation(&wave))
+            .expect("Formation should have been validated.");
+        for (j, i, kind) in formation {
+            let x = x + (i * x_offset);
+            let y = y + (j * y_offset);
+            let id = self.enemies.len() as u32;
+            let points = config
+                .row_scores
+                .get(kind as usize)
+                .cloned()
+                .unwrap_or(screen::ENEMY_POINTS[kind as usize]);
+            self.enemies.push(Enemy::new(x, y, j, i, id, kind, points));
+        }
+    }
+
+    /// Put the current player's ship back at its start, with no lasers in flight.
+    fn reset_ship(&mut self) {
+        self.life_display_timer = screen::NEW_LIFE_TIME;
+        self.ship.x = self.ship_start_x();
+        self.ship_lasers = Some(Vec::new());
+        self.ship_cooldown = Some(0);
+    }
+
+    /// Player one starts at the left; player two starts at the right.
+    fn ship_start_x(&self) -> i32 {
+        if self.current_player == Some(1) {
+            screen::SHIP_LIMIT_X2
+        } else {
+            screen::SHIP_LIMIT_X1
+        }
+    }
+
+    /// Exchange the current player's ship, lasers, score and lives with the other player's.
+    fn swap_players(&mut self) {
+        if let Some(mut other) = self.second_player.take() {
+            std::mem::swap(&mut self.ship, &mut other.ship);
+            let lasers = self.ship_lasers.take().unwrap_or_default();
+            self.ship_lasers = Some(std::mem::replace(&mut other.ship_lasers, lasers));
+            let cooldown = self.ship_cooldown.unwrap_or(0);
+            self.ship_cooldown = Some(std::mem::replace(&mut other.ship_cooldown, cooldown));
+            std::mem::swap(&mut self.score, &mut other.score);
+            std::mem::swap(&mut self.lives, &mut other.lives);
+            std::mem::swap(&mut self.life_display_timer, &mut other.life_display_timer);
+            self.second_player = Some(other);
+            self.current_player = Some(1 - self.current_player.unwrap_or(0));
+        }
+    }
+
+    /// In alternating games, hand the turn to the other player if they have lives left.
+    fn pass_turn(&mut self) {
+        let other_lives = self.second_player.as_ref().map(|p| p.lives).unwrap_or(-1);
+        if other_lives >= 0 {
+            self.swap_players();
+        }
+    }
+
+    /// The (score, lives) of each player, in player order.
+    pub fn player_stats(&self) -> Vec<(i32, i32)> {
+        let mut stats = vec![(self.score, self.lives)];
+        if let Some(other) = &self.second_player {
+            stats.push((other.score, other.lives));
+            if self.current_player == Some(1) {
+                stats.reverse();
             }
         }
+        stats
+    }
+
+    /// The most lives any player has left; the game is over when this is negative.
+    pub fn team_lives(&self) -> i32 {
+        self.player_stats()
+            .iter()
+            .map(|&(_, lives)| lives)
+            .max()
+            .unwrap_or(self.lives)
+    }
+
+    /// Is any player's ship on screen?
+    fn any_ship_alive(&self) -> bool {
+        self.ship.alive
+            || self
+                .second_player
+                .as_ref()
+                .map(|p| p.ship.alive)
+                .unwrap_or(false)
     }
 
     /// Flash the player ship and display lives
@@ -503,47 +953,112 @@ impl StateCore {
                 self.ship.death_counter = None;
                 self.lives -= 1;
                 // self.ship.alive = true;
-                self.ship.x = screen::SHIP_LIMIT_X1;
+                self.ship.x = self.ship_start_x();
             }
         }
     }
 
-    /// Find the enemy, if any, hit by the ship_laser, and start its death timer.
-    fn laser_enemy_collisions(&mut self) {
-        let mut hit = None;
-        if let Some(laser) = &mut self.ship_laser {
-            let laser_rect = laser.rect();
+    /// The player's lasers in flight.
+    pub fn ship_lasers(&self) -> &[Laser] {
+        self.ship_lasers.as_deref().unwrap_or(&[])
+    }
 
-            let enemy_orient = self.enemies_movement.visual_orientation;
+    fn ship_lasers_mut(&mut self) -> &mut Vec<Laser> {
+        self.ship_lasers.get_or_insert_with(Vec::new)
+    }
 
-            // Check collision with living enemies:
-            for e in self
-                .enemies
-                .iter()
-                .filter(|e| e.alive && e.death_counter.is_none())
-            {
-                let enemy_rect = e.rect();
-
-                // Broad-phase collision: is it in the rectangle?
-                if laser_rect.intersects(&enemy_rect) {
-                    let sprite = get_invader_sprite(&e, enemy_orient);
-                    // pixel-perfect detection:
-                    if laser_rect.collides_visible(e.x, e.y, &sprite.data) {
-                        hit = Some(e.id);
-                        break;
-                    }
+    /// Fire the player's weapon, if there is room on screen and it has cooled down.
+    fn fire_ship_lasers(&mut self, config: &SpaceInvaders, fire: bool) {
+        let cooldown = self.ship_cooldown.unwrap_or(0);
+        if cooldown > 0 {
+            self.ship_cooldown = Some(cooldown - 1);
+            return;
+        }
+        if !fire {
+            return;
+        }
+        let weapon = config.weapon();
+        let drifts: Vec<i32> = match &weapon.spread {
+            Some(spread) => {
+                let n = spread.shots as i32;
+                // Shots are spaced evenly around straight up; an even spread skips the middle.
+                (0..n)
+                    .map(|i| (2 * i - (n - 1)) * spread.drift / 2)
+                    .collect()
+            }
+            None => vec![0],
+        };
+        if self.ship_lasers().len() + drifts.len() > weapon.max_shots as usize {
+            return;
+        }
+        let x = self.ship.x + self.ship.w / 2;
+        let y = self.ship.y;
+        for dx in drifts {
+            self.ship_lasers_mut()
+                .push(Laser::from_weapon(x, y, &weapon, dx));
+        }
+        self.ship_cooldown = Some(weapon.cooldown);
+    }
+
+    /// Move the player's lasers a pixel at a time, so none of them skip past a shield or an enemy.
+    fn ship_laser_movement(&mut self, config: &SpaceInvaders) {
+        let erosion = config.shield_erosion();
+        // Broad-phase collision: index the enemies by their rectangles once per frame.
+        let mut grid = SpatialGrid::new(screen::ENEMY_SIZE.0.max(screen::ENEMY_SIZE.1));
+        for e in self
+            .enemies
+            .iter()
+            .filter(|e| e.alive && e.death_counter.is_none())
+        {
+            grid.insert(e.rect(), e.id);
+        }
+        let lasers = self.ship_lasers.take().unwrap_or_default();
+        let mut flying = Vec::with_capacity(lasers.len());
+        'lasers: for mut laser in lasers {
+            laser.x += laser.dx.unwrap_or(0);
+            for _ in 0..laser.speed {
+                laser.y -= 1;
+                let rect = laser.rect();
+                if self.laser_shield_check(&rect, &erosion)
+                    || self.laser_enemy_collisions(&rect, &grid)
+                {
+                    continue 'lasers;
                 }
             }
+            flying.push(laser);
+        }
+        self.ship_lasers = Some(flying);
+    }
+
+    /// Find the enemy, if any, hit by a player laser, and start its death timer; true if there was a hit.
+    /// The grid holds enemy ids from the start of the frame, so enemies shot since then are skipped here.
+    fn laser_enemy_collisions(&mut self, laser_rect: &Rect, grid: &SpatialGrid<u32>) -> bool {
+        let mut hit = None;
+        let enemy_orient = self.enemies_movement.visual_orientation;
+
+        // Check collision with the living enemies the laser might touch:
+        for &id in grid.query(laser_rect) {
+            let e = &self.enemies[id as usize];
+            if !e.alive || e.death_counter.is_some() {
+                continue;
+            }
+            let sprite = get_invader_sprite(e, enemy_orient);
+            // pixel-perfect detection:
+            if laser_rect.collides_visible(e.x, e.y, &sprite.data) {
+                hit = Some(e.id);
+                break;
+            }
         }
 
         // Start enemy death animations.
         if let Some(eid) = hit {
             let enemy = &mut self.enemies[eid as usize];
-            self.ship_laser = None;
             if enemy.death_counter.is_none() {
-                enemy.death_counter = Some(screen::DEATH_TIME)
+                enemy.death_counter = Some(screen::DEATH_TIME);
+                enemy.shot_by = Some(self.current_player.unwrap_or(0));
             }
         }
+        hit.is_some()
     }
 
     // If the player's laser has hit the UFO, start its death timer.
@@ -558,23 +1073,26 @@ impl StateCore {
         }
 
         let ufo = &mut self.ufo;
-        if let Some(ref mut laser) = self.ship_laser {
+        let ufo_rect = ufo.rect();
+        let mut hit = None;
+        for (index, laser) in self.ship_lasers.iter().flatten().enumerate() {
             let laser_rect = laser.rect();
-            let ufo_rect = ufo.rect();
             if laser_rect.intersects(&ufo_rect) {
                 if let Some(sprite) = get_ufo_sprite(&ufo) {
                     if laser_rect.collides_visible(ufo.x, ufo.y, &sprite.data) {
-                        ufo.start_death_counter();
-                        self.score += screen::UFO_BONUS;
+                        hit = Some(index);
+                        break;
                     }
                 } else {
                     unreachable!("We should have exited earlier if the death counter is 0 and sprite is None.");
                 }
             }
         }
-        //
-        if ufo.death_counter.is_some() {
-            self.ship_laser = None;
+        // Only one laser gets the bonus; it is used up.
+        if let Some(index) = hit {
+            ufo.start_death_counter();
+            self.score += screen::UFO_BONUS;
+            self.ship_lasers_mut().remove(index);
         }
     }
 
@@ -607,7 +1125,12 @@ impl StateCore {
                 if dc == 0 {
                     enemy.death_counter = None;
                     enemy.alive = false;
-                    self.score += enemy.points;
+                    match self.second_player.as_mut() {
+                        Some(other) if enemy.shot_by != self.current_player => {
+                            other.score += enemy.points
+                        }
+                        _ => self.score += enemy.points,
+                    }
                 } else {
                     enemy.death_counter = Some(dc);
                 }
@@ -615,16 +1138,10 @@ impl StateCore {
         }
     }
 
-    /// Deletes the laser if it has gone off the top of the screen.
+    /// Deletes lasers that have gone off the screen.
     fn laser_miss_check(&mut self) {
-        if self
-            .ship_laser
-            .as_ref()
-            .map(|laser| laser.y < 0)
-            .unwrap_or(false)
-        {
-            self.ship_laser = None;
-        }
+        self.ship_lasers_mut()
+            .retain(|laser| laser.y >= 0 && laser.x + laser.w > 0 && laser.x < screen::GAME_SIZE.0);
 
         // Collect lasers that will have gone off-screen:
         let mut delete = Vec::new();
@@ -639,14 +1156,20 @@ impl StateCore {
         }
     }
 
-    fn laser_shield_check(&mut self, laser: &Rect) -> bool {
+    fn laser_shield_check(&mut self, laser: &Rect, erosion: &Erosion) -> bool {
         // Check collision with living shields:
         for shield in self.shields.iter_mut() {
             let shield_rect = Rect::new(shield.x, shield.y, shield.width(), shield.height());
 
             // Broad-phase collision: is it in the rectangle?
             if laser.intersects(&shield_rect) {
-                if destruction::destructive_collide(&laser, shield.x, shield.y, &mut shield.data) {
+                if destruction::destructive_collide(
+                    laser,
+                    shield.x,
+                    shield.y,
+                    &mut shield.data,
+                    erosion,
+                ) {
                     return true;
                 }
             }
@@ -655,7 +1178,7 @@ impl StateCore {
     }
 
     /// Move enemies as a group!
-    fn enemy_shift(&mut self) {
+    fn enemy_shift(&mut self, config: &SpaceInvaders) {
         // Enemies do not move every frame; there is a delay.
         if self.enemies_movement.move_counter > 0 {
             self.enemies_movement.move_counter -= 1;
@@ -678,15 +1201,7 @@ impl StateCore {
         let num_killed = (self.enemies.len() - rectangle.len()) as i32;
 
         // Calculate which direction we are moving, and what next to do.
-        let (deltax, deltay) = screen::ENEMY_DELTA;
-        let startx = screen::ENEMY_START_POS.0;
-        let end = screen::ENEMY_END_POS.0;
-
-        let x = rect_union.x1();
-
-        // we're going to fill in the change to apply to all enemies.
-        let mut dx = 0;
-        let mut dy = 0;
+        let wave = config.wave(self.level);
 
         // Grab a reference to the state variables we need.
         let mut movement = &mut self.enemies_movement;
@@ -694,50 +1209,12 @@ impl StateCore {
         // They're moving, so flip the sprites.
         movement.visual_orientation = !movement.visual_orientation;
 
-        let at_left = x <= startx;
-        let at_right = rect_union.x2() >= end;
-
-        let new_dir = match movement.move_dir {
-            Direction::Left => {
-                if at_left {
-                    Direction::Down
-                } else {
-                    dx = -deltax;
-                    Direction::Left
-                }
-            }
-            Direction::Right => {
-                if at_right {
-                    Direction::Down
-                } else {
-                    dx = deltax;
-                    Direction::Right
-                }
-            }
-            Direction::Down => {
-                dy = deltay;
-                if at_left {
-                    Direction::Right
-                } else {
-                    Direction::Left
-                }
-            }
-            Direction::Up => unreachable!(),
-        };
-
         // Change direction if necessary.
+        let (dx, dy, new_dir) = march_step(movement.move_dir, &rect_union, wave.march_delta);
         movement.move_dir = new_dir;
 
         // Accelerate appropriately.
-        for (num_dead, speedup) in screen::ENEMY_SPEEDUPS {
-            if num_killed >= *num_dead {
-                movement.move_counter = *speedup;
-            }
-        }
-        // If we haven't killed enough enemies to accelerate, reset to the original period.
-        if movement.move_counter == 0 {
-            movement.move_counter = screen::ENEMY_PERIOD;
-        }
+        movement.move_counter = config.march_wait(&wave, num_killed);
 
         // Now actually move enemies!
         for e in self.enemies.iter_mut() {
@@ -766,13 +1243,16 @@ impl StateCore {
 
     fn enemy_fire_lasers(&mut self, config: &SpaceInvaders) {
         // Don't fire too many lasers.
-        if self.enemy_lasers.len() > 1 {
+        if self.enemy_lasers.len() >= config.enemy_protocol.max_lasers() {
             return;
         }
         self.enemy_shot_delay -= 1;
         if self.enemy_shot_delay <= 0 {
-            self.enemy_shot_delay = 50;
             let shooter_index = enemy_fire_lasers(self, config);
+            let shots_fired = self.shots_fired.unwrap_or(0) + 1;
+            self.shots_fired = Some(shots_fired);
+            self.last_shooter = Some(shooter_index);
+            self.enemy_shot_delay = config.enemy_protocol.next_shot_delay(shots_fired);
             let shooter = &self.enemies[shooter_index as usize];
             let start = shooter.rect();
             let shot = Laser::new(start.center_x(), start.center_y(), Direction::Down);
@@ -803,14 +1283,15 @@ impl StateCore {
     }
 
     /// Move all lasers watching for collision with shields!
-    fn enemy_laser_movement(&mut self) {
+    fn enemy_laser_movement(&mut self, config: &SpaceInvaders) {
+        let erosion = config.shield_erosion();
         let mut delete_lasers = Vec::new();
         for laser_idx in 0..self.enemy_lasers.len() {
             let laser_speed = self.enemy_lasers[laser_idx].speed;
             for _ in 0..laser_speed {
                 self.enemy_lasers[laser_idx].y += 1;
                 let laser_rectangle = self.enemy_lasers[laser_idx].rect();
-                if self.laser_shield_check(&laser_rectangle) {
+                if self.laser_shield_check(&laser_rectangle, &erosion) {
                     delete_lasers.push(laser_idx);
                     break;
                 }
@@ -824,6 +1305,10 @@ impl StateCore {
     /// If enemies have moved far enough down to overlap with shields,
     /// then shields are removed.
     fn remove_shields(&mut self) {
+        let top = match self.shields.iter().map(|shield| shield.y).min() {
+            Some(top) => top,
+            None => return,
+        };
         for id in self.active_weapon_enemy_ids() {
             let enemy = &self.enemies[id as usize];
             // We only care about the lowest enemies. However, since
@@ -834,11 +1319,107 @@ impl StateCore {
             // or at actual pixel overlap (put another way: do partially
             // destructed shields lead to a delay in their final disappearance?)
             // Going with the simpler version for now.
-            if lower_bound > screen::SHIELD1_POS.1 {
+            if lower_bound > top {
                 self.shields = Vec::new();
             }
         }
     }
+    /// Where every living enemy is, and what it is worth.
+    fn enemy_summaries(&self) -> Vec<serde_json::Value> {
+        self.enemies
+            .iter()
+            .filter(|e| e.alive)
+            .map(|e| {
+                serde_json::json!({
+                    "id": e.id,
+                    "row": e.row,
+                    "col": e.col,
+                    "x": e.x,
+                    "y": e.y,
+                    "points": e.points,
+                    "dying": e.death_counter.is_some(),
+                })
+            })
+            .collect()
+    }
+
+    /// Where every laser in a list is and how many pixels per frame it moves; positive vy is down.
+    fn laser_summaries(lasers: &[Laser]) -> Vec<serde_json::Value> {
+        lasers
+            .iter()
+            .map(|laser| {
+                let vy = match laser.movement {
+                    Direction::Up => -laser.speed,
+                    _ => laser.speed,
+                };
+                serde_json::json!({
+                    "x": laser.x,
+                    "y": laser.y,
+                    "w": laser.w,
+                    "h": laser.h,
+                    "vx": laser.dx.unwrap_or(0),
+                    "vy": vy,
+                })
+            })
+            .collect()
+    }
+
+    /// For each enemy laser, where and in how many frames it will reach the top of the ship, ignoring shields.
+    fn laser_impacts(&self) -> Vec<serde_json::Value> {
+        self.enemy_lasers
+            .iter()
+            .map(|laser| {
+                let distance = (self.ship.y - (laser.y + laser.h)).max(0);
+                // Round up: a laser that is one pixel away still needs a frame to arrive.
+                let frames = (distance + laser.speed - 1) / laser.speed.max(1);
+                serde_json::json!({
+                    "x": laser.x + laser.dx.unwrap_or(0) * frames,
+                    "frames": frames,
+                })
+            })
+            .collect()
+    }
+
+    /// The rectangle around every living enemy, if any are left.
+    pub fn formation_bounds(&self) -> Option<Rect> {
+        let rects: Vec<Rect> = self
+            .enemies
+            .iter()
+            .filter(|e| e.alive)
+            .map(|e| e.rect())
+            .collect();
+        Rect::merge(&rects)
+    }
+
+    /// How many frames until the invaders reach the ground if no more of them die, or None if they never will.
+    pub fn time_to_ground(&self, config: &SpaceInvaders) -> Option<i32> {
+        let mut bounds = self.formation_bounds()?;
+        let wave = config.wave(self.level);
+        let (deltax, deltay) = wave.march_delta;
+        // Without stepping sideways they never reach an edge to step down from.
+        if deltax < 1 || deltay < 1 {
+            return None;
+        }
+        if bounds.y2() >= screen::SKY_TO_GROUND {
+            return Some(0);
+        }
+        // With no more kills, every step after the next waits just as long, so go step by step rather than frame by frame.
+        let living = self.enemies.iter().filter(|e| e.alive).count();
+        let wait = config.march_wait(&wave, (self.enemies.len() - living) as i32);
+        let mut dir = self.enemies_movement.move_dir;
+        let mut frames = self.enemies_movement.move_counter.max(0) + 1;
+        loop {
+            let (dx, dy, next) = march_step(dir, &bounds, wave.march_delta);
+            bounds.x += dx;
+            bounds.y += dy;
+            dir = next;
+            if bounds.y2() >= screen::SKY_TO_GROUND {
+                return Some(frames);
+            }
+            frames += wait + 1;
+        }
+    }
+
     fn has_lost(&self) -> bool {
         self.enemies
             .iter()
@@ -848,7 +1429,7 @@ impl StateCore {
         self.enemies.iter().all(|e| !e.alive)
     }
     fn reset_condition(&self) -> bool {
-        let game_over = self.lives < 0;
+        let game_over = self.team_lives() < 0;
         let won = self.has_won();
         let lost = self.has_lost();
         game_over || won || lost
@@ -863,10 +1444,7 @@ impl toybox_core::Simulation for SpaceInvaders {
         screen::GAME_SIZE
     }
     fn new_game(&mut self) -> Box<dyn toybox_core::State + Send> {
-        Box::new(State {
-            config: self.clone(),
-            state: StateCore::new(self),
-        })
+        Box::new(State::try_new(self).expect("new_game should succeed."))
     }
     /// Sync with [ALE impl](https://github.com/mgbellemare/Arcade-Learning-Environment/blob/master/src/games/supported/SpaceInvaders.cpp#L85)
     /// Note, leaving a call to sort in this impl to remind users that these vecs are ordered!
@@ -882,15 +1460,30 @@ impl toybox_core::Simulation for SpaceInvaders {
         actions.sort();
         actions
     }
+    fn continuous_action_space(&self) -> Vec<ContinuousAxis> {
+        vec![ContinuousAxis::full(
+            "horizontal",
+            "Ship movement as a fraction of its speed; negative is left.",
+        )]
+    }
+    fn player_count(&self) -> usize {
+        if self.two_player.is_some() {
+            2
+        } else {
+            1
+        }
+    }
     fn new_state_from_json(
         &self,
         json_str: &str,
     ) -> Result<Box<dyn toybox_core::State + Send>, serde_json::Error> {
-        let state: StateCore = serde_json::from_str(json_str)?;
-        Ok(Box::new(State {
-            state,
-            config: self.clone(),
-        }))
+        let mut state: StateCore = serde_json::from_str(json_str)?;
+        if let Some(laser) = state.ship_laser.take() {
+            state.ship_lasers_mut().push(laser);
+        }
+        let mut config = self.clone();
+        config.prepare().map_err(serde::de::Error::custom)?;
+        Ok(Box::new(State { state, config }))
     }
 
     fn to_json(&self) -> String {
@@ -901,7 +1494,8 @@ impl toybox_core::Simulation for SpaceInvaders {
         &self,
         json_str: &str,
     ) -> Result<Box<dyn toybox_core::Simulation + Send>, serde_json::Error> {
-        let config: SpaceInvaders = serde_json::from_str(json_str)?;
+        let mut config: SpaceInvaders = serde_json::from_str(json_str)?;
+        config.prepare().map_err(serde::de::Error::custom)?;
         Ok(Box::new(config))
     }
     fn schema_for_config(&self) -> String {
@@ -914,25 +1508,37 @@ impl toybox_core::Simulation for SpaceInvaders {
     }
 }
 
-impl toybox_core::State for State
-where
-    State: Clone,
-{
-    fn lives(&self) -> i32 {
-        self.state.lives
-    }
-    fn level(&self) -> i32 {
-        self.state.level
-    }
-    fn score(&self) -> i32 {
-        self.state.score
+impl State {
+    /// Start a new game, once ``config`` is prepared.
+    pub fn try_new(config: &mut SpaceInvaders) -> Result<State, String> {
+        config.prepare()?;
+        Ok(State {
+            config: config.clone(),
+            state: StateCore::new(config),
+        })
     }
-    fn update_mut(&mut self, buttons: Input) {
+
+    /// Advance one frame; ``inputs[i]`` controls player i, and the stick only moves player one.
+    fn update_players(&mut self, inputs: &[Input], analog: AnalogInput) {
         if self.state.reset_condition() {
-            // If enemies hit the earth, you have lost. Game is over.
+            // If enemies hit the earth, you have lost. Game is over, unless the config is forgiving.
             if self.state.has_lost() {
-                self.state.lives = -1;
-                return;
+                match self.config.ground_rule.unwrap_or(GroundRule::EndGame) {
+                    GroundRule::EndGame => {
+                        self.state.lives = -1;
+                        if let Some(other) = self.state.second_player.as_mut() {
+                            other.lives = -1;
+                        }
+                        return;
+                    }
+                    GroundRule::LoseLife => {
+                        self.state.lives -= 1;
+                        self.state.ship.alive = false;
+                        if self.config.two_player == Some(TwoPlayerMode::Alternating) {
+                            self.state.pass_turn();
+                        }
+                    }
+                }
             } else if self.state.has_won() {
                 self.state.level += 1;
             }
@@ -940,71 +1546,136 @@ where
             self.state.reset_board(&self.config);
             return;
         }
-        if self.state.ship.alive {
-            // The ship can only move if it is alive.
-            if buttons.left {
-                self.state.ship.x -= self.state.ship.speed;
-            } else if buttons.right {
-                self.state.ship.x += self.state.ship.speed;
+        let input = |player: u32| inputs.get(player as usize).cloned().unwrap_or_default();
+        match self.config.two_player {
+            None => self.update_frame(input(0), analog),
+            Some(TwoPlayerMode::Alternating) => {
+                let player = self.state.current_player.unwrap_or(0);
+                let analog = if player == 0 {
+                    analog
+                } else {
+                    AnalogInput::default()
+                };
+                let lives = self.state.lives;
+                self.update_frame(input(player), analog);
+                if self.state.lives < lives {
+                    self.state.pass_turn();
+                }
             }
-
-            if self.state.ship.x > screen::SHIP_LIMIT_X2 {
-                self.state.ship.x = screen::SHIP_LIMIT_X2;
-            } else if self.state.ship.x < screen::SHIP_LIMIT_X1 {
-                self.state.ship.x = screen::SHIP_LIMIT_X1;
+            Some(TwoPlayerMode::Cooperative) => {
+                self.update_frame(input(0), analog);
+                self.state.swap_players();
+                self.update_other_ship(input(1));
+                self.state.swap_players();
             }
+        }
+    }
 
-            // Only shoot a laser if not present and if we aren't in the throes of death:
-            if self.state.ship_laser.is_none() && buttons.button1 {
-                self.state.ship_laser = Some(Laser::new(
-                    self.state.ship.x + self.state.ship.w / 2,
-                    self.state.ship.y,
-                    Direction::Up,
-                ));
+    /// Move the current player's ship and fire its weapon.
+    fn move_ship(&mut self, buttons: Input, analog: AnalogInput) {
+        let start_x = self.state.ship.x;
+        if buttons.left {
+            self.state.ship.x -= self.state.ship.speed;
+        } else if buttons.right {
+            self.state.ship.x += self.state.ship.speed;
+        } else if analog.horizontal != 0.0 {
+            // Move whole pixels, and keep the fraction for later frames.
+            let ship = &mut self.state.ship;
+            let wanted = ship.sub_x.unwrap_or(0)
+                + Fixed::from_f64(analog.horizontal * f64::from(ship.speed)).raw();
+            let one = Fixed::ONE.raw();
+            ship.x += wanted / one;
+            ship.sub_x = Some(wanted % one);
+        }
+
+        let ship = &mut self.state.ship;
+        ship.x = ship.x.clamp(screen::SHIP_LIMIT_X1, screen::SHIP_LIMIT_X2);
+        ship.last_move = Some(ship.x - start_x);
+
+        // Only shoot if the weapon has room and if we aren't in the throes of death:
+        self.state.fire_ship_lasers(&self.config, buttons.button1);
+    }
+
+    /// One frame for the current player's ship and for the rest of the world.
+    fn update_frame(&mut self, buttons: Input, analog: AnalogInput) {
+        let anyone_alive = self.state.any_ship_alive();
+        if self.state.ship.alive {
+            // The ship can only move if it is alive.
+            self.move_ship(buttons, analog);
+        } else if self.state.ship.death_counter.is_none() {
+            // Players who are out of lives stay off screen while their partner plays on.
+            if self.state.lives >= 0 {
+                self.state.flash_display_lives();
+            }
+            if !anyone_alive {
+                self.state.ufo.reset_mothership();
             }
-            // Enemies only move if the player is alive
-            self.state.enemy_shift();
-            // Enemies only fire if the player is alive
+        }
+        if anyone_alive {
+            // Enemies only move if a player is alive
+            self.state.enemy_shift(&self.config);
+            // Enemies only fire if a player is alive
             self.state.enemy_fire_lasers(&self.config);
             self.state.remove_shields();
 
             // See if lasers have gone off-screen.
             self.state.laser_miss_check();
 
-            // Only check and update the UFO if the player is alive
+            // Only check and update the UFO if a player is alive
             self.state.laser_ufo_movement_animation();
             self.state.laser_ufo_collision();
-        } else if self.state.ship.death_counter.is_none() {
-            self.state.flash_display_lives();
-            self.state.ufo.reset_mothership();
         }
 
-        // Player's laser continues moving during death.
-        if self.state.ship_laser.is_some() {
-            let laser_speed = self.state.ship_laser.as_ref().map(|l| l.speed).unwrap();
+        // Player's lasers continue moving during death.
+        self.state.ship_laser_movement(&self.config);
+        self.state.enemy_animation();
+        self.state.enemy_laser_movement(&self.config);
+        self.state.laser_player_collision();
+        self.state.player_toggle_death();
+    }
 
-            // Move the laser 1px at a time (for collisions) within a frame up to its speed.
-            for _ in 0..laser_speed {
-                if let Some(laser) = &mut self.state.ship_laser {
-                    laser.y -= 1;
-                } else {
-                    break;
-                }
-                if self.state.ship_laser.is_some() {
-                    let laser = self.state.ship_laser.as_ref().map(|l| l.rect()).unwrap();
-                    if self.state.laser_shield_check(&laser) {
-                        self.state.ship_laser = None;
-                        break;
-                    }
-                }
-                self.state.laser_enemy_collisions();
-            }
+    /// In cooperative games, one frame for the ship of the player who is swapped in; ``update_frame`` already moved the world.
+    fn update_other_ship(&mut self, buttons: Input) {
+        if self.state.ship.alive {
+            self.move_ship(buttons, AnalogInput::default());
+            self.state.laser_miss_check();
+            self.state.laser_ufo_collision();
+        } else if self.state.ship.death_counter.is_none() && self.state.lives >= 0 {
+            self.state.flash_display_lives();
         }
-        self.state.enemy_animation();
-        self.state.enemy_laser_movement();
+        self.state.ship_laser_movement(&self.config);
         self.state.laser_player_collision();
         self.state.player_toggle_death();
     }
+}
+
+impl toybox_core::State for State
+where
+    State: Clone,
+{
+    fn lives(&self) -> i32 {
+        self.state.team_lives()
+    }
+    fn level(&self) -> i32 {
+        self.state.level
+    }
+    /// In two-player games, this is the players' combined score.
+    fn score(&self) -> i32 {
+        self.state
+            .player_stats()
+            .iter()
+            .map(|&(score, _)| score)
+            .sum()
+    }
+    fn update_mut(&mut self, buttons: Input) {
+        self.update_mut_analog(buttons, AnalogInput::default());
+    }
+    fn update_mut_analog(&mut self, buttons: Input, analog: AnalogInput) {
+        self.update_players(&[buttons], analog);
+    }
+    fn update_mut_players(&mut self, inputs: &[Input]) {
+        self.update_players(inputs, AnalogInput::default());
+    }
 
     fn draw(&self) -> Vec<Drawable> {
         let mut output = Vec::new();
@@ -1042,14 +1713,15 @@ where
                 ));
             }
         } else {
+            let stats = self.state.player_stats();
             output.extend(draw_score(
-                self.state.score % 10000,
+                stats[0].0 % 10000,
                 screen::SCORE_LEFT_X_POS,
                 screen::SCORE_Y_POS,
                 FontChoice::LEFT,
             ));
             output.extend(draw_score(
-                0,
+                stats.get(1).map(|&(score, _)| score % 10000).unwrap_or(0),
                 screen::SCORE_RIGHT_X_POS,
                 screen::SCORE_Y_POS,
                 FontChoice::RIGHT,
@@ -1069,9 +1741,22 @@ where
                 player_sprite.clone(),
             ));
         }
+        // In cooperative games, the other ship is on screen too.
+        let partner = match (self.config.two_player, &self.state.second_player) {
+            (Some(TwoPlayerMode::Cooperative), Some(other)) => Some(other),
+            _ => None,
+        };
+        if let Some(other) = partner {
+            if let Some(sprite) = get_player_sprite(&other.ship, other.life_display_timer) {
+                output.push(Drawable::sprite(other.ship.x, other.ship.y, sprite));
+            }
+        }
 
         // In between lives.
-        if !self.state.ship.alive && self.state.ship.death_counter.is_none() {
+        if !self.state.ship.alive
+            && self.state.ship.death_counter.is_none()
+            && self.state.lives >= 0
+        {
             output.push(Drawable::sprite(
                 screen::LIVES_DISPLAY_POSITION.0,
                 screen::LIVES_DISPLAY_POSITION.1,
@@ -1092,7 +1777,13 @@ where
             ));
         }
 
-        if let Some(ref laser) = self.state.ship_laser {
+        let partner_lasers = partner.map(|other| other.ship_lasers.as_slice());
+        for laser in self
+            .state
+            .ship_lasers()
+            .iter()
+            .chain(partner_lasers.unwrap_or(&[]))
+        {
             if laser.is_visible() {
                 output.push(Drawable::rect(
                     laser.color,
@@ -1123,20 +1814,86 @@ where
         serde_json::to_string(&self.state).expect("Should be no JSON Serialization Errors.")
     }
 
-    fn query_json(&self, query: &str, _args: &serde_json::Value) -> Result<String, QueryError> {
+    fn query_json(&self, query: &str, args: &serde_json::Value) -> Result<String, QueryError> {
         if let Ok(parsed) = JSONQuery::parse(query) {
             if let Ok(Some(found)) = parsed.execute(&self) {
                 return Ok(serde_json::to_string(&found)?);
             }
         }
 
-        let _config = &self.config;
         let state = &self.state;
         Ok(match query {
             "ship_xy" => serde_json::to_string(&(state.ship.x, state.ship.y))?,
             "ship_x" => serde_json::to_string(&state.ship.x)?,
             "shield_count" => serde_json::to_string(&state.shields.len())?,
             "shields" => serde_json::to_string(&state.shields)?,
+            "enemies" => serde_json::to_string(&state.enemy_summaries())?,
+            "enemy_ids" => serde_json::to_string(
+                &state
+                    .enemies
+                    .iter()
+                    .filter(|e| e.alive)
+                    .map(|e| e.id)
+                    .collect::<Vec<_>>(),
+            )?,
+            "enemy_by_id" => {
+                if let Some(enemy) = args.as_u64().and_then(|i| state.enemies.get(i as usize)) {
+                    serde_json::to_string(&(enemy.x, enemy.y, enemy.alive))?
+                } else {
+                    Err(QueryError::BadInputArg)?
+                }
+            }
+            "shooter_ids" => serde_json::to_string(&state.active_weapon_enemy_ids())?,
+            "closest_enemy_id" => serde_json::to_string(&state.closest_enemy_id())?,
+            "enemy_lasers" => {
+                serde_json::to_string(&StateCore::laser_summaries(&state.enemy_lasers))?
+            }
+            "laser_impacts" => serde_json::to_string(&state.laser_impacts())?,
+            "ship_lasers" => {
+                serde_json::to_string(&StateCore::laser_summaries(state.ship_lasers()))?
+            }
+            "ship_laser" => serde_json::to_string(&state.ship_lasers().first())?,
+            "ufo" => {
+                let ufo = &state.ufo;
+                serde_json::to_string(&serde_json::json!({
+                    "x": ufo.x,
+                    "y": ufo.y,
+                    "visible": ufo.appearance_counter.is_none(),
+                    "appearance_counter": ufo.appearance_counter,
+                    "dying": ufo.death_counter.is_some(),
+                }))?
+            }
+            "formation_bounds" => serde_json::to_string(
+                &state
+                    .formation_bounds()
+                    .map(|r| serde_json::json!({"x": r.x, "y": r.y, "w": r.w, "h": r.h})),
+            )?,
+            "time_to_ground" => serde_json::to_string(&state.time_to_ground(&self.config))?,
+            "current_player" => serde_json::to_string(&state.current_player.unwrap_or(0))?,
+            "player_scores" => serde_json::to_string(
+                &state
+                    .player_stats()
+                    .iter()
+                    .map(|&(score, _)| score)
+                    .collect::<Vec<_>>(),
+            )?,
+            "player_lives" => serde_json::to_string(
+                &state
+                    .player_stats()
+                    .iter()
+                    .map(|&(_, lives)| lives)
+                    .collect::<Vec<_>>(),
+            )?,
+            "ships_xy" => {
+                let mut ships = vec![(state.ship.x, state.ship.y)];
+                if let Some(other) = &state.second_player {
+                    ships.push((other.ship.x, other.ship.y));
+                    if state.current_player == Some(1) {
+                        ships.reverse();
+                    }
+                }
+                serde_json::to_string(&ships)?
+            }
             _ => Err(QueryError::NoSuchQuery)?,
         })
     }
@@ -1153,4 +1910,741 @@ mod tests {
         assert_eq!(super::screen::SHIELD_SIZE.0, sprite.width());
         assert_eq!(super::screen::SHIELD_SIZE.1, sprite.height());
     }
+
+    #[test]
+    pub fn test_analog_ship_movement() {
+        use toybox_core::{AnalogInput, Input, State as _};
+        let mut game = super::SpaceInvaders::default();
+        let mut state = super::State {
+            state: super::StateCore::new(&mut game),
+            config: game.clone(),
+        };
+        // Wait for the ship to appear.
+        while !state.state.ship.alive {
+            state.update_mut(Input::default());
+        }
+        let start = state.state.ship.x;
+
+        // A quarter of full speed is 0.75 pixels a frame; the ship moves in whole pixels.
+        let analog = AnalogInput::new(0.25, 0.0);
+        let positions: Vec<i32> = (0..4)
+            .map(|_| {
+                state.update_mut_analog(Input::default(), analog);
+                state.state.ship.x - start
+            })
+            .collect();
+        assert_eq!(vec![0, 1, 2, 3], positions);
+
+        // Buttons win over the stick.
+        let left = Input {
+            left: true,
+            ..Default::default()
+        };
+        state.update_mut_analog(left, analog);
+        assert_eq!(start, state.state.ship.x);
+        assert_eq!(Some(-3), state.state.ship.last_move);
+    }
+
+    #[test]
+    pub fn test_firing_ai_validate() {
+        use crate::firing_ai::{FiringAI, ScriptedShot};
+        let mut game = super::SpaceInvaders::default();
+        assert!(game.validate().is_ok());
+        game.enemy_protocol = FiringAI::Burst {
+            shots: 0,
+            gap: 5,
+            cooldown: 100,
+        };
+        assert!(game.validate().is_err());
+        game.enemy_protocol = FiringAI::Scripted { schedule: vec![] };
+        assert!(game.validate().is_err());
+        game.enemy_protocol = FiringAI::Scripted {
+            schedule: vec![ScriptedShot { wait: 0, column: 1 }],
+        };
+        assert!(game.validate().is_err());
+    }
+
+    #[test]
+    pub fn test_firing_ai_burst_delays() {
+        use super::FiringAI;
+        let burst = FiringAI::Burst {
+            shots: 3,
+            gap: 5,
+            cooldown: 100,
+        };
+        let delays: Vec<i32> = (1..=6).map(|n| burst.next_shot_delay(n)).collect();
+        assert_eq!(vec![5, 5, 100, 5, 5, 100], delays);
+        assert_eq!(3, burst.max_lasers());
+        assert_eq!(50, FiringAI::TargetPlayer.next_shot_delay(1));
+    }
+
+    #[test]
+    pub fn test_default_formation() {
+        use toybox_core::Simulation;
+        let mut game = super::SpaceInvaders::default();
+        assert!(game.validate().is_ok());
+        let state = super::StateCore::new(&mut game);
+        assert_eq!(36, state.enemies.len());
+        let points: Vec<i32> = state.enemies.iter().step_by(6).map(|e| e.points).collect();
+        assert_eq!(super::screen::ENEMY_POINTS.to_vec(), points);
+        assert_eq!(super::screen::ENEMY_START_POS.1 + 2, state.enemies[0].y);
+        // The wave is only a default; old configs still load.
+        assert!(game.from_json(&game.to_json()).is_ok());
+    }
+
+    #[test]
+    pub fn test_waves() {
+        use super::Wave;
+        let wave = |formation: &[&str], start_y: i32, march_period: i32| Wave {
+            formation: Some(formation.iter().map(|row| row.to_string()).collect()),
+            start_y,
+            march_period,
+            march_delta: (4, 12),
+        };
+        let mut game = super::SpaceInvaders {
+            waves: Some(vec![
+                wave(&["3.3", " 5 "], 40, 20),
+                wave(&["1111111", "2222222"], 60, 10),
+            ]),
+            ..Default::default()
+        };
+        assert!(game.validate().is_ok());
+        assert_eq!(40, game.wave(1).start_y);
+        assert_eq!(60, game.wave(2).start_y);
+        // The last wave repeats.
+        assert_eq!(60, game.wave(7).start_y);
+
+        let mut state = super::StateCore::new(&mut game);
+        let layout: Vec<(i32, i32, i32, Option<i32>)> = state
+            .enemies
+            .iter()
+            .map(|e| (e.row, e.col, e.y, e.kind))
+            .collect();
+        assert_eq!(
+            vec![
+                (0, 0, 40, Some(2)),
+                (0, 2, 40, Some(2)),
+                (1, 1, 58, Some(4))
+            ],
+            layout
+        );
+        assert_eq!(20, state.enemies[0].points);
+        assert_eq!(20, state.enemies_movement.move_counter);
+
+        // Clearing the wave moves on to the next one.
+        for e in state.enemies.iter_mut() {
+            e.alive = false;
+        }
+        state.level += 1;
+        state.reset_board(&game);
+        assert_eq!(14, state.enemies.len());
+        assert_eq!(60, state.enemies[0].y);
+        assert_eq!(10, state.enemies_movement.move_counter);
+    }
+
+    #[test]
+    pub fn test_wave_validate() {
+        use super::Wave;
+        let mut game = super::SpaceInvaders {
+            formation: Some(vec!["12x".to_string()]),
+            ..Default::default()
+        };
+        assert!(game.validate().is_err());
+        game.formation = Some(vec!["...".to_string()]);
+        assert!(game.validate().is_err());
+        // Too wide to march.
+        game.formation = Some(vec!["12345612".to_string()]);
+        assert!(game.validate().is_err());
+        game.formation = Some(vec!["1234561".to_string()]);
+        assert!(game.validate().is_ok());
+        game.waves = Some(vec![]);
+        assert!(game.validate().is_err());
+        game.waves = Some(vec![Wave {
+            formation: None,
+            start_y: 30,
+            march_period: 0,
+            march_delta: (2, 10),
+        }]);
+        assert!(game.validate().is_err());
+
+        // Six rows are 100 pixels deep, and may not start inside the shields at y=157...
+        let deep = |start_y: i32| Wave {
+            formation: Some(vec!["1".to_string(); 6]),
+            start_y,
+            march_period: 10,
+            march_delta: (2, 10),
+        };
+        game.waves = Some(vec![deep(57)]);
+        assert!(game.validate().is_ok());
+        game.waves = Some(vec![deep(30), deep(58)]);
+        assert!(game.validate().is_err());
+        // ...or, without shields, the ground at y=195.
+        game.shields.clear();
+        assert!(game.validate().is_ok());
+        game.waves = Some(vec![deep(96)]);
+        assert!(game.validate().is_err());
+    }
+
+    #[test]
+    pub fn test_weapon_shots_and_cooldown() {
+        use super::Weapon;
+        let mut game = super::SpaceInvaders {
+            weapon: Some(Weapon {
+                max_shots: 2,
+                speed: 5,
+                size: (3, 6),
+                cooldown: 4,
+                spread: None,
+            }),
+            ..Default::default()
+        };
+        assert!(game.validate().is_ok());
+        let mut state = super::StateCore::new(&mut game);
+        // Hold fire for a few frames: one shot, then a wait, then another, then no room.
+        let counts: Vec<usize> = (0..12)
+            .map(|_| {
+                state.fire_ship_lasers(&game, true);
+                state.ship_lasers().len()
+            })
+            .collect();
+        assert_eq!(vec![1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2], counts);
+        let laser = &state.ship_lasers()[0];
+        assert_eq!((3, 6, 5), (laser.w, laser.h, laser.speed));
+
+        // The default weapon is the Atari's single shot.
+        let mut game = super::SpaceInvaders::default();
+        let mut state = super::StateCore::new(&mut game);
+        for _ in 0..3 {
+            state.fire_ship_lasers(&game, true);
+        }
+        assert_eq!(1, state.ship_lasers().len());
+    }
+
+    #[test]
+    pub fn test_weapon_spread_erodes_shields() {
+        use super::{Spread, Weapon};
+        let mut game = super::SpaceInvaders {
+            weapon: Some(Weapon {
+                max_shots: 3,
+                speed: 3,
+                size: (2, 11),
+                cooldown: 0,
+                spread: Some(Spread { shots: 3, drift: 1 }),
+            }),
+            ..Default::default()
+        };
+        assert!(game.validate().is_ok());
+        let mut state = super::StateCore::new(&mut game);
+        // Under the middle of the first shield.
+        state.ship.x = super::screen::SHIELD1_POS.0;
+        state.fire_ship_lasers(&game, true);
+        let drifts: Vec<Option<i32>> = state.ship_lasers().iter().map(|l| l.dx).collect();
+        assert_eq!(vec![Some(-1), None, Some(1)], drifts);
+
+        let solid = |state: &super::StateCore| -> usize {
+            state.shields[0]
+                .data
+                .iter()
+                .flatten()
+                .filter(|px| px.is_visible())
+                .count()
+        };
+        let before = solid(&state);
+        for _ in 0..20 {
+            state.ship_laser_movement(&game);
+        }
+        // Every laser of the volley took a bite out of the shield and was used up.
+        assert!(state.ship_lasers().is_empty());
+        assert!(before - solid(&state) >= 3 * 2);
+
+        game.weapon.as_mut().unwrap().max_shots = 2;
+        assert!(game.validate().is_err());
+        // Two shots one pixel apart would both fly straight up.
+        game.weapon.as_mut().unwrap().spread = Some(Spread { shots: 2, drift: 1 });
+        assert!(game.validate().is_err());
+        game.weapon.as_mut().unwrap().spread = Some(Spread { shots: 2, drift: 2 });
+        assert!(game.validate().is_ok());
+    }
+
+    #[test]
+    pub fn test_old_ship_laser_loads() {
+        use toybox_core::Simulation;
+        let mut game = super::SpaceInvaders::default();
+        let mut state = super::StateCore::new(&mut game);
+        state.fire_ship_lasers(&game, true);
+        let laser = state.ship_lasers()[0].clone();
+
+        // States saved before multiple shots had a single ``ship_laser``.
+        let mut json = serde_json::to_value(&state).unwrap();
+        let old = json.as_object_mut().unwrap();
+        old.remove("ship_lasers");
+        old.insert(
+            "ship_laser".to_string(),
+            serde_json::to_value(&laser).unwrap(),
+        );
+        let loaded = game.new_state_from_json(&json.to_string()).unwrap();
+        let loaded: super::StateCore = serde_json::from_str(&loaded.to_json()).unwrap();
+        assert_eq!(vec![laser], loaded.ship_lasers().to_vec());
+        assert_eq!(None, loaded.ship_laser);
+    }
+
+    #[test]
+    pub fn test_laser_hits_near_grid_cell_boundary() {
+        use super::{get_invader_sprite, screen, Laser};
+        use toybox_core::Direction;
+        let mut game = super::SpaceInvaders::default();
+        let mut state = super::StateCore::new(&mut game);
+        for e in state.enemies.iter_mut().skip(1) {
+            e.alive = false;
+        }
+        // The grid cells are one enemy wide; put the enemy's rightmost visible column on the first pixel of a cell.
+        let orientation = state.enemies_movement.visual_orientation;
+        let sprite = get_invader_sprite(&state.enemies[0], orientation);
+        let last_column = (0..screen::ENEMY_SIZE.0)
+            .filter(|&x| sprite.data.iter().any(|row| row[x as usize].is_visible()))
+            .max()
+            .unwrap();
+        let boundary = 3 * screen::ENEMY_SIZE.0;
+        state.enemies[0].x = boundary - last_column;
+        let y = state.enemies[0].y + 1;
+
+        // One pixel right of the sprite: same cell, no visible pixel, so the laser flies on.
+        let mut miss = state.clone();
+        miss.ship_lasers_mut()
+            .push(Laser::new(boundary + 1, y, Direction::Up));
+        miss.ship_laser_movement(&game);
+        assert_eq!(1, miss.ship_lasers().len());
+        assert_eq!(None, miss.enemies[0].death_counter);
+
+        // On the boundary column itself, the enemy is hit from the neighbouring cell.
+        state
+            .ship_lasers_mut()
+            .push(Laser::new(boundary, y, Direction::Up));
+        state.ship_laser_movement(&game);
+        assert!(state.ship_lasers().is_empty());
+        assert_eq!(Some(screen::DEATH_TIME), state.enemies[0].death_counter);
+    }
+
+    #[test]
+    pub fn test_shield_config() {
+        use crate::types::{Erosion, ShieldShape};
+        let mut game = super::SpaceInvaders {
+            shields: vec![(100, 150), (200, 150)],
+            shield_shape: Some(ShieldShape::Ascii(vec![
+                "XXXXXX".to_string(),
+                "XXXXXX".to_string(),
+                "XX..XX".to_string(),
+            ])),
+            ..Default::default()
+        };
+        assert!(game.validate().is_ok());
+        let state = super::StateCore::new(&mut game);
+        assert_eq!(2, state.shields.len());
+        assert_eq!(
+            (6, 3),
+            (state.shields[1].width(), state.shields[1].height())
+        );
+        assert_eq!((200, 150), state.shields[1].position());
+
+        // Shields in the ship lane or off screen are rejected, as are ragged or missing shapes.
+        game.shields = vec![(100, 183)];
+        assert!(game.validate().is_err());
+        game.shields = vec![(316, 150)];
+        assert!(game.validate().is_err());
+        game.shields = vec![(100, 150)];
+        game.shield_shape = Some(ShieldShape::Ascii(vec!["XX".to_string(), "X".to_string()]));
+        assert!(game.validate().is_err());
+        game.shield_shape = Some(ShieldShape::Png("no/such/shield.png".to_string()));
+        assert!(game.validate().is_err());
+        game.shield_shape = None;
+        game.shield_erosion = Some(Erosion::Block {
+            width: 0,
+            height: 2,
+        });
+        assert!(game.validate().is_err());
+    }
+
+    #[test]
+    pub fn test_png_shield_is_read_once() {
+        use crate::types::ShieldShape;
+        let path = std::env::temp_dir().join(format!("si_shield_{}.png", std::process::id()));
+        std::fs::copy("../tb_amidar/src/resources/amidar/player_l1.png", &path).unwrap();
+        let mut game = super::SpaceInvaders {
+            shield_shape: Some(ShieldShape::Png(path.to_string_lossy().into_owned())),
+            ..Default::default()
+        };
+        game.prepare().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // New games and levels reuse the decoded shield instead of the missing file.
+        let mut state = super::State::try_new(&mut game).unwrap();
+        state.state.level += 1;
+        state.state.reset_board(&state.config);
+        assert_eq!(3, state.state.shields.len());
+        assert_eq!(
+            (7, 7),
+            (
+                state.state.shields[0].width(),
+                state.state.shields[0].height()
+            )
+        );
+
+        // A config that was never prepared is validated before its game starts.
+        let mut unprepared = super::SpaceInvaders {
+            shield_shape: game.shield_shape.clone(),
+            ..Default::default()
+        };
+        assert!(super::State::try_new(&mut unprepared).is_err());
+    }
+
+    #[test]
+    pub fn test_shield_erosion() {
+        use super::destruction::destructive_collide;
+        use crate::types::Erosion;
+        use toybox_core::collision::Rect;
+        let solid = |sprite: &Vec<Vec<super::Color>>| -> usize {
+            sprite.iter().flatten().filter(|px| px.is_visible()).count()
+        };
+        let shield = super::SHIELD_SPRITE.clone();
+        // A laser covering a 2x2 solid part of the shield.
+        let laser = Rect::new(5, 5, 1, 1);
+        let bites: Vec<usize> = [
+            Erosion::Block {
+                width: 2,
+                height: 2,
+            },
+            Erosion::Block {
+                width: 4,
+                height: 4,
+            },
+            Erosion::Radius { radius: 1 },
+            Erosion::Indestructible,
+        ]
+        .iter()
+        .map(|erosion| {
+            let mut data = shield.data.clone();
+            assert!(destructive_collide(&laser, 0, 0, &mut data, erosion));
+            solid(&shield.data) - solid(&data)
+        })
+        .collect();
+        assert_eq!(vec![16, 16, 12, 0], bites);
+    }
+
+    #[test]
+    pub fn test_descent_and_time_to_ground() {
+        use toybox_core::State as _;
+        let mut game = super::SpaceInvaders {
+            descent_step: Some(20),
+            speedups: Some(vec![(1, 10)]),
+            ..Default::default()
+        };
+        assert!(game.validate().is_ok());
+        assert_eq!(20, game.wave(1).march_delta.1);
+        let state = super::State {
+            state: super::StateCore::new(&mut game),
+            config: game.clone(),
+        };
+        let bounds: serde_json::Value = serde_json::from_str(
+            &state
+                .query_json("formation_bounds", &serde_json::Value::Null)
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::json!({"x": 44, "y": 33, "w": 176, "h": 100}),
+            bounds
+        );
+
+        // The prediction matches marching for real.
+        let march = |mut marching: super::StateCore| {
+            let mut frames = 0;
+            while !marching.has_lost() {
+                marching.enemy_shift(&game);
+                frames += 1;
+            }
+            frames
+        };
+        let predicted = state.state.time_to_ground(&game).unwrap();
+        assert_eq!(march(state.state.clone()), predicted);
+
+        // One kill is enough to speed up, so the ground comes sooner.
+        let mut faster = state.state.clone();
+        faster.enemies[0].alive = false;
+        assert_eq!(march(faster.clone()), faster.time_to_ground(&game).unwrap());
+        assert!(faster.time_to_ground(&game).unwrap() < predicted);
+
+        // Part way through a march to the left, too.
+        faster.enemies_movement.move_dir = super::Direction::Left;
+        faster.enemies_movement.move_counter = 3;
+        for e in faster.enemies.iter_mut() {
+            e.x += 30;
+        }
+        assert_eq!(march(faster.clone()), faster.time_to_ground(&game).unwrap());
+
+        // Invaders that never step down never arrive.
+        game.descent_step = Some(0);
+        assert_eq!(None, state.state.time_to_ground(&game));
+
+        game.speedups = Some(vec![(10, 4), (5, 8)]);
+        assert!(game.validate().is_err());
+    }
+
+    #[test]
+    pub fn test_ground_rule() {
+        use crate::types::GroundRule;
+        use toybox_core::{Input, State as _};
+        let land = |state: &mut super::State| {
+            for e in state.state.enemies.iter_mut() {
+                e.y += 150;
+            }
+            state.update_mut(Input::default());
+        };
+        let mut game = super::SpaceInvaders::default();
+        let mut state = super::State {
+            state: super::StateCore::new(&mut game),
+            config: game.clone(),
+        };
+        land(&mut state);
+        assert_eq!(-1, state.lives());
+
+        game.ground_rule = Some(GroundRule::LoseLife);
+        let mut state = super::State {
+            state: super::StateCore::new(&mut game),
+            config: game.clone(),
+        };
+        land(&mut state);
+        assert_eq!(2, state.lives());
+        // The wave starts over from the top.
+        assert_eq!(
+            super::screen::ENEMY_START_POS.1 + 2,
+            state.state.enemies[0].y
+        );
+        assert_eq!(1, state.level());
+    }
+
+    #[test]
+    pub fn test_queries() {
+        use toybox_core::{Input, State as _};
+        let mut game = super::SpaceInvaders::default();
+        let mut state = super::State {
+            state: super::StateCore::new(&mut game),
+            config: game.clone(),
+        };
+        let query = |state: &super::State, q: &str| -> serde_json::Value {
+            serde_json::from_str(&state.query_json(q, &serde_json::Value::Null).unwrap()).unwrap()
+        };
+        assert_eq!(36, query(&state, "enemies").as_array().unwrap().len());
+        assert_eq!(
+            serde_json::json!({"id": 0, "row": 0, "col": 0, "x": 44, "y": 33, "points": 30, "dying": false}),
+            query(&state, "enemies")[0]
+        );
+        assert_eq!(
+            serde_json::json!([30, 31, 32, 33, 34, 35]),
+            query(&state, "shooter_ids")
+        );
+        assert_eq!(serde_json::json!(31), query(&state, "closest_enemy_id"));
+        assert_eq!(serde_json::Value::Null, query(&state, "ship_laser"));
+        assert_eq!(serde_json::json!(false), query(&state, "ufo")["visible"]);
+        assert!(state
+            .query_json("enemy_by_id", &serde_json::json!(99))
+            .is_err());
+
+        state.state.enemies[0].alive = false;
+        assert_eq!(35, query(&state, "enemy_ids").as_array().unwrap().len());
+
+        // Play until the enemies fire, then check the laser reports line up with the lasers.
+        while state.state.enemy_lasers.is_empty() {
+            state.update_mut(Input {
+                button1: true,
+                ..Default::default()
+            });
+        }
+        let laser = state.state.enemy_lasers[0].clone();
+        let reported = &query(&state, "enemy_lasers")[0];
+        assert_eq!(serde_json::json!(laser.x), reported["x"]);
+        assert_eq!(serde_json::json!(3), reported["vy"]);
+        assert_eq!(
+            serde_json::to_value(&state.state.ship_lasers()[0]).unwrap(),
+            query(&state, "ship_laser")
+        );
+        let impact = &query(&state, "laser_impacts")[0];
+        assert_eq!(serde_json::json!(laser.x), impact["x"]);
+        let frames = impact["frames"].as_i64().unwrap() as i32;
+        assert!(laser.y + laser.h + frames * 3 >= state.state.ship.y);
+        assert!(laser.y + laser.h + (frames - 1) * 3 < state.state.ship.y);
+    }
+
+    /// A new two-player game, played until both ships are on screen (or the first one is, for alternating turns).
+    fn two_player_game(mode: super::TwoPlayerMode) -> super::State {
+        use toybox_core::{Input, Simulation, State as _};
+        let mut game = super::SpaceInvaders {
+            two_player: Some(mode),
+            ..Default::default()
+        };
+        assert_eq!(2, game.player_count());
+        let mut state = super::State {
+            state: super::StateCore::new(&mut game),
+            config: game.clone(),
+        };
+        while !state.state.ship.alive {
+            state.update_mut_players(&[Input::default(), Input::default()]);
+        }
+        state
+    }
+
+    #[test]
+    pub fn test_two_player_cooperative() {
+        use toybox_core::{Input, State as _};
+        let mut state = two_player_game(super::TwoPlayerMode::Cooperative);
+        let query = |state: &super::State, q: &str| -> String {
+            state.query_json(q, &serde_json::Value::Null).unwrap()
+        };
+        let start = query(&state, "ships_xy");
+        assert_eq!("[[68,185],[230,185]]", start);
+
+        // Each input moves its own ship.
+        let left = Input {
+            left: true,
+            ..Default::default()
+        };
+        let right = Input {
+            right: true,
+            ..Default::default()
+        };
+        state.update_mut_players(&[right, left]);
+        assert_eq!("[[71,185],[227,185]]", query(&state, "ships_xy"));
+        assert_eq!("0", query(&state, "current_player"));
+
+        // Player two's shot, from between the shields, is player two's to score.
+        for _ in 0..20 {
+            state.update_mut_players(&[Input::default(), left]);
+        }
+        let fire = Input {
+            button1: true,
+            ..Default::default()
+        };
+        state.update_mut_players(&[Input::default(), fire]);
+        assert!(state.state.ship_lasers().is_empty());
+        assert_eq!(
+            1,
+            state
+                .state
+                .second_player
+                .as_ref()
+                .unwrap()
+                .ship_lasers
+                .len()
+        );
+        for _ in 0..100 {
+            state.update_mut_players(&[Input::default(), Input::default()]);
+        }
+        let scores: Vec<i32> = serde_json::from_str(&query(&state, "player_scores")).unwrap();
+        assert_eq!(0, scores[0]);
+        assert!(scores[1] > 0);
+        assert_eq!(scores[1], state.score());
+        assert_eq!("[3,3]", query(&state, "player_lives"));
+    }
+
+    #[test]
+    pub fn test_two_player_alternating() {
+        use toybox_core::{Input, State as _};
+        let mut state = two_player_game(super::TwoPlayerMode::Alternating);
+        assert_eq!(Some(0), state.state.current_player);
+        // Sit still under the invaders' fire until the first player loses a life.
+        let mut frames = 0;
+        while state.state.current_player == Some(0) {
+            state.update_mut_players(&[Input::default(), Input::default()]);
+            frames += 1;
+            assert!(frames < 10_000, "player one should lose a life");
+        }
+        assert_eq!(vec![(0, 2), (0, 3)], state.state.player_stats());
+        assert_eq!(3, state.lives());
+        // Now the second input drives the only ship on screen.
+        while !state.state.ship.alive {
+            state.update_mut_players(&[Input::default(), Input::default()]);
+        }
+        let x = state.state.ship.x;
+        let left = Input {
+            left: true,
+            ..Default::default()
+        };
+        state.update_mut_players(&[Input::default(), left]);
+        assert_eq!(x - 3, state.state.ship.x);
+        state.update_mut_players(&[left, Input::default()]);
+        assert_eq!(x - 3, state.state.ship.x);
+    }
+
+    /// Run a fresh game with the given protocol and collect the columns of the first few shooters.
+    fn shooter_columns(protocol: super::FiringAI, shots: u32) -> Vec<i32> {
+        use toybox_core::{Input, State as _};
+        let mut game = super::SpaceInvaders {
+            enemy_protocol: protocol,
+            ..Default::default()
+        };
+        let mut state = super::State {
+            state: super::StateCore::new(&mut game),
+            config: game.clone(),
+        };
+        let mut columns = Vec::new();
+        let mut seen = 0;
+        for _ in 0..10_000 {
+            if columns.len() as u32 >= shots {
+                break;
+            }
+            state.update_mut(Input::default());
+            let fired = state.state.shots_fired.unwrap_or(0);
+            if fired > seen {
+                seen = fired;
+                let id = state.state.last_shooter.unwrap() as usize;
+                columns.push(state.state.enemies[id].col);
+            }
+        }
+        columns
+    }
+
+    #[test]
+    pub fn test_firing_ai_round_robin() {
+        let columns = shooter_columns(super::FiringAI::RoundRobin, 8);
+        assert_eq!(vec![0, 1, 2, 3, 4, 5, 0, 1], columns);
+    }
+
+    #[test]
+    pub fn test_firing_ai_lead_target() {
+        use crate::firing_ai::{enemy_fire_lasers, FiringAI};
+        let mut game = super::SpaceInvaders {
+            enemy_protocol: FiringAI::LeadTarget,
+            jitter: 0.0,
+            ..Default::default()
+        };
+        let mut state = super::StateCore::new(&mut game);
+        // Park the ship under the middle of column 2.
+        let under = state.enemies.iter().find(|e| e.col == 2).unwrap().rect();
+        state.ship.x = under.center_x() - state.ship.w / 2;
+        let mut shooter_column = |last_move: Option<i32>| {
+            state.ship.last_move = last_move;
+            let id = enemy_fire_lasers(&mut state, &game);
+            state.enemies[id as usize].col
+        };
+        assert_eq!(2, shooter_column(None));
+        // The laser takes a while to fall, so a moving ship is shot at from the column it is heading for.
+        assert_eq!(3, shooter_column(Some(2)));
+        assert_eq!(1, shooter_column(Some(-2)));
+    }
+
+    #[test]
+    pub fn test_firing_ai_scripted() {
+        use crate::firing_ai::{FiringAI, ScriptedShot};
+        let schedule = vec![
+            ScriptedShot {
+                wait: 10,
+                column: 4,
+            },
+            ScriptedShot {
+                wait: 20,
+                column: 2,
+            },
+        ];
+        let columns = shooter_columns(FiringAI::Scripted { schedule }, 4);
+        assert_eq!(vec![4, 2, 4, 2], columns);
+    }
 }
@@ -26,6 +26,10 @@ pub struct Player {
     pub death_counter: Option<i32>,
     /// This is an animation flag; it is set based on the value of death_counter.
     pub death_hit_1: bool,
+    /// Analog movement too small to show yet, in 1/256ths of a pixel; it carries over to the next frame.
+    pub sub_x: Option<i32>,
+    /// How many pixels the ship moved last frame; negative is left. Some firing AIs use this to lead their shots.
+    pub last_move: Option<i32>,
 }
 
 /// Each shot in SpaceInvaders by the player or the enemy is a Laser object.
@@ -47,6 +51,32 @@ pub struct Laser {
     pub speed: i32,
     /// What color is this laser "bullet"?
     pub color: Color,
+    /// How many pixels per frame the laser drifts sideways; spread shots fan out this way.
+    pub dx: Option<i32>,
+}
+
+/// The player's weapon: how many shots, how fast, how big, and how often.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Weapon {
+    /// How many player lasers may be on screen at once; the Atari allowed only one.
+    pub max_shots: u32,
+    /// How many pixels per frame the player's lasers travel.
+    pub speed: i32,
+    /// The (width, height) of the player's lasers.
+    pub size: (i32, i32),
+    /// How many frames after firing before the ship can fire again.
+    pub cooldown: i32,
+    /// If present, each press of fire launches a fan of lasers instead of one.
+    pub spread: Option<Spread>,
+}
+
+/// A spread shot fires several lasers at once, fanning out to either side.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Spread {
+    /// How many lasers each volley has.
+    pub shots: u32,
+    /// How many more pixels per frame each laser drifts sideways than its inner neighbor; must be even when ``shots`` is, so the middle pair splits evenly.
+    pub drift: i32,
 }
 
 /// This struct represents both the Mothership and its appearance delay.
@@ -80,6 +110,23 @@ pub struct Enemy {
     pub points: i32,
     /// This is an animation counter; it's presence indicates the enemy is in the process of dying.
     pub death_counter: Option<i32>,
+    /// Which player (0 or 1) shot this enemy; they get the points when it finishes dying.
+    pub shot_by: Option<u32>,
+    /// Which kind of invader is this (0-based)? It picks the sprite; older states use the row.
+    pub kind: Option<i32>,
+}
+
+/// One wave of invaders; the game moves to the next wave when all enemies are destroyed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Wave {
+    /// Rows of the formation, top first: the digits 1 to 6 place that kind of invader, and a space or a dot leaves a gap. If missing, the game's formation is used.
+    pub formation: Option<Vec<String>>,
+    /// The y-coordinate of the top row when the wave begins.
+    pub start_y: i32,
+    /// How many frames the invaders wait between steps, before any speedups.
+    pub march_period: i32,
+    /// How many pixels the invaders move sideways, and down, in each step.
+    pub march_delta: (i32, i32),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -92,6 +139,61 @@ pub struct EnemiesMovementState {
     pub visual_orientation: bool,
 }
 
+/// Where the shape of the shields comes from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum ShieldShape {
+    /// Rows of pixels, top first: 'X' is solid and '.' is empty.
+    Ascii(Vec<String>),
+    /// The path of an 8-bit RGBA png; pixels that are not fully transparent are solid and keep their color.
+    Png(String),
+}
+
+/// What a laser does to the shield pixels it hits.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum Erosion {
+    /// Clear the grid-aligned block of this size around each pixel hit; the Atari took 2x2 bites.
+    Block { width: i32, height: i32 },
+    /// Clear every pixel within this many pixels of each pixel hit.
+    Radius { radius: i32 },
+    /// Shields stop lasers but never wear down.
+    Indestructible,
+}
+
+/// What happens when the invaders reach the ground.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum GroundRule {
+    /// The game is over at once, as on the Atari.
+    EndGame,
+    /// The current player loses a life and the wave starts over from the top.
+    LoseLife,
+}
+
+/// How two players share a game of Space Invaders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum TwoPlayerMode {
+    /// Both ships are on screen at once, fighting the same invaders.
+    Cooperative,
+    /// One ship at a time; the turn passes to the other player whenever a life is lost. The invaders and shields are shared.
+    Alternating,
+}
+
+/// Everything that belongs to the player who is not in the ``StateCore`` ship fields right now.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PlayerSlot {
+    /// This player's ship.
+    pub ship: Player,
+    /// This player's lasers in flight.
+    pub ship_lasers: Vec<Laser>,
+    /// How many frames until this player may fire again.
+    pub ship_cooldown: i32,
+    /// How many points has this player earned?
+    pub score: i32,
+    /// How many lives does this player have left?
+    pub lives: i32,
+    /// This player's animation timer; lives are shown before the ship appears.
+    pub life_display_timer: i32,
+}
+
 /// This struct represents the configuration for Space Invaders; all of these values cannot change from frame-to-frame but require a "new_game" reset to take effect.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SpaceInvaders {
@@ -107,6 +209,34 @@ pub struct SpaceInvaders {
     pub jitter: f64,
     /// This is a list of (x,y) positions that represents where shields are created.
     pub shields: Vec<(i32, i32)>,
+    /// The shape of every shield; if missing, the Atari's arch.
+    pub shield_shape: Option<ShieldShape>,
+    /// How lasers wear down shields; if missing, 2x2 blocks.
+    pub shield_erosion: Option<Erosion>,
+    /// How many pixels the invaders step down at each edge; waves with their own march_delta ignore this.
+    pub descent_step: Option<i32>,
+    /// How the invaders speed up as they die: (enemies killed, frames between steps), in increasing order of kills.
+    pub speedups: Option<Vec<(i32, i32)>>,
+    /// What happens when the invaders reach the ground; if missing, the game ends.
+    pub ground_rule: Option<GroundRule>,
+    /// Rows of the invader formation, top first: the digits 1 to 6 place that kind of invader, and a space or a dot leaves a gap.
+    pub formation: Option<Vec<String>>,
+    /// If present, two players share the game this way; see ``State::update_mut_players``.
+    pub two_player: Option<TwoPlayerMode>,
+    /// The player's weapon; if missing, a single slow laser like the Atari's.
+    pub weapon: Option<Weapon>,
+    /// The waves of each level, in order; the last one repeats. If missing, every level uses the formation, starting a little lower each time.
+    pub waves: Option<Vec<Wave>>,
+    /// The shield decoded by ``SpaceInvaders::prepare``, so a png is read once per config rather than once per level.
+    #[serde(skip)]
+    pub(crate) shield: Option<DecodedShield>,
+}
+
+/// A shield sprite, and the ``shield_shape`` it was decoded from.
+#[derive(Debug, Clone)]
+pub(crate) struct DecodedShield {
+    pub(crate) shape: Option<ShieldShape>,
+    pub(crate) sprite: SpriteData,
 }
 
 /// This struct contains the state of Space Invaders; everything that can change from frame to frame is represented.
@@ -124,8 +254,12 @@ pub struct StateCore {
     pub score: i32,
     /// Ship is a rectangular actor (logically).
     pub ship: Player,
-    /// Emulate the fact that Atari could only have one laser at a time (and it "recharges" faster if you hit the front row...)
+    /// The player's lasers in flight; the default weapon emulates the fact that Atari could only have one laser at a time (and it "recharges" faster if you hit the front row...)
+    pub ship_lasers: Option<Vec<Laser>>,
+    /// The single player laser of states saved before ``ship_lasers``; it is moved into ``ship_lasers`` when such a state is loaded.
     pub ship_laser: Option<Laser>,
+    /// How many frames until the ship may fire again.
+    pub ship_cooldown: Option<i32>,
     /// Shields are destructible, so we need to track their pixels...
     pub shields: Vec<SpriteData>,
     /// Enemies are rectangular actors (logically speaking).
@@ -136,9 +270,18 @@ pub struct StateCore {
     pub enemy_shot_delay: i32,
     /// The enemies can have many lasers fired at once.
     pub enemy_lasers: Vec<Laser>,
+    /// How many shots have the enemies fired this game? Some firing AIs count through a pattern with this.
+    pub shots_fired: Option<u32>,
+    /// Which enemy fired the last shot?
+    pub last_shooter: Option<u32>,
 
     /// Mothership
     pub ufo: Ufo,
+
+    /// In two-player games, the other player; the fields above (ship, lasers, score, lives) belong to ``current_player``.
+    pub second_player: Option<PlayerSlot>,
+    /// Which player (0 or 1) the ship fields above belong to.
+    pub current_player: Option<u32>,
 }
 
 /// The unified state of SpaceInvaders contains both the config (read-only) and the frame state.
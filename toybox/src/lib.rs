@@ -2,6 +2,8 @@ extern crate toybox_core;
 
 pub use toybox_core::graphics;
 pub use toybox_core::random;
+/// AnalogInput is the optional continuous control given alongside Input.
+pub use toybox_core::AnalogInput;
 /// Input represents the buttons pressed given to our games.
 pub use toybox_core::Input;
 pub use toybox_core::Simulation;